@@ -82,6 +82,44 @@ const TreeNodeElement: React.FC<TreeNodeElementProps> = ({ nodeDatum, toggleNode
   );
 };
 
+const label = (node: ASTNode, input: string): string => {
+  switch (node.kind) {
+    case 'Identifier':
+    case 'Constant':
+      return node.name;
+    default:
+      return input.slice(node.start, node.end + 1);
+  }
+};
+
+// Identifiers share an id so every occurrence of a variable toggles together.
+const nodeId = (node: ASTNode): string => {
+  switch (node.kind) {
+    case 'Identifier':
+      return `id-${node.name}`;
+    case 'Constant':
+      return `const-${node.name}`;
+    default:
+      return `${node.kind.toLowerCase()}-${node.start}-${node.end}`;
+  }
+};
+
+// Plain arithmetic shows just its value, everything else also names the operation.
+const operation = (node: ASTNode): string | undefined => {
+  switch (node.kind) {
+    case 'Identifier':
+    case 'Constant':
+    case 'Int':
+    case 'BinaryOp':
+    case 'UnaryOp':
+      return undefined;
+    case 'FunctionCall':
+      return node.name;
+    default:
+      return node.kind;
+  }
+};
+
 const ASTTreeVisualization: React.FC<ASTTreeVisualizationProps> = ({
   ast,
   input,
@@ -107,78 +145,29 @@ const ASTTreeVisualization: React.FC<ASTTreeVisualizationProps> = ({
       };
     }
 
-    if ('BinaryOpNode' in node.type) {
-      const binOp = node.type.BinaryOpNode;
-      const value = R300.fromJson(node.value);
-      const treeNode: TreeNode = {
-        name: `${input.slice(node.start, node.end + 1)}`,
-        nodeId: `binary-${binOp.op}-${binOp.left}-${binOp.right}`,
-        value: value,
-        attributes: {
-          value: value.display()
-        }
-      };
-
-      // Add children
-      const children: TreeNode[] = [];
-      const leftNode = convertASTToTreeData(binOp.left, input);
-      if (leftNode) children.push(leftNode);
-      const rightNode = convertASTToTreeData(binOp.right, input);
-      if (rightNode) children.push(rightNode);
-      if (children.length > 0) {
-        treeNode.children = children;
-      }
-
-      return treeNode;
-    }
-
-    if ('UnaryOpNode' in node.type) {
-      const unaryOp = node.type.UnaryOpNode;
-      const value = R300.fromJson(node.value);
-      const treeNode: TreeNode = {
-        name: `${input.slice(node.start, node.end + 1)}`,
-        nodeId: `unary-${unaryOp.op}-${unaryOp.operand}`,
-        value: value,
-        attributes: {
-          value: value.display()
-        }
-      };
-
-      // Add child
-      const operandNode = convertASTToTreeData(unaryOp.operand, input);
-      if (operandNode) {
-        treeNode.children = [operandNode];
+    const value = R300.fromBlades(node.value);
+    const treeNode: TreeNode = {
+      name: label(node, input),
+      nodeId: nodeId(node),
+      value: value,
+      attributes: {
+        ...(operation(node) && { operation: operation(node) }),
+        value: value.display(),
+        ...(node.kind === 'Equation' && { holds: node.holds ? 'yes' : 'no' })
       }
+    };
 
-      return treeNode;
-    }
-
-    if ('Int' in node.type) {
-      const value = R300.fromJson(node.value);
-      return {
-        name: `${input.slice(node.start, node.end + 1)}`,
-        nodeId: `int-${node.value}`,
-        value: value,
-        attributes: {
-          value: value.display()
-        }
-      };
+    // Add children
+    const children: TreeNode[] = [];
+    for (const child of node.children) {
+      const childNode = convertASTToTreeData(child, input);
+      if (childNode) children.push(childNode);
     }
-    if ('Identifier' in node.type) {
-      const identifier = node.type.Identifier;
-      const value = R300.fromJson(node.value);
-      return {
-        name: identifier.name,
-        nodeId: `id-${identifier.name}`,
-        value: value,
-        attributes: {
-          value: value.display()
-        }
-      };
+    if (children.length > 0) {
+      treeNode.children = children;
     }
 
-
-    return null;
+    return treeNode;
   };
 
   const handleNodeClick = useCallback((nodeDatum: TreeNode) => {
@@ -1,4 +1,5 @@
 import { useEffect, useState } from 'react';
+import { R300 } from 'geo-calc';
 
 import {
   StyledVectorForm,
@@ -25,6 +26,8 @@ type Props = {
 const VectorForm = ({ selectedVector, onSave }: Props) => {
   const [values, setValues] = useState<Values>({ x: 1, y: 1, z: 1 });
   const [errors, setErrors] = useState<Errors>({ x: null, y: null, z: null });
+  const [literal, setLiteral] = useState('');
+  const [literalError, setLiteralError] = useState<string | null>(null);
 
   const handleChange = (e: any, axis: Axes) => {
     e.persist()
@@ -33,6 +36,31 @@ const VectorForm = ({ selectedVector, onSave }: Props) => {
     const errorMessage = isNaN(e.target.value) ? 'Not a valid number' : null;
     setValues((values) => ({ ...values, [axis]: e.target.value }));
     setErrors((errors) => ({ ...errors, [axis]: errorMessage }));
+    // the coordinates no longer come from the literal
+    setLiteral('');
+    setLiteralError(null);
+  };
+
+  // fill the coordinates from a literal such as `2e1 - e_{3}`
+  const handleLiteralChange = (e: any) => {
+    const text: string = e.target.value;
+    setLiteral(text);
+    if (text.trim() === '') {
+      setLiteralError(null);
+      return;
+    }
+    try {
+      const value = R300.fromString(text);
+      if (!value.isVector()) {
+        setLiteralError('Not a vector');
+        return;
+      }
+      setLiteralError(null);
+      setValues({ x: value.get(1), y: value.get(2), z: value.get(3) });
+      setErrors({ x: null, y: null, z: null });
+    } catch (error) {
+      setLiteralError(String(error));
+    }
   };
 
   const handleSubmit = () => {
@@ -50,6 +78,16 @@ const VectorForm = ({ selectedVector, onSave }: Props) => {
 
   return (
     <StyledVectorForm>
+      <FormField>
+        <FieldLabel>v</FieldLabel>
+        <FieldInput
+          name='literal'
+          placeholder='e.g. 2e1 - e_{3}'
+          value={literal}
+          onChange={handleLiteralChange}
+        />
+        {literalError && <FieldError>{literalError}</FieldError>}
+      </FormField>
       {(['x', 'y', 'z'] as Axes[]).map((axis: Axes) => (
         <FormField key={axis}>
           <FieldLabel>{axis}</FieldLabel>
@@ -63,7 +101,7 @@ const VectorForm = ({ selectedVector, onSave }: Props) => {
       ))}
       <FormButton
         type='submit'
-        disabled={Object.values(errors).some(item => item !== null)}
+        disabled={literalError !== null || Object.values(errors).some(item => item !== null)}
         onClick={handleSubmit}
       >
         {selectedVector ? 'Update' : 'Draw'}
@@ -2,8 +2,9 @@ import React, { useState, useRef, useEffect } from "react";
 import katex from "katex";
 import "katex/dist/katex.min.css";
 import { parse_latex } from "latex-expr-parser";
-import { find_identifiers, R300, calculate_expression } from "geo-calc";
+import { generate_variables, find_identifiers, R300, calculate_expression } from "geo-calc";
 import ASTTreeVisualization from "./ASTTreeVisualization";
+import ParameterSliders, { Declarations, declareParameters, scalar } from "./ParameterSliders";
 import ThreeJs3DSpace from "./ThreeJs3DSpace";
 
 interface LatexEditorProps {
@@ -16,31 +17,27 @@ const LatexEditor: React.FC<LatexEditorProps> = ({ value, onChange }) => {
     const [ast, setAst] = useState<any>(null);
     const threejsRef = useRef<any>(null);
     const [identifiers, setIdentifiers] = useState<[string, R300][]>([]);
+    const [parsed, setParsed] = useState<any>(null);
+    const [declarations, setDeclarations] = useState<Declarations>({});
 
     const createIdentifiers = (ast: any) => {
-        // Find all identifiers in the AST
-        const ids = find_identifiers(ast);
-        const uniqueIds = new Set(ids);
-
-        // Create random vectors for new identifiers
-        const identifiers: [string, R300][] = [];
-
-        uniqueIds.forEach(id => {
-            // Create a random vector for this identifier
-            const x = Math.random() * 10 - 5; // Range: -5 to 5
-            const y = Math.random() * 10 - 5;
-            const z = Math.random() * 10 - 5;
-
-            // Create an R300 vector and add it to the array of tuples
-            const vector = R300.vector(x, y, z);
-            identifiers.push([id, vector]);
-        });
-
+        const declarations = declareParameters(find_identifiers(ast));
+        // Seeded from the expression, so the same input always shows the same geometry
+        const generated = JSON.parse(generate_variables(ast, declarations, undefined));
+        const identifiers: [string, R300][] = Object.entries(generated)
+            .map(([id, value]) => [id, R300.fromJson(value)]);
 
         // Update the identifiers map
+        setDeclarations(declarations);
         setIdentifiers(identifiers);
+        return identifiers;
     }
 
+    const calculate = (ast: any, identifiers: [string, R300][]) => {
+        const vars = Object.fromEntries(identifiers.map(([key, value]) => [key, value.toJson()]));
+        const calculated = JSON.parse(calculate_expression(ast, vars)).root;
+        setAst(calculated);
+    }
 
     // Called when the user types in the textarea
     const handleInputChange = (e: React.ChangeEvent<HTMLTextAreaElement>) => {
@@ -49,15 +46,27 @@ const LatexEditor: React.FC<LatexEditorProps> = ({ value, onChange }) => {
         onChange(input);
         try {
             const ast = parse_latex(input);
-            createIdentifiers(ast);
-            const vars = Object.fromEntries(identifiers.map(([key, value]) => [key, value.toJson()]));
-            const calculated = JSON.parse(calculate_expression(ast, vars));
-            setAst(calculated);
+            setParsed(ast);
+            calculate(ast, createIdentifiers(ast));
+        } catch (error: any) {
+            setAst({ error: error.message });
+        }
+    };
+
+    // Called when a parameter's slider moves
+    const handleParameterChange = (name: string, value: number) => {
+        const updated: [string, R300][] = identifiers
+            .map(([id, current]) => [id, id === name ? scalar(value) : current]);
+        setIdentifiers(updated);
+        try {
+            calculate(parsed, updated);
         } catch (error: any) {
             setAst({ error: error.message });
         }
     };
 
+    const parameterValues = Object.fromEntries(identifiers.map(([id, value]) => [id, value.get(0)]));
+
     // Use KaTeX to convert the LaTeX input to HTML
     let renderedHTML = "";
     try {
@@ -100,6 +109,11 @@ const LatexEditor: React.FC<LatexEditorProps> = ({ value, onChange }) => {
                             placeholder="Enter LaTeX here..."
                             spellCheck="false"
                         />
+                        <ParameterSliders
+                            declarations={declarations}
+                            values={parameterValues}
+                            onChange={handleParameterChange}
+                        />
                     </div>
                     <div className="ast-section">
                         <h2>AST Preview</h2>
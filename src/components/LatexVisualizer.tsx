@@ -2,8 +2,9 @@ import React, { useState, useRef, useEffect } from 'react';
 import katex from 'katex';
 import 'katex/dist/katex.min.css';
 import { parse_latex } from 'latex-expr-parser';
-import { find_identifiers, R300, calculate_expression } from 'geo-calc';
+import { generate_variables, find_identifiers, R300, calculate_expression } from 'geo-calc';
 import ASTTreeVisualization from './ASTTreeVisualization';
+import ParameterSliders, { Declarations, declareParameters, scalar } from './ParameterSliders';
 import ThreeJs3DSpace, { ThreeJs3DSpaceRef } from './ThreeJs3DSpace';
 
 interface LatexVisualizerProps {
@@ -22,33 +23,49 @@ const LatexVisualizer: React.FC<LatexVisualizerProps> = ({ latex, className }) =
     const [visualizationMode, setVisualizationMode] = useState<VisualizationMode>(VisualizationMode.NONE);
     const threejsRef = useRef<ThreeJs3DSpaceRef>(null);
 
+    const [parsed, setParsed] = useState<any>(null);
+    const [identifiers, setIdentifiers] = useState<[string, R300][]>([]);
+    const [declarations, setDeclarations] = useState<Declarations>({});
+
+    const calculate = (parsedAst: any, identifiers: [string, R300][]) => {
+        const vars = Object.fromEntries(identifiers.map(([key, value]) => [key, value.toJson()]));
+        const calculated = JSON.parse(calculate_expression(parsedAst, vars)).root;
+        setAst(calculated);
+    };
+
     // Parse LaTeX and create AST
     useEffect(() => {
         try {
             const parsedAst = parse_latex(latex);
-            const ids = find_identifiers(parsedAst);
-            const uniqueIds = new Set(ids);
-
-            // Create random vectors for identifiers
-            const newIdentifiers: [string, R300][] = [];
-            uniqueIds.forEach(id => {
-                const x = Math.random() * 10 - 5; // Range: -5 to 5
-                const y = Math.random() * 10 - 5;
-                const z = Math.random() * 10 - 5;
-                const vector = R300.vector(x, y, z);
-                newIdentifiers.push([id, vector]);
-            });
-
-            // Calculate the expression with the random vectors
-            const vars = Object.fromEntries(newIdentifiers.map(([key, value]) => [key, value.toJson()]));
-            const calculated = JSON.parse(calculate_expression(parsedAst, vars));
-            console.log(calculated);
-            setAst(calculated);
+            // Greek letters such as θ get a slider instead of a random vector
+            const newDeclarations = declareParameters(find_identifiers(parsedAst));
+            const newIdentifiers: [string, R300][] = Object.entries(
+                JSON.parse(generate_variables(parsedAst, newDeclarations, undefined))
+            ).map(([id, value]) => [id, R300.fromJson(value)]);
+
+            // Calculate the expression with the generated values
+            setParsed(parsedAst);
+            setDeclarations(newDeclarations);
+            setIdentifiers(newIdentifiers);
+            calculate(parsedAst, newIdentifiers);
         } catch (error: any) {
             setAst({ error: error.message });
         }
     }, [latex]);
 
+    const handleParameterChange = (name: string, value: number) => {
+        const updated: [string, R300][] = identifiers
+            .map(([id, current]) => [id, id === name ? scalar(value) : current]);
+        setIdentifiers(updated);
+        try {
+            calculate(parsed, updated);
+        } catch (error: any) {
+            setAst({ error: error.message });
+        }
+    };
+
+    const parameterValues = Object.fromEntries(identifiers.map(([id, value]) => [id, value.get(0)]));
+
     // Render LaTeX using KaTeX
     let renderedHTML = "";
     try {
@@ -91,6 +108,11 @@ const LatexVisualizer: React.FC<LatexVisualizerProps> = ({ latex, className }) =
             <div className="latex-header-container">
                 <div className="latex-display">
                     <div className="latex-content" dangerouslySetInnerHTML={{ __html: renderedHTML }} />
+                    <ParameterSliders
+                        declarations={declarations}
+                        values={parameterValues}
+                        onChange={handleParameterChange}
+                    />
                 </div>
                 <div className="visualization-controls">
                     <button
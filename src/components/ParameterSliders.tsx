@@ -0,0 +1,49 @@
+import React from 'react';
+import { R300 } from 'geo-calc';
+import { Parameter } from './types';
+
+// What generate_variables and analyze_expression are told about each identifier
+export type Declarations = Record<string, { parameter: Parameter }>;
+
+const GREEK = /^\\?(alpha|beta|gamma|delta|epsilon|theta|vartheta|phi|varphi|psi|omega|lambda|mu|tau)$/;
+
+// Greek letters such as \theta are scalar parameters with a slider, the other identifiers vectors
+export const declareParameters = (names: string[]): Declarations =>
+    Object.fromEntries(
+        names
+            .filter((name) => GREEK.test(name))
+            .map((name) => [name, { parameter: { min: 0, max: 2 * Math.PI, step: 0.01 } }])
+    );
+
+export const scalar = (value: number): R300 =>
+    R300.fromJson({ mvec: [value, 0, 0, 0, 0, 0, 0, 0] });
+
+interface ParameterSlidersProps {
+    declarations: Declarations;
+    values: Record<string, number>;
+    onChange: (name: string, value: number) => void;
+}
+
+const ParameterSliders: React.FC<ParameterSlidersProps> = ({ declarations, values, onChange }) => (
+    <div className="parameter-sliders">
+        {Object.entries(declarations).map(([name, { parameter }]) => {
+            const value = values[name] ?? parameter.min;
+            return (
+                <label key={name} className="parameter-slider">
+                    <span className="parameter-name">{name}</span>
+                    <input
+                        type="range"
+                        min={parameter.min}
+                        max={parameter.max}
+                        step={parameter.step ?? (parameter.max - parameter.min) / 100}
+                        value={value}
+                        onChange={(e) => onChange(name, Number(e.target.value))}
+                    />
+                    <span className="parameter-value">{value.toFixed(2)}</span>
+                </label>
+            );
+        })}
+    </div>
+);
+
+export default ParameterSliders;
@@ -9,8 +9,8 @@ import {
 } from 'three/examples/jsm/renderers/CSS2DRenderer.js';
 
 import ControlBoard, { SelectedVector } from './ControlBoard/ControlBoard';
-import { ThreeJSMultiVector } from './types';
-import { R300 } from 'geo-calc';
+import { Item, Primitive, Scene, ThreeJSMultiVector } from './types';
+import { R300, describe_scene } from 'geo-calc';
 import katex from 'katex';
 import 'katex/dist/katex.min.css';
 
@@ -64,30 +64,148 @@ export const StyledApp = styled.div`
   }
 `;
 
-// Function to generate a random vector
-const r300_to_threejs_object = (r300: R300): THREE.Vector3 | [THREE.Vector3, THREE.Vector3] => {
-  if (r300.isVector()) {
-    return new THREE.Vector3(r300.get(1), r300.get(2), r300.get(3));
-  }
-  // TODO: I am pretty sure this is wrong...
-  if (r300.isBivector()) {
-    const b = new THREE.Vector3(r300.get(4), r300.get(5), r300.get(6));
-    let v1;
-    if (b.x !== 0) {
-      v1 = new THREE.Vector3(-b.y/b.x, 1, 0);
-    } else if (b.y !== 0) {
-      v1 = new THREE.Vector3(1, -b.y/b.z, 0);
-    } else {
-      v1 = new THREE.Vector3(1, 0, 0);
-    }
-
-    v1.normalize();
-    v1.multiplyScalar(b.length());
-    const v2 = new THREE.Vector3().crossVectors(b, v1).normalize().multiplyScalar(v1.length());
+const toVector3 = (point: [number, number, number]) => new THREE.Vector3(...point);
+
+// A circular arc from u towards v with an arrowhead at its end, see `Primitive` in
+// geo-calc/src/primitive.rs.
+const arcObject = (
+  center: THREE.Vector3,
+  u: THREE.Vector3,
+  v: THREE.Vector3,
+  angle: number,
+  color: string
+): THREE.Object3D => {
+  const at = (t: number) => center.clone()
+    .addScaledVector(u, Math.cos(t))
+    .addScaledVector(v, Math.sin(t));
+  const segments = Math.max(8, Math.ceil(Math.abs(angle) * 16));
+  const points = Array.from({ length: segments + 1 }, (_, i) => at(angle * i / segments));
+  const arc = new THREE.Line(
+    new THREE.BufferGeometry().setFromPoints(points),
+    new THREE.LineBasicMaterial({ color })
+  );
+  const tangent = u.clone().multiplyScalar(-Math.sin(angle))
+    .addScaledVector(v, Math.cos(angle))
+    .multiplyScalar(Math.sign(angle))
+    .normalize();
+  const head = new THREE.ArrowHelper(tangent, at(angle), 0.01, color, 0.2, 0.1);
+  const group = new THREE.Group();
+  group.add(arc, head);
+  return group;
+};
 
-    return [v1, v2] as [THREE.Vector3, THREE.Vector3];
+// Turns one primitive of the scene description into THREE objects.
+const primitiveToObject = (primitive: Primitive, color: string): THREE.Object3D => {
+  switch (primitive.primitive) {
+    case 'arrow': {
+      const vector = toVector3(primitive.vector);
+      return new THREE.ArrowHelper(
+        vector.clone().normalize(),
+        toVector3(primitive.origin),
+        vector.length(),
+        color,
+        0.2,
+        0.1
+      );
+    }
+    case 'segment': {
+      const geometry = new THREE.BufferGeometry()
+        .setFromPoints([toVector3(primitive.from), toVector3(primitive.to)]);
+      const material = new THREE.LineDashedMaterial({ color, dashSize: 0.1, gapSize: 0.1 });
+      const line = new THREE.Line(geometry, material);
+      line.computeLineDistances();
+      return line;
+    }
+    case 'parallelogram': {
+      const origin = toVector3(primitive.origin);
+      const u = toVector3(primitive.u);
+      const v = toVector3(primitive.v);
+      const points = [origin, origin.clone().add(u), origin.clone().add(u).add(v), origin.clone().add(v)];
+      const group = new THREE.Group();
+      group.add(new THREE.LineLoop(
+        new THREE.BufferGeometry().setFromPoints(points),
+        new THREE.LineBasicMaterial({ color, opacity: 0.5, transparent: true })
+      ));
+      group.add(new THREE.Mesh(
+        new THREE.BufferGeometry().setFromPoints([points[0], points[1], points[2], points[0], points[2], points[3]]),
+        new THREE.MeshBasicMaterial({ color, opacity: 0.2, transparent: true, side: THREE.DoubleSide })
+      ));
+      // The orientation, from the first side towards the second.
+      const center = origin.clone().addScaledVector(u, 0.5).addScaledVector(v, 0.5);
+      group.add(arcObject(center, u.clone().multiplyScalar(0.25), v.clone().setLength(u.length() * 0.25), Math.PI, color));
+      return group;
+    }
+    case 'parallelepiped': {
+      const origin = toVector3(primitive.origin);
+      const [u, v, w] = [primitive.u, primitive.v, primitive.w].map(toVector3);
+      const corner = (i: number) => origin.clone()
+        .addScaledVector(u, i & 1)
+        .addScaledVector(v, (i >> 1) & 1)
+        .addScaledVector(w, (i >> 2) & 1);
+      const edges = [[0, 1], [2, 3], [4, 5], [6, 7], [0, 2], [1, 3], [4, 6], [5, 7], [0, 4], [1, 5], [2, 6], [3, 7]];
+      const faces = [[0, 1, 3, 2], [4, 5, 7, 6], [0, 1, 5, 4], [2, 3, 7, 6], [0, 2, 6, 4], [1, 3, 7, 5]];
+      const group = new THREE.Group();
+      group.add(new THREE.LineSegments(
+        new THREE.BufferGeometry().setFromPoints(edges.flat().map(corner)),
+        new THREE.LineBasicMaterial({ color, opacity: 0.5, transparent: true })
+      ));
+      group.add(new THREE.Mesh(
+        new THREE.BufferGeometry().setFromPoints(
+          faces.flatMap(([a, b, c, d]) => [a, b, c, a, c, d]).map(corner)
+        ),
+        new THREE.MeshBasicMaterial({ color, opacity: 0.15, transparent: true, side: THREE.DoubleSide })
+      ));
+      // The handedness, as the three edges from the origin in order.
+      [u, v, w].forEach((edge) => group.add(
+        new THREE.ArrowHelper(edge.clone().normalize(), origin, edge.length(), color, 0.2, 0.1)
+      ));
+      return group;
+    }
+    case 'disc': {
+      const center = toVector3(primitive.center);
+      const normal = toVector3(primitive.normal).normalize();
+      const disc = new THREE.Mesh(
+        new THREE.CircleGeometry(primitive.radius, 48),
+        new THREE.MeshBasicMaterial({ color, opacity: 0.2, transparent: true, side: THREE.DoubleSide })
+      );
+      disc.quaternion.setFromUnitVectors(new THREE.Vector3(0, 0, 1), normal);
+      disc.position.copy(center);
+      // The circulation, counterclockwise seen from the tip of the normal.
+      const u = new THREE.Vector3(1, 0, 0).applyQuaternion(disc.quaternion).multiplyScalar(primitive.radius * 0.7);
+      const v = new THREE.Vector3().crossVectors(normal, u);
+      const group = new THREE.Group();
+      group.add(disc, arcObject(center, u, v, 1.5 * Math.PI, color));
+      return group;
+    }
+    case 'sphere': {
+      const center = toVector3(primitive.center);
+      const sphere = new THREE.Mesh(
+        new THREE.SphereGeometry(primitive.radius, 32, 16),
+        new THREE.MeshBasicMaterial({ color, opacity: 0.15, transparent: true })
+      );
+      sphere.position.copy(center);
+      // The handedness, as a circulation about the z axis: counterclockwise seen from above
+      // for a right handed volume.
+      const sign = primitive.handedness === 'right' ? 1 : -1;
+      const group = new THREE.Group();
+      group.add(sphere, arcObject(
+        center,
+        new THREE.Vector3(primitive.radius, 0, 0),
+        new THREE.Vector3(0, primitive.radius, 0),
+        sign * 1.5 * Math.PI,
+        color
+      ));
+      return group;
+    }
+    case 'arc':
+      return arcObject(
+        toVector3(primitive.center),
+        toVector3(primitive.u),
+        toVector3(primitive.v),
+        primitive.angle,
+        color
+      );
   }
-  return new THREE.Vector3(0, 0, 0);
 };
 
 const ThreeJs3DSpace = forwardRef<ThreeJs3DSpaceRef, ThreeJs3DSpaceProps>(({ className }, ref) => {
@@ -218,74 +336,15 @@ const ThreeJs3DSpace = forwardRef<ThreeJs3DSpaceRef, ThreeJs3DSpaceProps>(({ cla
     []
   );
 
-  const drawBivector = useCallback(
-    (vectors: [THREE.Vector3, THREE.Vector3], name: string, idx: number | null) => {
-      // Create a container for the bivector visualization
-      const bivectorContainer = new THREE.Group();
-
-      // Draw the two spanning vectors
-      const [v1, v2] = vectors;
-      const hex = 0x00ffff;
-
-      // Draw first vector
-      const dir1 = v1.clone().normalize();
-      const vectorHelper1 = new THREE.ArrowHelper(
-        dir1,
-        new THREE.Vector3(0, 0, 0),
-        v1.length(),
-        hex,
-        0.2,
-        0.1
-      );
-
-      // Draw second vector
-      const dir2 = v2.clone().normalize();
-      const vectorHelper2 = new THREE.ArrowHelper(
-        dir2,
-        new THREE.Vector3(0, 0, 0),
-        v2.length(),
-        hex,
-        0.2,
-        0.1
-      );
+  const drawItem = useCallback(
+    (item: Item, idx: number | null) => {
+      const container = new THREE.Group();
+      item.shapes.forEach((primitive) => container.add(primitiveToObject(primitive, item.color)));
+      const labelPosition = toVector3(item.label.position);
+      container.add(createLabel(labelPosition, item.label.text));
+      container.userData.target = labelPosition;
 
-      // Create a parallelogram to represent the plane
-      const points: [THREE.Vector3, THREE.Vector3, THREE.Vector3, THREE.Vector3] = [
-        new THREE.Vector3(0, 0, 0),
-        v1.clone(),
-        v1.clone().add(v2),
-        v2.clone(),
-      ];
-
-      const geometry = new THREE.BufferGeometry();
-      geometry.setFromPoints(points);
-      const material = new THREE.LineBasicMaterial({ color: hex, opacity: 0.5, transparent: true });
-      const parallelogram = new THREE.LineLoop(geometry, material);
-
-      // Create fill for the parallelogram
-      const fillGeometry = new THREE.BufferGeometry();
-      fillGeometry.setFromPoints([points[0], points[1], points[2], points[0], points[2], points[3]]);
-      const fillMaterial = new THREE.MeshBasicMaterial({ color: hex, opacity: 0.2, transparent: true, side: THREE.DoubleSide });
-      const fill = new THREE.Mesh(fillGeometry, fillMaterial);
-
-      // Add label at the center of the parallelogram
-      const center = new THREE.Vector3()
-        .addVectors(v1, v2)
-        .multiplyScalar(0.5);
-      const bivectorLabel = createLabel(center, name);
-
-      // Add everything to the container
-      bivectorContainer.add(vectorHelper1);
-      bivectorContainer.add(vectorHelper2);
-      bivectorContainer.add(parallelogram);
-      bivectorContainer.add(fill);
-      bivectorContainer.add(bivectorLabel);
-
-      // Store the original vectors in userData
-      bivectorContainer.userData.target = vectors;
-
-      // Add to scene
-      sceneRef.current!.add(bivectorContainer);
+      sceneRef.current!.add(container);
 
       setVectors((vectors) => {
         let existingVectors = vectors;
@@ -300,7 +359,7 @@ const ThreeJs3DSpace = forwardRef<ThreeJs3DSpaceRef, ThreeJs3DSpaceProps>(({ cla
           );
         }
 
-        let newVector = { vector: bivectorContainer, name: name };
+        let newVector = { vector: container, name: item.name };
         return [...existingVectors, newVector];
       });
     },
@@ -309,13 +368,9 @@ const ThreeJs3DSpace = forwardRef<ThreeJs3DSpaceRef, ThreeJs3DSpaceProps>(({ cla
 
   // Add methods to add/remove vectors by name
   const addVector = useCallback((name: string, value: R300) => {
-    const result = r300_to_threejs_object(value);
-    if (Array.isArray(result)) {
-      drawBivector(result, name, null);
-    } else {
-      drawVector(result, name, null);
-    }
-  }, [drawVector, drawBivector]);
+    const scene: Scene = JSON.parse(describe_scene({ [name]: value.toJson() }, undefined));
+    scene.items.forEach((item) => drawItem(item, null));
+  }, [drawItem]);
 
   const addVectorWithPosition = useCallback((name: string, position: { x: number, y: number, z: number }) => {
     const vector = new THREE.Vector3(position.x, position.y, position.z);
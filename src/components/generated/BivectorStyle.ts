@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How bivectors are drawn. Both have the bivector's area and orientation.
+ */
+export type BivectorStyle = "parallelogram" | "disc";
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A multivector by the coefficients of its basis blades.
+ */
+export type Blades = { scalar: number, e1: number, e2: number, e3: number, e12: number, e13: number, e23: number, e123: number, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A worked step for one node: the rule that produced its value, written in LaTeX, followed by
+ * the same rule with the children's numbers substituted and the final value.
+ */
+export type Derivation = { rule: string, steps: Array<string>, };
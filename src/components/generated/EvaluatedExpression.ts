@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Node } from "./Node";
+
+export type EvaluatedExpression = { version: number, 
+/**
+ * The algebra the values live in, `R300` is the geometric algebra of 3D Euclidean space.
+ */
+algebra: string, 
+/**
+ * The blades of the convention the `components` of every node are written in, e.g. `e31`
+ * for the cyclic one. Absent for the standard blades of `value`.
+ */
+basis?: Array<string>, root: Node, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The orientation of a volume, the sign of its e123 component.
+ */
+export type Handedness = "right" | "left";
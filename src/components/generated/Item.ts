@@ -0,0 +1,24 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Label } from "./Label";
+import type { Primitive } from "./Primitive";
+
+/**
+ * One named value and the shapes that draw it.
+ */
+export type Item = { name: string, 
+/**
+ * Absent for values with several grades.
+ */
+grade?: number, 
+/**
+ * A CSS colour, see `color`.
+ */
+color: string, shapes: Array<Primitive>, label: Label, 
+/**
+ * The signed volume of a trivector, see `primitive::signed_volume`.
+ */
+volume?: number, 
+/**
+ * The source span of the node this item draws, so clicking it can select the node.
+ */
+start?: number, end?: number, };
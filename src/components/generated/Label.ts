@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Text placed at a point, e.g. the name of a vector at its tip.
+ */
+export type Label = { position: [number, number, number], text: string, };
@@ -0,0 +1,32 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Blades } from "./Blades";
+import type { Derivation } from "./Derivation";
+import type { NodeError } from "./NodeError";
+import type { Operator } from "./Operator";
+import type { PartialDerivative } from "./PartialDerivative";
+
+export type Node = { start: number, end: number, 
+/**
+ * Always in the standard blades, whatever the document's `basis`.
+ */
+value: Blades, 
+/**
+ * `value` in the document's `basis`, present when it has one.
+ */
+components?: Array<number>, 
+/**
+ * The operands, left to right, e.g. the base of a power or the arguments of a call.
+ */
+children: Array<Node>, 
+/**
+ * Present when `value` has a single grade.
+ */
+grade?: number, description?: string, 
+/**
+ * Why this node could not be evaluated, in which case `value` is zero.
+ */
+errors?: Array<NodeError>, derivation?: Derivation, 
+/**
+ * The derivatives of `value` with respect to each component of the inputs.
+ */
+jacobian?: Array<PartialDerivative>, } & ({ "kind": "Identifier", name: string, } | { "kind": "Constant", name: string, } | { "kind": "Int" } | { "kind": "BinaryOp", op: Operator, } | { "kind": "UnaryOp", op: Operator, } | { "kind": "Reverse" } | { "kind": "Dual" } | { "kind": "Conjugate" } | { "kind": "Involute" } | { "kind": "Inverse" } | { "kind": "Power", exponent: number, } | { "kind": "Magnitude" } | { "kind": "Angle" } | { "kind": "Area" } | { "kind": "FunctionCall", name: string, } | { "kind": "Equation", holds: boolean, });
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * An evaluation error of a node, see `EvalError`.
+ */
+export type NodeError = { message: string, 
+/**
+ * The span to highlight, which can be a part of the node such as an exponent.
+ */
+start: number, end: number, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Operator } from "./Operator";
+
+/**
+ * What a node computes, the serialized form of `Kind`.
+ */
+export type NodeKind = { "kind": "Identifier", name: string, } | { "kind": "Constant", name: string, } | { "kind": "Int" } | { "kind": "BinaryOp", op: Operator, } | { "kind": "UnaryOp", op: Operator, } | { "kind": "Reverse" } | { "kind": "Dual" } | { "kind": "Conjugate" } | { "kind": "Involute" } | { "kind": "Inverse" } | { "kind": "Power", exponent: number, } | { "kind": "Magnitude" } | { "kind": "Angle" } | { "kind": "Area" } | { "kind": "FunctionCall", name: string, } | { "kind": "Equation", holds: boolean, };
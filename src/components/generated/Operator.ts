@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The operator of a `BinaryOp` or `UnaryOp` node, named by the parser's token.
+ */
+export type Operator = "Plus" | "Minus" | "Multiply" | "Dot" | "Wedge" | "Frac" | "Caret" | "Equals" | "Dagger" | "Tilde" | "Star" | "Bar" | "Hat" | "Pipe" | "DoublePipe" | "LParen" | "RParen";
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A scalar identifier shown as a slider, such as the angle in `e^{-\frac{\theta}{2}B}`.
+ *
+ * In JSON: `{"min": 0, "max": 6.28, "step": 0.01}`, the step being optional.
+ */
+export type Parameter = { min: number, max: number, step?: number, };
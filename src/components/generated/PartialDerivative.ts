@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Blades } from "./Blades";
+
+/**
+ * The derivative of a node's value with respect to one component of one input.
+ */
+export type PartialDerivative = { input: string, 
+/**
+ * The component of the input, named like in `basis`, e.g. `e1`.
+ */
+blade: string, derivative: Blades, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Handedness } from "./Handedness";
+
+/**
+ * Plain geometry, what the 3D view draws without knowing any geometric algebra.
+ */
+export type Primitive = { "primitive": "arrow", origin: [number, number, number], vector: [number, number, number], } | { "primitive": "segment", from: [number, number, number], to: [number, number, number], } | { "primitive": "parallelogram", origin: [number, number, number], u: [number, number, number], v: [number, number, number], } | { "primitive": "parallelepiped", origin: [number, number, number], u: [number, number, number], v: [number, number, number], w: [number, number, number], } | { "primitive": "disc", center: [number, number, number], normal: [number, number, number], radius: number, } | { "primitive": "sphere", center: [number, number, number], radius: number, handedness: Handedness, } | { "primitive": "arc", center: [number, number, number], u: [number, number, number], v: [number, number, number], angle: number, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Item } from "./Item";
+
+/**
+ * Everything the 3D view or an export draws, so that they all show the same geometry.
+ */
+export type Scene = { items: Array<Item>, };
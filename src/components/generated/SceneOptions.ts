@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BivectorStyle } from "./BivectorStyle";
+import type { TrivectorStyle } from "./TrivectorStyle";
+
+export type SceneOptions = { bivectors: BivectorStyle, trivectors: TrivectorStyle, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How trivectors are drawn. Both have the trivector's volume and handedness.
+ */
+export type TrivectorStyle = "parallelepiped" | "sphere";
@@ -1,38 +1,17 @@
-export interface SerializedR300 {
-    mvec: number[];
-}
-
-export interface BinaryOperator {
-    BinaryOpNode: {
-        op: string;
-        left: ASTNode;
-        right: ASTNode;
-    }
-}
-
-export interface UnaryOperator {
-    UnaryOpNode: {
-        op: string;
-        operand: ASTNode;
-    }
-}
-
-export interface Identifier {
-    Identifier: {
-        name: string;
-    }
-}
-
-export interface Int {
-    Int: {}
-}
-
-export type ASTNode = {
-    start: number;
-    end: number;
-    value: SerializedR300,
-    type: BinaryOperator | UnaryOperator | Identifier | Int;
-}
+// The evaluated AST is generated from the Rust types in geo-calc/src/schema.rs, run
+// `UPDATE_GENERATED=1 cargo test` there to regenerate it.
+export type { Blades } from './generated/Blades';
+export type { Derivation } from './generated/Derivation';
+export type { EvaluatedExpression } from './generated/EvaluatedExpression';
+export type { Node as ASTNode } from './generated/Node';
+export type { NodeKind } from './generated/NodeKind';
+export type { NodeError } from './generated/NodeError';
+// The scene description is generated from geo-calc/src/scene.rs the same way.
+export type { Item } from './generated/Item';
+export type { Primitive } from './generated/Primitive';
+export type { Scene } from './generated/Scene';
+// Slider ranges come from geo-calc/src/parameter.rs.
+export type { Parameter } from './generated/Parameter';
 
 export interface ThreeJSMultiVector {
     vector: THREE.Object3D;
@@ -0,0 +1,87 @@
+use latex_expr_parser::{ASTNode, ASTNodeType};
+use std::fmt;
+
+/// A parser node with metadata of type `T` attached to it and to each of its descendants.
+///
+/// The tree borrows the parser's nodes instead of mirroring them, so every node type the parser
+/// produces can be annotated. `children` is the only place that needs to know its variants.
+#[derive(Clone, PartialEq)]
+pub struct Annotated<'a, T> {
+    pub node: &'a ASTNode,
+    /// The annotated children, in the order `children` lists them.
+    pub children: Vec<Annotated<'a, T>>,
+    pub meta: T,
+}
+
+impl<'a, T> Annotated<'a, T> {
+    /// Builds the tree bottom-up, `f` sees each node together with its annotated children.
+    pub fn build(
+        node: &'a ASTNode,
+        f: &mut impl FnMut(&'a ASTNode, &[Annotated<'a, T>]) -> T,
+    ) -> Self {
+        let children = children(node)
+            .into_iter()
+            .map(|child| Annotated::build(child, f))
+            .collect::<Vec<_>>();
+        let meta = f(node, &children);
+        Annotated {
+            node,
+            children,
+            meta,
+        }
+    }
+
+    /// The same tree with new metadata, `f` sees the old metadata of the node and its subtree.
+    pub fn map<U>(&self, f: &mut impl FnMut(&Annotated<'a, T>) -> U) -> Annotated<'a, U> {
+        Annotated {
+            node: self.node,
+            children: self.children.iter().map(|child| child.map(f)).collect(),
+            meta: f(self),
+        }
+    }
+
+    /// Every node, children before their parent and siblings left to right, which is the order
+    /// they are evaluated in.
+    pub fn post_order(&self) -> Vec<&Annotated<'a, T>> {
+        let mut nodes = Vec::new();
+        self.collect(&mut nodes);
+        nodes
+    }
+
+    fn collect<'b>(&'b self, nodes: &mut Vec<&'b Annotated<'a, T>>) {
+        for child in &self.children {
+            child.collect(nodes);
+        }
+        nodes.push(self);
+    }
+
+    pub fn start(&self) -> usize {
+        self.node.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.node.end
+    }
+}
+
+/// Only the spans are shown for the parser node, the subtree is already in `children`.
+impl<T: fmt::Debug> fmt::Debug for Annotated<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Annotated")
+            .field("start", &self.node.start)
+            .field("end", &self.node.end)
+            .field("meta", &self.meta)
+            .field("children", &self.children)
+            .finish()
+    }
+}
+
+/// The direct children of a parser node, left to right.
+pub fn children(node: &ASTNode) -> Vec<&ASTNode> {
+    match &node.r#type {
+        ASTNodeType::Int { .. } | ASTNodeType::Identifier { .. } => vec![],
+        ASTNodeType::BinaryOpNode { left, right, .. } => vec![left, right],
+        ASTNodeType::UnaryOpNode { operand, .. } => vec![operand],
+        ASTNodeType::FunctionCall { args, .. } => args.iter().collect(),
+    }
+}
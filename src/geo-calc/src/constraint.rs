@@ -0,0 +1,335 @@
+use crate::generate::{as_vector, reject, Declaration};
+use crate::r300::{R300, TOLERANCE};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Sweeps over all constraints before giving up on converging.
+const MAX_SWEEPS: usize = 100;
+
+/// A condition on the identifier `name`. The other identifiers a constraint mentions are read,
+/// never moved, so `b` orthogonal to `a` turns `b` and leaves `a` where the user put it.
+/// References can also be multivector literals such as `e_3`.
+///
+/// In JSON: `{"kind": "orthogonal", "name": "b", "to": "a"}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Constraint {
+    pub name: String,
+    #[serde(flatten)]
+    pub condition: Condition,
+}
+
+/// What a constraint asks of its identifier. Declarations for `generate` use the same
+/// conditions without a name, e.g. `{"kind": "unit"}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Condition {
+    Unit,
+    Magnitude {
+        magnitude: f64,
+    },
+    Orthogonal {
+        to: String,
+    },
+    Parallel {
+        to: String,
+    },
+    /// Lies in the blade spanned by the wedge of `spanned_by`, e.g. `["a", "c"]` for the plane
+    /// of `a` and `c` or `["e_{12}"]` for the e1e2 plane.
+    InBlade {
+        spanned_by: Vec<String>,
+    },
+    /// The angle to `to` in radians, between 0 and pi.
+    Angle {
+        to: String,
+        angle: f64,
+    },
+}
+
+impl Condition {
+    /// The identifiers or literals this condition reads.
+    pub fn references(&self) -> Vec<&String> {
+        match self {
+            Condition::Unit | Condition::Magnitude { .. } => vec![],
+            Condition::Orthogonal { to }
+            | Condition::Parallel { to }
+            | Condition::Angle { to, .. } => vec![to],
+            Condition::InBlade { spanned_by } => spanned_by.iter().collect(),
+        }
+    }
+
+    /// This condition on `name`.
+    pub fn on(&self, name: &str) -> Constraint {
+        Constraint {
+            name: name.to_string(),
+            condition: self.clone(),
+        }
+    }
+}
+
+impl Constraint {
+    /// The identifier this constraint moves.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The closest value to `value` that satisfies this constraint, given the current `values`
+    /// of everything it refers to.
+    fn project(
+        &self,
+        value: R300,
+        values: &HashMap<String, R300>,
+    ) -> Result<R300, ConstraintError> {
+        let grade = self.grade(value)?;
+        let v = as_vector(value, grade);
+        let reference = |reference: &String| {
+            let value = resolve(self.name(), reference, values.get(reference))?;
+            if value == R300::zero() || value.grade() == Some(grade) {
+                Ok(as_vector(value, grade))
+            } else {
+                Err(self.invalid("refers to a value of another grade"))
+            }
+        };
+        let projected = match &self.condition {
+            Condition::Unit => with_length(v, 1.0),
+            Condition::Magnitude { magnitude } => with_length(v, *magnitude),
+            Condition::Orthogonal { to } => reject(v, &unit(reference(to)?)),
+            Condition::Parallel { to } => match unit(reference(to)?)[..] {
+                [u] => v.dot(u)[0] * u,
+                _ => v,
+            },
+            Condition::InBlade { spanned_by } => {
+                if grade != 1 {
+                    return Err(self.invalid("only vectors lie in a blade"));
+                }
+                let blade = self.blade(spanned_by, values)?;
+                match blade.inverse() {
+                    Some(inverse) => (v.dot(blade) * inverse).grade_part(1),
+                    None => return Err(self.invalid("the blade is zero")),
+                }
+            }
+            Condition::Angle { to, angle } => {
+                let u = unit(reference(to)?);
+                let Some(&u) = u.first() else {
+                    return Ok(value);
+                };
+                // Turn `v` in the plane it spans with `u`, or any plane through `u` when they
+                // are parallel, keeping its length.
+                let mut perpendicular = reject(v, &[u]);
+                if perpendicular.norm() < TOLERANCE {
+                    perpendicular = [R300::vector(1.0, 0.0, 0.0), R300::vector(0.0, 1.0, 0.0)]
+                        .into_iter()
+                        .map(|axis| reject(axis, &[u]))
+                        .max_by(|a, b| a.norm().total_cmp(&b.norm()))
+                        .unwrap();
+                }
+                v.norm() * (angle.cos() * u + angle.sin() * perpendicular.normalized())
+            }
+        };
+        Ok(from_vector(projected, grade))
+    }
+
+    /// The wedge of the references of an `InBlade` condition.
+    fn blade(
+        &self,
+        spanned_by: &[String],
+        values: &HashMap<String, R300>,
+    ) -> Result<R300, ConstraintError> {
+        spanned_by
+            .iter()
+            .map(|reference| resolve(self.name(), reference, values.get(reference)))
+            .try_fold(R300::new(1.0, 0), |blade, value| {
+                value.map(|value| blade.wedge(value))
+            })
+    }
+
+    /// How far `values` are from satisfying this constraint. A zero value has no direction to
+    /// scale, so it is measured against the magnitude rather than against its projection.
+    pub fn violation(&self, values: &HashMap<String, R300>) -> Result<f64, ConstraintError> {
+        let value = *values
+            .get(self.name())
+            .ok_or_else(|| ConstraintError::UnknownIdentifier(self.name.clone()))?;
+        match self.condition {
+            Condition::Unit => Ok((value.norm() - 1.0).abs()),
+            Condition::Magnitude { magnitude } => Ok((value.norm() - magnitude).abs()),
+            _ => Ok((self.project(value, values)? - value).norm()),
+        }
+    }
+
+    /// Only vectors and bivectors have directions, bivectors are handled through their duals.
+    fn grade(&self, value: R300) -> Result<usize, ConstraintError> {
+        match value.grade() {
+            Some(grade @ (1 | 2)) => Ok(grade),
+            None if value == R300::zero() => Ok(1),
+            _ => match self.condition {
+                Condition::Unit | Condition::Magnitude { .. } => Ok(1),
+                _ => Err(self.invalid("only vectors and bivectors have a direction")),
+            },
+        }
+    }
+
+    fn invalid(&self, reason: &str) -> ConstraintError {
+        ConstraintError::InvalidConstraint {
+            name: self.name.clone(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = &self.name;
+        match &self.condition {
+            Condition::Unit => write!(f, "|{}| = 1", name),
+            Condition::Magnitude { magnitude } => write!(f, "|{}| = {}", name, magnitude),
+            Condition::Orthogonal { to } => write!(f, "{} \\perp {}", name, to),
+            Condition::Parallel { to } => write!(f, "{} \\parallel {}", name, to),
+            Condition::InBlade { spanned_by } => {
+                write!(f, "{} \\in {}", name, spanned_by.join(" \\wedge "))
+            }
+            Condition::Angle { to, angle } => {
+                write!(f, "\\angle({}, {}) = {}", name, to, angle)
+            }
+        }
+    }
+}
+
+impl Declaration {
+    /// The constraints that keep a value generated for `name` what it was declared as.
+    pub fn constraints(&self, name: &str) -> Vec<Constraint> {
+        self.constraints
+            .iter()
+            .map(|condition| condition.on(name))
+            .collect()
+    }
+}
+
+/// Why constraints could not be applied, shared by the solver and `generate`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstraintError {
+    UnknownIdentifier(String),
+    /// A constraint on `name` names something that is neither an identifier with a value nor a
+    /// multivector literal.
+    UnknownReference {
+        name: String,
+        reference: String,
+    },
+    InvalidConstraint {
+        name: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for ConstraintError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConstraintError::UnknownIdentifier(name) => {
+                write!(f, "Constraint on an unknown identifier: {}", name)
+            }
+            ConstraintError::UnknownReference { name, reference } => {
+                write!(
+                    f,
+                    "Unknown reference in the constraints of {}: {}",
+                    name, reference
+                )
+            }
+            ConstraintError::InvalidConstraint { name, reason } => {
+                write!(f, "Invalid constraint on {}: {}", name, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConstraintError {}
+
+/// The projected values and how well they satisfy the constraints.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Solution {
+    pub values: HashMap<String, R300>,
+    /// False when the constraints contradict each other, e.g. `a` both parallel and orthogonal
+    /// to `b`. The values are then the last projection tried.
+    pub converged: bool,
+    /// The constraints still violated by more than the tolerance, by index.
+    pub violated: Vec<usize>,
+}
+
+/// Moves `values` onto the set where all `constraints` hold by projecting onto each constraint
+/// in turn until nothing moves. Values without constraints are returned unchanged, so the
+/// result can be passed straight to `calculate_ast_expression`.
+pub fn solve(
+    values: &HashMap<String, R300>,
+    constraints: &[Constraint],
+) -> Result<Solution, ConstraintError> {
+    let mut values = values.clone();
+    let mut converged = false;
+    for _ in 0..MAX_SWEEPS {
+        let mut moved: f64 = 0.0;
+        for constraint in constraints {
+            let name = constraint.name().to_string();
+            let value = *values
+                .get(&name)
+                .ok_or_else(|| ConstraintError::UnknownIdentifier(name.clone()))?;
+            let projected = constraint.project(value, &values)?;
+            moved = moved.max((projected - value).norm());
+            values.insert(name, projected);
+        }
+        if moved < TOLERANCE {
+            converged = true;
+            break;
+        }
+    }
+    let mut violated = Vec::new();
+    for (i, constraint) in constraints.iter().enumerate() {
+        if constraint.violation(&values)? > TOLERANCE.sqrt() {
+            violated.push(i);
+        }
+    }
+    Ok(Solution {
+        values,
+        converged: converged && violated.is_empty(),
+        violated,
+    })
+}
+
+/// The value `reference` stands for in a constraint on `name`: `known` when it is an identifier
+/// with a value, otherwise the basis blade it spells, e.g. `e_3`. Reserved identifiers such as
+/// `I` are not blade literals, so a misspelled reference is never silently the pseudoscalar.
+pub(crate) fn resolve(
+    name: &str,
+    reference: &str,
+    known: Option<&R300>,
+) -> Result<R300, ConstraintError> {
+    known
+        .copied()
+        .or_else(|| R300::parse_blade(reference))
+        .ok_or_else(|| ConstraintError::UnknownReference {
+            name: name.to_string(),
+            reference: reference.to_string(),
+        })
+}
+
+/// The inverse of `as_vector`, `(B e_{123}) e_{123} = -B`.
+fn from_vector(v: R300, grade: usize) -> R300 {
+    if grade == 2 {
+        -1.0 * v.Dual()
+    } else {
+        v
+    }
+}
+
+/// `u` normalized as a one element basis for `reject`, empty when `u` is zero.
+fn unit(u: R300) -> Vec<R300> {
+    if u.norm() < TOLERANCE {
+        vec![]
+    } else {
+        vec![u.normalized()]
+    }
+}
+
+fn with_length(v: R300, length: f64) -> R300 {
+    if v.norm() < TOLERANCE {
+        v
+    } else {
+        length * v.normalized()
+    }
+}
@@ -0,0 +1,234 @@
+use crate::keyframe::lerp;
+use crate::primitive::{factor, Role, Shape};
+use crate::r300::R300;
+use crate::{Evaluated, Kind};
+use latex_expr_parser::TokenKind;
+use serde::Serialize;
+use std::f64::consts::FRAC_PI_2;
+
+/// Frames per step after the first one, so a step has `FRAMES + 1` of them.
+const FRAMES: usize = 16;
+
+/// One stage of a construction, e.g. sweeping `a` along `b`. The view plays `frames` in order
+/// and may tween between neighbouring ones, which show the same shapes in the same order.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Step {
+    pub caption: String,
+    pub frames: Vec<Vec<Shape>>,
+}
+
+/// How the node spanning `start..end` builds its value from its operands.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Construction {
+    pub start: usize,
+    pub end: usize,
+    pub steps: Vec<Step>,
+}
+
+/// The constructions of every node of `root` that has one, in evaluation order. Only operations
+/// with a familiar picture have one: sums, wedges, dots and geometric products of vectors and
+/// bivectors, and negation, duals, reverses and inverses of a single one.
+pub fn construct(root: &Evaluated) -> Vec<Construction> {
+    root.post_order()
+        .into_iter()
+        .filter_map(|node| {
+            let steps = steps(node)?;
+            Some(Construction {
+                start: node.start(),
+                end: node.end(),
+                steps,
+            })
+        })
+        .collect()
+}
+
+fn steps(node: &Evaluated) -> Option<Vec<Step>> {
+    node.meta.value?;
+    let operand = |i: usize| {
+        let value = node.children[i].value();
+        (value.grade(), value)
+    };
+    let zero = R300::zero();
+    let steps = match node.kind() {
+        Kind::BinaryOp(op) => {
+            let ((left_grade, a), (right_grade, b)) = (operand(0), operand(1));
+            match (op, left_grade?, right_grade?) {
+                (TokenKind::Plus, 1, 1) => tip_to_tail(a, b, "Place b at the tip of a"),
+                (TokenKind::Minus, 1, 1) => [
+                    vec![animate("Flip b", |s| {
+                        vec![
+                            Shape::arrow(Role::Left, zero, a),
+                            Shape::arrow(Role::Right, zero, lerp(b, -1.0 * b, s)),
+                        ]
+                    })],
+                    tip_to_tail(a, -1.0 * b, "Place -b at the tip of a"),
+                ]
+                .concat(),
+                (TokenKind::Wedge, 1, 1) => vec![sweep(a, b)],
+                (TokenKind::Wedge, 1, 2) => vec![sweep_box(factor(b)?, a, Role::Right)],
+                (TokenKind::Wedge, 2, 1) => vec![sweep_box(factor(a)?, b, Role::Left)],
+                (TokenKind::Dot, 1, 1) => vec![project(a, b)],
+                (TokenKind::Dot, 1, 2) => contract(a, b, 1.0, Role::Left)?,
+                (TokenKind::Dot, 2, 1) => contract(b, a, -1.0, Role::Right)?,
+                (TokenKind::Multiply, 1, 1) => vec![project(a, b), sweep(a, b)],
+                _ => return None,
+            }
+        }
+        Kind::UnaryOp(TokenKind::Minus) => match operand(0) {
+            (Some(1), a) => vec![animate("Flip the direction", |s| {
+                vec![Shape::arrow(Role::Result, zero, lerp(a, -1.0 * a, s))]
+            })],
+            _ => return None,
+        },
+        Kind::Inverse => match operand(0) {
+            (Some(1), a) => vec![animate("Scale by 1/|a|^2", |s| {
+                vec![
+                    Shape::arrow(Role::Operand, zero, a),
+                    Shape::arrow(Role::Result, zero, lerp(a, node.value(), s)),
+                ]
+            })],
+            _ => return None,
+        },
+        Kind::Dual => match operand(0) {
+            (Some(1), a) => {
+                let (u, v) = factor(node.value())?;
+                vec![animate("Grow the plane orthogonal to a", |s| {
+                    vec![
+                        Shape::arrow(Role::Operand, zero, a),
+                        Shape::parallelogram(Role::Result, s * u, s * v),
+                    ]
+                })]
+            }
+            (Some(2), b) => {
+                let (u, v) = factor(b)?;
+                vec![animate("Grow the normal of the plane", |s| {
+                    vec![
+                        Shape::parallelogram(Role::Operand, u, v),
+                        Shape::arrow(Role::Result, zero, s * node.value()),
+                    ]
+                })]
+            }
+            _ => return None,
+        },
+        Kind::Reverse => match operand(0) {
+            (Some(2), b) => {
+                let (u, v) = factor(b)?;
+                vec![animate("Swap the orientation", |s| {
+                    vec![Shape::parallelogram(Role::Result, u, lerp(v, -1.0 * v, s))]
+                })]
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(steps)
+}
+
+/// A step whose frames are `shapes` at evenly spaced times from 0 to 1.
+fn animate(caption: &str, shapes: impl Fn(f64) -> Vec<Shape>) -> Step {
+    Step {
+        caption: caption.to_string(),
+        frames: (0..=FRAMES)
+            .map(|i| shapes(i as f64 / FRAMES as f64))
+            .collect(),
+    }
+}
+
+fn tip_to_tail(a: R300, b: R300, caption: &str) -> Vec<Step> {
+    let zero = R300::zero();
+    vec![
+        animate(caption, |s| {
+            vec![
+                Shape::arrow(Role::Left, zero, a),
+                Shape::arrow(Role::Right, s * a, b),
+            ]
+        }),
+        animate("Draw the sum from the origin", |s| {
+            vec![
+                Shape::arrow(Role::Left, zero, a),
+                Shape::arrow(Role::Right, a, b),
+                Shape::arrow(Role::Result, zero, s * (a + b)),
+            ]
+        }),
+    ]
+}
+
+/// `a \wedge b` as `a` swept along `b`.
+fn sweep(a: R300, b: R300) -> Step {
+    let zero = R300::zero();
+    animate("Sweep a along b", |s| {
+        vec![
+            Shape::arrow(Role::Left, zero, a),
+            Shape::arrow(Role::Right, zero, b),
+            Shape::parallelogram(Role::Result, a, s * b),
+        ]
+    })
+}
+
+/// A bivector factored into `(u, v)` swept along the vector `w`, `role` being the vector's.
+fn sweep_box((u, v): (R300, R300), w: R300, role: Role) -> Step {
+    let plane = if role == Role::Left {
+        Role::Right
+    } else {
+        Role::Left
+    };
+    let zero = R300::zero();
+    animate("Sweep the plane along the vector", |s| {
+        vec![
+            Shape::parallelogram(plane, u, v),
+            Shape::arrow(role, zero, w),
+            Shape::parallelepiped(Role::Result, u, v, s * w),
+        ]
+    })
+}
+
+/// `a` dropped onto the line of `b`, whose length times `|b|` is `a \cdot b`.
+fn project(a: R300, b: R300) -> Step {
+    let zero = R300::zero();
+    let projected = match b.inverse() {
+        Some(inverse) => a.dot(b) * inverse,
+        None => zero,
+    };
+    animate("Project a onto b", |s| {
+        vec![
+            Shape::arrow(Role::Left, zero, lerp(a, projected, s)),
+            Shape::arrow(Role::Right, zero, b),
+            Shape::segment(Role::Guide, a, lerp(a, projected, s)),
+        ]
+    })
+}
+
+/// The contraction of the vector `a` by the bivector `blade`: `a` is projected into the plane,
+/// then turned a quarter turn in it and scaled by `|B|`. `sign` is -1 for `B \cdot a`, which
+/// turns the other way.
+fn contract(a: R300, blade: R300, sign: f64, role: Role) -> Option<Vec<Step>> {
+    let zero = R300::zero();
+    let ((u, v), inverse) = (factor(blade)?, blade.inverse()?);
+    let projected = a.dot(blade) * inverse;
+    let plane = if role == Role::Left {
+        Role::Right
+    } else {
+        Role::Left
+    };
+    let unit = (1.0 / blade.norm()) * blade;
+    Some(vec![
+        animate("Project a into the plane", |s| {
+            vec![
+                Shape::parallelogram(plane, u, v),
+                Shape::arrow(role, zero, lerp(a, projected, s)),
+                Shape::segment(Role::Guide, a, lerp(a, projected, s)),
+            ]
+        }),
+        animate("Turn a quarter turn in the plane and scale by |B|", |s| {
+            // p e^{s pi/2 B/|B|} |B|^s, a vector in the plane since p is in it.
+            let turn = (sign * s * FRAC_PI_2 * unit)
+                .exp()
+                .unwrap_or(R300::new(1.0, 0));
+            let turned = blade.norm().powf(s) * (projected * turn).grade_part(1);
+            vec![
+                Shape::parallelogram(plane, u, v),
+                Shape::arrow(Role::Result, zero, turned),
+            ]
+        }),
+    ])
+}
@@ -0,0 +1,240 @@
+use crate::r300::{basis, basis_count, basis_grades, R300};
+use serde::{Deserialize, Serialize};
+use serde_wasm_bindgen::{from_value, to_value};
+use std::fmt;
+use wasm_bindgen::prelude::*;
+
+/// One blade of a convention, `e31` is stored as the internal `e13` with sign -1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConventionBlade {
+    pub name: String,
+    pub index: usize,
+    pub sign: f64,
+}
+
+impl ConventionBlade {
+    pub fn grade(&self) -> usize {
+        basis_grades[self.index]
+    }
+
+    /// The vector indices in the name, `31` for `e_{31}` or `σ₃σ₁` and empty for the scalar.
+    pub fn indices(&self) -> String {
+        self.name
+            .chars()
+            .filter_map(|c| match c {
+                '₀'..='₉' => char::from_u32(c as u32 - '₀' as u32 + '0' as u32),
+                _ => c.is_ascii_digit().then_some(c),
+            })
+            .collect()
+    }
+}
+
+/// The order and orientation of the basis blades as a textbook writes them. Internally every
+/// value keeps the `basis` layout, a convention only changes how coefficients are read in,
+/// written out and displayed. Hestenes' `cyclic` convention uses `e23, e31, e12` so that the
+/// dual of `e3` is `+e12`.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<String>", into = "Vec<String>")]
+pub struct Convention {
+    blades: Vec<ConventionBlade>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConventionError {
+    /// The name is not a single basis blade, e.g. `e4` or `e1 + e2`.
+    InvalidBlade(String),
+    /// Two names for the same blade, e.g. `e13` and `e31`.
+    DuplicateBlade(String),
+    /// No name for this blade of `basis`.
+    MissingBlade(String),
+    /// A serialized value with a different number of components than basis blades.
+    ComponentCount(usize),
+}
+
+impl fmt::Display for ConventionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConventionError::InvalidBlade(name) => write!(f, "Not a basis blade: {}", name),
+            ConventionError::DuplicateBlade(name) => {
+                write!(f, "Basis blade named twice: {}", name)
+            }
+            ConventionError::MissingBlade(name) => write!(f, "Missing basis blade: {}", name),
+            ConventionError::ComponentCount(count) => {
+                write!(f, "Expected {} components, found {}", basis_count, count)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConventionError {}
+
+impl Convention {
+    /// One name per blade, in the order they should be listed. Names are read like multivector
+    /// literals, so `e31`, `e_{31}` and `\sigma_3\sigma_1` are all `-e13`, and `1` is the scalar.
+    pub fn from_names<S: AsRef<str>>(names: &[S]) -> Result<Self, ConventionError> {
+        let mut blades = Vec::with_capacity(basis_count);
+        for name in names {
+            let name = name.as_ref();
+            let invalid = || ConventionError::InvalidBlade(name.to_string());
+            let value = R300::parse(name).map_err(|_| invalid())?;
+            let index = (0..basis_count)
+                .find(|&i| value[i] != 0.0)
+                .ok_or_else(invalid)?;
+            if value[index].abs() != 1.0 || value != R300::new(value[index], index) {
+                return Err(invalid());
+            }
+            if blades
+                .iter()
+                .any(|blade: &ConventionBlade| blade.index == index)
+            {
+                return Err(ConventionError::DuplicateBlade(name.to_string()));
+            }
+            blades.push(ConventionBlade {
+                name: name.to_string(),
+                index,
+                sign: value[index],
+            });
+        }
+        if let Some(missing) = (0..basis_count).find(|&i| blades.iter().all(|b| b.index != i)) {
+            return Err(ConventionError::MissingBlade(basis[missing].to_string()));
+        }
+        Ok(Self { blades })
+    }
+
+    pub fn blades(&self) -> &[ConventionBlade] {
+        &self.blades
+    }
+
+    /// The blade naming `basis[index]`, every blade has one.
+    pub fn blade(&self, index: usize) -> &ConventionBlade {
+        self.blades
+            .iter()
+            .find(|blade| blade.index == index)
+            .unwrap()
+    }
+
+    /// Coefficients in this convention's order and orientation.
+    pub fn components(&self, value: &R300) -> Vec<f64> {
+        self.blades
+            .iter()
+            .map(|blade| blade.sign * value[blade.index])
+            .collect()
+    }
+
+    /// The inverse of `components`, None unless there is one coefficient per blade.
+    pub fn value(&self, components: &[f64]) -> Option<R300> {
+        if components.len() != basis_count {
+            return None;
+        }
+        let mut res = R300::zero();
+        for (blade, &coefficient) in self.blades.iter().zip(components) {
+            res = res + R300::new(blade.sign * coefficient, blade.index);
+        }
+        Some(res)
+    }
+
+    pub fn serialize(&self, value: &R300) -> ConventionalValue {
+        ConventionalValue {
+            basis: self.names(),
+            components: self.components(value),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Convention {
+    /// `1, e1, e2, e3, e12, e13, e23, e123`, the internal layout.
+    pub fn standard() -> Self {
+        Self::from_names(basis).unwrap()
+    }
+
+    /// `1, e1, e2, e3, e23, e31, e12, e123`, as in Hestenes.
+    pub fn cyclic() -> Self {
+        Self::from_names(&["1", "e1", "e2", "e3", "e23", "e31", "e12", "e123"]).unwrap()
+    }
+
+    /// The same order with the pseudoscalar written `e321`, so `I` has the opposite orientation.
+    #[wasm_bindgen(js_name = leftHanded)]
+    pub fn left_handed(&self) -> Self {
+        let mut res = self.clone();
+        for blade in res.blades.iter_mut().filter(|blade| blade.grade() == 3) {
+            blade.name = "e321".to_string();
+            blade.sign = -1.0;
+        }
+        res
+    }
+
+    #[wasm_bindgen(js_name = fromNames)]
+    pub fn from_js_names(names: JsValue) -> Result<Convention, JsError> {
+        let names: Vec<String> = from_value(names)?;
+        Ok(Self::from_names(&names)?)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.blades.iter().map(|blade| blade.name.clone()).collect()
+    }
+
+    #[wasm_bindgen(js_name = toComponents)]
+    pub fn to_components(&self, value: &R300) -> Vec<f64> {
+        self.components(value)
+    }
+
+    #[wasm_bindgen(js_name = fromComponents)]
+    pub fn from_components(&self, components: Vec<f64>) -> Result<R300, JsError> {
+        self.value(&components)
+            .ok_or_else(|| JsError::new("Expected one component per basis blade"))
+    }
+
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self, value: &R300) -> JsValue {
+        to_value(&self.serialize(value)).unwrap()
+    }
+
+    /// Reads a value written by `toJson` under any convention.
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: JsValue) -> Result<R300, JsError> {
+        let value: ConventionalValue = from_value(json)?;
+        Ok(R300::try_from(value)?)
+    }
+}
+
+impl Default for Convention {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl TryFrom<Vec<String>> for Convention {
+    type Error = ConventionError;
+
+    fn try_from(names: Vec<String>) -> Result<Self, Self::Error> {
+        Self::from_names(&names)
+    }
+}
+
+impl From<Convention> for Vec<String> {
+    fn from(convention: Convention) -> Self {
+        convention.names()
+    }
+}
+
+/// A value written in a named basis, `{"basis": ["1", "e1", ..., "e31", ...], "components":
+/// [...]}`. The basis travels with the components, so a value saved under one convention reads
+/// back correctly under any other.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConventionalValue {
+    pub basis: Vec<String>,
+    pub components: Vec<f64>,
+}
+
+impl TryFrom<ConventionalValue> for R300 {
+    type Error = ConventionError;
+
+    fn try_from(value: ConventionalValue) -> Result<Self, Self::Error> {
+        let convention = Convention::from_names(&value.basis)?;
+        convention
+            .value(&value.components)
+            .ok_or(ConventionError::ComponentCount(value.components.len()))
+    }
+}
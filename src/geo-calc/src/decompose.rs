@@ -0,0 +1,135 @@
+use crate::format::{format_coefficient, FormatOptions};
+use crate::primitive::{factor, point, Primitive};
+use crate::r300::{R300, TOLERANCE};
+use crate::scene::{color, describe, Item, SceneOptions};
+use serde::Serialize;
+
+const GRADE_NAMES: [&str; 4] = ["scalar", "vector", "bivector", "trivector"];
+
+/// The part of a multivector of one grade and how it is drawn on its own.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Part {
+    pub grade: usize,
+    pub value: R300,
+    pub item: Item,
+}
+
+/// A sum of a scalar and a bivector read as `|R| (cos(angle) + sin(angle) I)` for the unit
+/// bivector `I`, e.g. `a b` with `angle` the angle from `a` to `b` and `I` their plane. As a
+/// rotor, `R x \tilde{R}` turns vectors by `-2 angle` in `I`, that is by `rotation` in `-I`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Spinor {
+    pub magnitude: f64,
+    pub angle: f64,
+    pub plane: R300,
+    pub rotation: f64,
+}
+
+/// One line of the key next to the picture.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LegendEntry {
+    pub color: String,
+    /// LaTeX, e.g. `\text{bivector, area } 2`.
+    pub text: String,
+}
+
+/// A multivector split into parts that can each be drawn.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Decomposition {
+    /// The nonzero grade parts, lowest grade first.
+    pub parts: Vec<Part>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spinor: Option<Spinor>,
+    /// The angle of a spinor as an arc in its plane, from a vector to the one it makes that
+    /// angle with.
+    pub shapes: Vec<Primitive>,
+    pub legend: Vec<LegendEntry>,
+}
+
+impl Decomposition {
+    /// Every shape of the parts and of the spinor, for drawing the whole value at once.
+    pub fn all_shapes(&self) -> Vec<Primitive> {
+        self.parts
+            .iter()
+            .flat_map(|part| part.item.shapes.iter().copied())
+            .chain(self.shapes.iter().copied())
+            .collect()
+    }
+}
+
+/// Splits `value` by grade, the part of grade `k` named `⟨name⟩_k`, and recognizes spinors.
+pub fn decompose(name: &str, value: R300, options: &SceneOptions) -> Decomposition {
+    let format = FormatOptions::default();
+    let number = |x: f64| format_coefficient(x, &format);
+    let parts = (0..=3)
+        .map(|grade| (grade, value.grade_part(grade)))
+        .filter(|(_, part)| part.norm() > TOLERANCE)
+        .map(|(grade, part)| {
+            let name = format!("\\langle {} \\rangle_{}", name, grade);
+            Part {
+                grade,
+                value: part,
+                item: describe(&name, part, options),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut legend = parts
+        .iter()
+        .map(|part| {
+            let measure = match part.grade {
+                0 => number(part.value[0]),
+                1 => format!("\\text{{length }} {}", number(part.value.norm())),
+                2 => format!("\\text{{area }} {}", number(part.value.norm())),
+                _ => format!("\\text{{volume }} {}", number(part.value[7])),
+            };
+            LegendEntry {
+                color: part.item.color.clone(),
+                text: format!("\\text{{{}, }} {}", GRADE_NAMES[part.grade], measure),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let spinor = spinor(value);
+    let mut shapes = Vec::new();
+    if let Some(spinor) = &spinor {
+        if let Some((u, v)) = factor(spinor.plane) {
+            shapes.push(Primitive::Arc {
+                center: [0.0; 3],
+                u: point(u),
+                v: point(v.normalized()),
+                angle: spinor.angle,
+            });
+        }
+        legend.push(LegendEntry {
+            color: color(None).to_string(),
+            text: format!(
+                "\\text{{spinor, angle }} {} \\text{{ in }} {}",
+                number(spinor.angle),
+                spinor.plane.to_latex()
+            ),
+        });
+    }
+    Decomposition {
+        parts,
+        spinor,
+        shapes,
+        legend,
+    }
+}
+
+/// `value` as a spinor, None unless it has a nonzero bivector part and nothing besides it and
+/// a scalar.
+pub fn spinor(value: R300) -> Option<Spinor> {
+    let blade = value.grade_part(2);
+    if (value - value[0] - blade).norm() > TOLERANCE || blade.norm() < TOLERANCE {
+        return None;
+    }
+    let angle = blade.norm().atan2(value[0]);
+    Some(Spinor {
+        magnitude: value.norm(),
+        angle,
+        plane: blade.normalized(),
+        rotation: 2.0 * angle,
+    })
+}
@@ -0,0 +1,443 @@
+use crate::format::{blade_name, format_coefficient, format_multivector, FormatOptions};
+use crate::r300::{basis, basis_count, R300, TOLERANCE};
+use crate::{arguments, Evaluated, Kind};
+use latex_expr_parser::TokenKind;
+use serde::{Deserialize, Serialize};
+
+/// A worked step for one node: the rule that produced its value, written in LaTeX, followed by
+/// the same rule with the children's numbers substituted and the final value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema, ts_rs::TS))]
+#[cfg_attr(test, ts(export_to = "../../components/generated/"))]
+pub struct Derivation {
+    pub rule: String,
+    pub steps: Vec<String>,
+}
+
+/// Fills in the derivation of `node` and of everything below it. Nodes without a value get none.
+pub fn derive(node: &mut Evaluated, options: &FormatOptions) {
+    for child in node.children.iter_mut() {
+        derive(child, options);
+    }
+    node.meta.derivation = node.meta.value.and_then(|_| derivation(node, options));
+}
+
+fn derivation(node: &Evaluated, options: &FormatOptions) -> Option<Derivation> {
+    let value = node.value();
+    let operand = node.children.first();
+    let (left, right) = (node.children.first(), node.children.get(1));
+    match node.kind() {
+        Kind::Identifier(name) | Kind::Constant(name) => Some(Derivation {
+            rule: format!("{} = {}", name, latex(value, options)),
+            steps: vec![],
+        }),
+        Kind::BinaryOp(op) => binary_derivation(op, left?, right?, value, options),
+        Kind::UnaryOp(TokenKind::Minus) => Some(Derivation {
+            rule: format!("-{} = (-1) {}", label(operand?, "A"), label(operand?, "A")),
+            steps: vec![result(value, options)],
+        }),
+        Kind::Reverse => Some(Derivation {
+            rule: format!(
+                "\\tilde{{{0}}} = \\sum_k (-1)^{{k(k-1)/2}} \\langle {0} \\rangle_k",
+                label(operand?, "A")
+            ),
+            steps: vec![result(value, options)],
+        }),
+        Kind::Dual => {
+            // A^* = A e_{123}, which is -A e_{321} in a left handed convention.
+            let (negate, pseudoscalar) = blade_name(options.convention.blade(7), options);
+            let sign = if negate { "-" } else { "" };
+            Some(Derivation {
+                rule: format!("{0}^* = {1}{0} {2}", label(operand?, "A"), sign, pseudoscalar),
+                steps: vec![
+                    format!(
+                        "= {}({}) {}",
+                        sign,
+                        latex(operand?.value(), options),
+                        pseudoscalar
+                    ),
+                    result(value, options),
+                ],
+            })
+        }
+        Kind::Conjugate => Some(Derivation {
+            rule: format!(
+                "\\bar{{{0}}} = \\sum_k (-1)^{{k(k+1)/2}} \\langle {0} \\rangle_k",
+                label(operand?, "A")
+            ),
+            steps: vec![result(value, options)],
+        }),
+        Kind::Involute => Some(Derivation {
+            rule: format!(
+                "\\hat{{{0}}} = \\sum_k (-1)^k \\langle {0} \\rangle_k",
+                label(operand?, "A")
+            ),
+            steps: vec![result(value, options)],
+        }),
+        Kind::Inverse => {
+            let operand = operand?;
+            let conjugate = operand.value().Conjugate();
+            Some(Derivation {
+                rule: format!(
+                    "{0}^{{-1}} = \\bar{{{0}}} ({0} \\bar{{{0}}})^{{-1}}",
+                    label(operand, "A")
+                ),
+                steps: vec![
+                    format!(
+                        "= ({}) ({})^{{-1}}",
+                        latex(conjugate, options),
+                        latex(operand.value() * conjugate, options)
+                    ),
+                    result(value, options),
+                ],
+            })
+        }
+        Kind::Power(0) => Some(Derivation {
+            rule: format!("{}^{{0}} = 1", label(operand?, "A")),
+            steps: vec![],
+        }),
+        Kind::Power(exponent) => {
+            // A negative power multiplies the inverse.
+            let repeated = match exponent {
+                n if n < 0 => format!("{}^{{-1}}", label(operand?, "A")),
+                _ => label(operand?, "A"),
+            };
+            Some(Derivation {
+                rule: format!(
+                    "{}^{{{}}} = \\underbrace{{{2} \\cdots {2}}}_{{{3}}}",
+                    label(operand?, "A"),
+                    exponent,
+                    repeated,
+                    exponent.unsigned_abs()
+                ),
+                steps: vec![result(value, options)],
+            })
+        }
+        Kind::Magnitude | Kind::Area => Some(Derivation {
+            rule: format!(
+                "|{0}| = \\sqrt{{\\langle {0} \\tilde{{{0}}} \\rangle_0}} = \\sqrt{{\\sum_k {0}_k^2}}",
+                label(operand?, "A")
+            ),
+            steps: vec![
+                format!("= \\sqrt{{{}}}", sum_of_squares(operand?.value(), options)),
+                result(value, options),
+            ],
+        }),
+        Kind::Angle => {
+            let (left, right) = (left?, right?);
+            Some(Derivation {
+                rule: format!(
+                    "\\theta = \\arccos \\frac{{\\langle {0} \\tilde{{{1}}} \\rangle_0}}{{|{0}| |{1}|}}",
+                    label(left, "A"),
+                    label(right, "B")
+                ),
+                steps: vec![
+                    format!(
+                        "= \\arccos \\frac{{{}}}{{{} \\cdot {}}}",
+                        num((left.value() * right.value().Reverse())[0], options),
+                        num(left.value().norm(), options),
+                        num(right.value().norm(), options)
+                    ),
+                    result(value, options),
+                ],
+            })
+        }
+        Kind::FunctionCall(name) => {
+            function_derivation(name, arguments(node.node, &node.children), value, options)
+        }
+        Kind::Equation { holds } => {
+            let (left, right) = (left?, right?);
+            Some(Derivation {
+                rule: format!(
+                    "{0} = {1} \\iff {0} - {1} = 0",
+                    label(left, "L"),
+                    label(right, "R")
+                ),
+                steps: vec![
+                    format!("= ({}) - ({})", latex(left.value(), options), latex(right.value(), options)),
+                    result(value, options),
+                    if holds {
+                        "\\text{the identity holds}".to_string()
+                    } else {
+                        "\\text{the identity does not hold}".to_string()
+                    },
+                ],
+            })
+        }
+        // Integers need no working, and kinds without a rule yet simply show their value.
+        _ => None,
+    }
+}
+
+fn binary_derivation(
+    op: TokenKind,
+    left: &Evaluated,
+    right: &Evaluated,
+    value: R300,
+    options: &FormatOptions,
+) -> Option<Derivation> {
+    let (a, b) = (label(left, "A"), label(right, "B"));
+    let (l, r) = (left.value(), right.value());
+    match op {
+        TokenKind::Plus | TokenKind::Minus => {
+            let sign = if op == TokenKind::Plus { "+" } else { "-" };
+            // Coefficients are those of the convention's blades, `e_{31}` having minus `e13`'s.
+            let terms = options
+                .convention
+                .blades()
+                .iter()
+                .filter(|blade| l[blade.index] != 0.0 || r[blade.index] != 0.0)
+                .map(|blade| {
+                    let (negate, name) = blade_name(blade, options);
+                    let sign_of = if negate { -1.0 } else { 1.0 };
+                    let (l, r) = (sign_of * l[blade.index], sign_of * r[blade.index]);
+                    format!(
+                        "({} {} {}) {}",
+                        num(l, options),
+                        sign,
+                        factor(r, options),
+                        name
+                    )
+                    .trim_end()
+                    .to_string()
+                })
+                .collect::<Vec<_>>();
+            Some(Derivation {
+                rule: format!("{a} {sign} {b} = \\sum_k ({a}_k {sign} {b}_k) e_k"),
+                steps: vec![format!("= {}", join_terms(terms)), result(value, options)],
+            })
+        }
+        TokenKind::Dot if l.is_vector() && r.is_vector() => Some(Derivation {
+            rule: format!("{a} \\cdot {b} = {a}_1 {b}_1 + {a}_2 {b}_2 + {a}_3 {b}_3"),
+            steps: vec![
+                format!(
+                    "= {} \\cdot {} + {} \\cdot {} + {} \\cdot {}",
+                    num(l[1], options),
+                    factor(r[1], options),
+                    factor(l[2], options),
+                    factor(r[2], options),
+                    factor(l[3], options),
+                    factor(r[3], options)
+                ),
+                result(value, options),
+            ],
+        }),
+        TokenKind::Dot if l.is_vector() => match (right.kind(), right.children.as_slice()) {
+            (Kind::BinaryOp(TokenKind::Wedge), [b_node, c_node])
+                if b_node.value().is_vector() && c_node.value().is_vector() =>
+            {
+                let (b, c) = (label(b_node, "b"), label(c_node, "c"));
+                let (ab, ac) = (l.dot(b_node.value())[0], l.dot(c_node.value())[0]);
+                Some(Derivation {
+                    rule: format!(
+                        "{a} \\cdot ({b} \\wedge {c}) = ({a} \\cdot {b}){c} - ({a} \\cdot {c}){b}"
+                    ),
+                    steps: vec![
+                        format!(
+                            "= {} ({}) - {} ({})",
+                            num(ab, options),
+                            latex(c_node.value(), options),
+                            factor(ac, options),
+                            latex(b_node.value(), options)
+                        ),
+                        result(value, options),
+                    ],
+                })
+            }
+            _ => generic_product(
+                format!("{a} \\cdot {b} = \\langle {a} {b} \\rangle_{{|r - s|}}"),
+                value,
+                options,
+            ),
+        },
+        TokenKind::Dot => generic_product(
+            format!("{a} \\cdot {b} = \\langle {a} {b} \\rangle_{{|r - s|}}"),
+            value,
+            options,
+        ),
+        TokenKind::Wedge if l.is_vector() && r.is_vector() => {
+            let planes = options
+                .convention
+                .blades()
+                .iter()
+                .filter(|blade| blade.grade() == 2)
+                .map(|blade| {
+                    let (negate, name) = blade_name(blade, options);
+                    // `e_{31}` is `e3 \wedge e1`, so its coefficient has the indices swapped.
+                    let digits = basis[blade.index].as_bytes();
+                    let (i, j) = ((digits[1] - b'0') as usize, (digits[2] - b'0') as usize);
+                    let (i, j) = if negate { (j, i) } else { (i, j) };
+                    (i, j, name)
+                })
+                .collect::<Vec<_>>();
+            Some(Derivation {
+                rule: format!(
+                    "{a} \\wedge {b} = {}",
+                    planes
+                        .iter()
+                        .map(|(i, j, name)| format!("({a}_{i} {b}_{j} - {a}_{j} {b}_{i}) {name}"))
+                        .collect::<Vec<_>>()
+                        .join(" + ")
+                ),
+                steps: vec![
+                    format!(
+                        "= {}",
+                        planes
+                            .iter()
+                            .map(|(i, j, name)| wedge_component(l, r, *i, *j, name, options))
+                            .collect::<Vec<_>>()
+                            .join(" + ")
+                    ),
+                    result(value, options),
+                ],
+            })
+        }
+        TokenKind::Wedge => generic_product(
+            format!("{a} \\wedge {b} = \\langle {a} {b} \\rangle_{{r + s}}"),
+            value,
+            options,
+        ),
+        TokenKind::Multiply if l.is_vector() && r.is_vector() => Some(Derivation {
+            rule: format!("{a} {b} = {a} \\cdot {b} + {a} \\wedge {b}"),
+            steps: vec![
+                format!(
+                    "= {} + ({})",
+                    num(l.dot(r)[0], options),
+                    latex(l.wedge(r), options)
+                ),
+                result(value, options),
+            ],
+        }),
+        TokenKind::Multiply => generic_product(
+            format!("{a} {b} = \\sum_{{j,k}} {a}_j {b}_k e_j e_k"),
+            value,
+            options,
+        ),
+        TokenKind::Frac => {
+            let inverse = r.inverse()?;
+            Some(Derivation {
+                rule: format!("\\frac{{{a}}}{{{b}}} = {a} {b}^{{-1}}"),
+                steps: vec![
+                    format!("= ({}) ({})", latex(l, options), latex(inverse, options)),
+                    result(value, options),
+                ],
+            })
+        }
+        _ => None,
+    }
+}
+
+fn function_derivation(
+    name: &str,
+    args: &[Evaluated],
+    value: R300,
+    options: &FormatOptions,
+) -> Option<Derivation> {
+    match (name, args) {
+        // The closed form divides by |B|, which a zero bivector does not have.
+        ("exp", [arg]) if arg.value().is_bivector() && arg.value().norm() <= TOLERANCE => {
+            Some(Derivation {
+                rule: format!("e^{{{}}} = e^{{0}} = 1", label(arg, "B")),
+                steps: vec![],
+            })
+        }
+        ("exp", [arg]) if arg.value().is_bivector() => {
+            let b = label(arg, "B");
+            let theta = arg.value().norm();
+            Some(Derivation {
+                rule: format!("e^{{{b}}} = \\cos|{b}| + \\frac{{{b}}}{{|{b}|}} \\sin|{b}|"),
+                steps: vec![
+                    format!(
+                        "= \\cos {0} + \\frac{{{1}}}{{{0}}} \\sin {0}",
+                        num(theta, options),
+                        latex(arg.value(), options)
+                    ),
+                    result(value, options),
+                ],
+            })
+        }
+        _ => Some(Derivation {
+            rule: format!(
+                "\\operatorname{{{}}}({})",
+                name,
+                args.iter()
+                    .map(|arg| latex(arg.value(), options))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            steps: vec![result(value, options)],
+        }),
+    }
+}
+
+fn generic_product(rule: String, value: R300, options: &FormatOptions) -> Option<Derivation> {
+    Some(Derivation {
+        rule,
+        steps: vec![result(value, options)],
+    })
+}
+
+fn wedge_component(
+    l: R300,
+    r: R300,
+    i: usize,
+    j: usize,
+    name: &str,
+    options: &FormatOptions,
+) -> String {
+    format!(
+        "({} \\cdot {} - {} \\cdot {}) {}",
+        num(l[i], options),
+        factor(r[j], options),
+        factor(l[j], options),
+        factor(r[i], options),
+        name
+    )
+}
+
+fn sum_of_squares(value: R300, options: &FormatOptions) -> String {
+    let terms = (0..basis_count)
+        .filter(|&i| value[i] != 0.0)
+        .map(|i| format!("{}^2", factor(value[i], options)))
+        .collect::<Vec<_>>();
+    if terms.is_empty() {
+        "0".to_string()
+    } else {
+        terms.join(" + ")
+    }
+}
+
+/// Identifiers are shown by name, anything else by a placeholder letter.
+fn label(node: &Evaluated, placeholder: &str) -> String {
+    match node.kind() {
+        Kind::Identifier(name) | Kind::Constant(name) => name.to_string(),
+        _ => placeholder.to_string(),
+    }
+}
+
+fn result(value: R300, options: &FormatOptions) -> String {
+    format!("= {}", latex(value, options))
+}
+
+fn latex(value: R300, options: &FormatOptions) -> String {
+    format_multivector(&value, options)
+}
+
+fn join_terms(terms: Vec<String>) -> String {
+    if terms.is_empty() {
+        return "0".to_string();
+    }
+    terms.join(" + ").replace("+ -", "- ")
+}
+
+fn num(x: f64, options: &FormatOptions) -> String {
+    format_coefficient(x, options)
+}
+
+fn factor(x: f64, options: &FormatOptions) -> String {
+    if num(x, options).starts_with('-') {
+        format!("({})", num(x, options))
+    } else {
+        num(x, options)
+    }
+}
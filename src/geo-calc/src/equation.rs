@@ -0,0 +1,88 @@
+use crate::error::EvalError;
+use crate::r300::{basis_grades, R300, TOLERANCE};
+use crate::rng::Rng;
+use crate::symbols::ReservedSymbols;
+use crate::{calculate_ast_expression, find_ast_identifiers, Kind};
+use latex_expr_parser::ASTNode;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Looser than `TOLERANCE` since both sides of an equation carry their own rounding.
+pub const DEFAULT_TOLERANCE: f64 = 1e3 * TOLERANCE;
+
+/// Whether `left = right` up to `tolerance`, relative to the size of the two sides so that
+/// identities between large values are not failed by rounding.
+pub fn holds(left: R300, right: R300, tolerance: f64) -> bool {
+    (left - right).norm() <= tolerance * (1.0 + left.norm().max(right.norm()))
+}
+
+/// Evidence that an equation holds for all values of its variables, not only the shown ones.
+#[derive(Debug, Serialize)]
+pub struct IdentityCheck {
+    pub trials: usize,
+    pub failures: usize,
+    pub max_residual: f64,
+    pub holds: bool,
+    pub counterexample: Option<HashMap<String, R300>>,
+}
+
+/// Evaluates the equation `ast` under `trials` random assignments. Each variable keeps the
+/// grades it has in `vars` (a vector when it is missing), so `a \cdot B` stays a vector dotted
+/// with a bivector.
+pub fn check_identity(
+    ast: &ASTNode,
+    vars: &HashMap<String, R300>,
+    symbols: &ReservedSymbols,
+    trials: usize,
+    seed: u64,
+) -> Result<IdentityCheck, EvalError> {
+    let mut identifiers = Vec::new();
+    find_ast_identifiers(ast, symbols, &mut identifiers);
+    let mut rng = Rng::new(seed);
+    let mut check = IdentityCheck {
+        trials,
+        failures: 0,
+        max_residual: 0.0,
+        holds: true,
+        counterexample: None,
+    };
+
+    for _ in 0..trials {
+        let assignment = identifiers
+            .iter()
+            .map(|name| {
+                let template = vars
+                    .get(name)
+                    .copied()
+                    .filter(|value| *value != R300::zero())
+                    .unwrap_or(R300::vector(1.0, 1.0, 1.0));
+                (name.clone(), random_like(template, &mut rng))
+            })
+            .collect::<HashMap<_, _>>();
+        let result = calculate_ast_expression(ast, &assignment, symbols)?;
+        let Kind::Equation { holds } = result.kind() else {
+            return Err(EvalError::NotAnEquation {
+                start: ast.start,
+                end: ast.end,
+            });
+        };
+        check.max_residual = check.max_residual.max(result.value().norm());
+        if !holds {
+            check.failures += 1;
+            check.holds = false;
+            check.counterexample.get_or_insert(assignment);
+        }
+    }
+    Ok(check)
+}
+
+/// A random multivector with components in [-1, 1) on the grades `template` has.
+fn random_like(template: R300, rng: &mut Rng) -> R300 {
+    let mut res = R300::zero();
+    for (i, &grade) in basis_grades.iter().enumerate() {
+        if template.grade_part(grade) != R300::zero() {
+            res[i] = rng.range(-1.0, 1.0);
+        }
+    }
+    res
+}
@@ -0,0 +1,93 @@
+use latex_expr_parser::TokenKind;
+use serde::Serialize;
+use std::fmt;
+
+/// Why an expression could not be evaluated. Spans point into the LaTeX source so the UI can
+/// highlight the offending part.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum EvalError {
+    UnboundIdentifier {
+        name: String,
+        start: usize,
+        end: usize,
+    },
+    UnsupportedOperator {
+        op: TokenKind,
+        start: usize,
+        end: usize,
+    },
+    NotInvertible {
+        start: usize,
+        end: usize,
+    },
+    InvalidExponent {
+        start: usize,
+        end: usize,
+    },
+    UnknownFunction {
+        name: String,
+        start: usize,
+        end: usize,
+    },
+    InvalidArgument {
+        function: String,
+        reason: String,
+        start: usize,
+        end: usize,
+    },
+    NotAnEquation {
+        start: usize,
+        end: usize,
+    },
+    NotSymbolic {
+        start: usize,
+        end: usize,
+    },
+    NotExact {
+        start: usize,
+        end: usize,
+    },
+}
+
+impl EvalError {
+    /// The part of the source to highlight.
+    pub fn span(&self) -> (usize, usize) {
+        match self {
+            EvalError::UnboundIdentifier { start, end, .. }
+            | EvalError::UnsupportedOperator { start, end, .. }
+            | EvalError::NotInvertible { start, end }
+            | EvalError::InvalidExponent { start, end }
+            | EvalError::UnknownFunction { start, end, .. }
+            | EvalError::InvalidArgument { start, end, .. }
+            | EvalError::NotAnEquation { start, end }
+            | EvalError::NotSymbolic { start, end }
+            | EvalError::NotExact { start, end } => (*start, *end),
+        }
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::UnboundIdentifier { name, .. } => write!(f, "Unbound identifier: {}", name),
+            EvalError::UnsupportedOperator { op, .. } => {
+                write!(f, "Unsupported operator: {:?}", op)
+            }
+            EvalError::NotInvertible { .. } => write!(f, "Multivector is not invertible"),
+            EvalError::InvalidExponent { .. } => {
+                write!(f, "Exponent must be an integer scalar")
+            }
+            EvalError::UnknownFunction { name, .. } => write!(f, "Unknown function: {}", name),
+            EvalError::InvalidArgument {
+                function, reason, ..
+            } => write!(f, "Invalid argument to {}: {}", function, reason),
+            EvalError::NotAnEquation { .. } => {
+                write!(f, "Expected an equation with `=` at the top level")
+            }
+            EvalError::NotSymbolic { .. } => write!(f, "Cannot be expanded symbolically"),
+            EvalError::NotExact { .. } => write!(f, "Cannot be evaluated with exact fractions"),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
@@ -0,0 +1,153 @@
+use crate::convention::Convention;
+use crate::error::EvalError;
+use crate::expand::Expander;
+use crate::format::blade_latex;
+use crate::multivector::Multivector;
+use crate::r300::{basis_count, R300};
+use crate::scalar::Scalar;
+use crate::symbols::ReservedSymbols;
+use latex_expr_parser::ASTNode;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{Signed, ToPrimitive};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// One basis blade of an exact result, e.g. `e12` with coefficient `-1/3`.
+#[derive(Debug, Serialize)]
+pub struct ExactBlade {
+    pub blade: String,
+    pub numerator: String,
+    pub denominator: String,
+    pub latex: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExactResult {
+    pub blades: Vec<ExactBlade>,
+    pub latex: String,
+    /// The same result rounded to `f64`.
+    pub value: R300,
+}
+
+impl ExactResult {
+    /// `value` written in `convention`'s blades, `e31` having minus the coefficient of `e13`.
+    pub fn new(value: Multivector<BigRational>, convention: &Convention) -> Self {
+        let nonzero = convention
+            .blades()
+            .iter()
+            .filter(|blade| !Scalar::is_zero(&value[blade.index]))
+            .map(|blade| {
+                let coefficient = value[blade.index].clone();
+                let coefficient = if blade.sign < 0.0 {
+                    -coefficient
+                } else {
+                    coefficient
+                };
+                (blade, coefficient)
+            })
+            .collect::<Vec<_>>();
+        let blades = nonzero
+            .iter()
+            .map(|(blade, coefficient)| ExactBlade {
+                blade: blade.name.clone(),
+                numerator: coefficient.numer().to_string(),
+                denominator: coefficient.denom().to_string(),
+                latex: rational_latex(coefficient),
+            })
+            .collect();
+
+        let mut latex = String::new();
+        for (n, (blade, coefficient)) in nonzero.iter().enumerate() {
+            let sign = match (n, coefficient.is_negative()) {
+                (0, true) => "-",
+                (0, false) => "",
+                (_, true) => " - ",
+                (_, false) => " + ",
+            };
+            let magnitude = coefficient.abs();
+            let term = match (blade.grade(), num_traits::One::is_one(&magnitude)) {
+                (0, _) => rational_latex(&magnitude),
+                (_, true) => blade_latex(blade),
+                (_, false) => format!("{} {}", rational_latex(&magnitude), blade_latex(blade)),
+            };
+            latex.push_str(sign);
+            latex.push_str(&term);
+        }
+
+        ExactResult {
+            blades,
+            latex: if latex.is_empty() {
+                "0".to_string()
+            } else {
+                latex
+            },
+            value: value.map(|c| c.to_f64().unwrap_or(f64::NAN)).into(),
+        }
+    }
+}
+
+fn rational_latex(value: &BigRational) -> String {
+    let sign = if value.is_negative() { "-" } else { "" };
+    if value.is_integer() {
+        value.numer().to_string()
+    } else {
+        format!(
+            "{}\\frac{{{}}}{{{}}}",
+            sign,
+            value.numer().abs(),
+            value.denom()
+        )
+    }
+}
+
+/// The rational written by `value`'s shortest decimal form, so `0.1` is exactly `1/10` rather
+/// than the nearest binary fraction. None for infinities and NaN.
+pub fn to_rational(value: f64) -> Option<BigRational> {
+    if !value.is_finite() {
+        return None;
+    }
+    let formatted = value.to_string();
+    let (whole, fraction) = formatted.split_once('.').unwrap_or((&formatted, ""));
+    let numerator: BigInt = format!("{}{}", whole, fraction).parse().ok()?;
+    let denominator = BigInt::from(10).pow(fraction.len() as u32);
+    Some(BigRational::new(numerator, denominator))
+}
+
+/// Evaluates `ast` with rational coefficients, so `\frac{1}{3}` stays `1/3` and products of
+/// fractions never pick up rounding error. Variable values are read as the decimals they print
+/// as. Only the operations of `expand` are available, norms and functions are not exact. The
+/// result is written in `convention`'s blades.
+pub fn calculate_exact(
+    ast: &ASTNode,
+    vars: &HashMap<String, R300>,
+    symbols: &ReservedSymbols,
+    convention: &Convention,
+) -> Result<ExactResult, EvalError> {
+    let identifier = |node: &ASTNode, name: &str| {
+        let value = symbols
+            .get(name)
+            .or_else(|| vars.get(name).copied())
+            .ok_or_else(|| EvalError::UnboundIdentifier {
+                name: name.to_string(),
+                start: node.start,
+                end: node.end,
+            })?;
+        let mut res = Multivector::zero();
+        for i in 0..basis_count {
+            res[i] = to_rational(value[i]).ok_or(EvalError::NotExact {
+                start: node.start,
+                end: node.end,
+            })?;
+        }
+        Ok(res)
+    };
+    let expander = Expander {
+        identifier: &identifier,
+        unsupported: |start, end| EvalError::NotExact { start, end },
+    };
+    Ok(ExactResult::new(
+        expander.expand_expression(ast)?,
+        convention,
+    ))
+}
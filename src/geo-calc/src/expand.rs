@@ -0,0 +1,77 @@
+use crate::error::EvalError;
+use crate::multivector::Multivector;
+use crate::r300::basis_count;
+use crate::scalar::Scalar;
+use latex_expr_parser::{ASTNode, ASTNodeType, TokenKind};
+
+type Resolve<'a, S> = dyn Fn(&ASTNode, &str) -> Result<Multivector<S>, EvalError> + 'a;
+
+/// Evaluates an AST over any `Scalar`, for the modes where coefficients are not plain `f64`.
+/// Only operations that stay inside the scalar type are supported, so there are no norms or
+/// transcendental functions here.
+pub struct Expander<'a, S> {
+    /// Resolves an identifier node to its value.
+    pub identifier: &'a Resolve<'a, S>,
+    /// The error for a node this mode cannot evaluate.
+    pub unsupported: fn(usize, usize) -> EvalError,
+}
+
+impl<S: Scalar> Expander<'_, S> {
+    /// A top-level `=` expands to `left - right`, which is zero for an identity.
+    pub fn expand_expression(&self, ast: &ASTNode) -> Result<Multivector<S>, EvalError> {
+        match &ast.r#type {
+            ASTNodeType::BinaryOpNode {
+                op: TokenKind::Equals,
+                left,
+                right,
+            } => Ok(self.expand(left)? - self.expand(right)?),
+            _ => self.expand(ast),
+        }
+    }
+
+    pub fn expand(&self, ast: &ASTNode) -> Result<Multivector<S>, EvalError> {
+        let unsupported = || (self.unsupported)(ast.start, ast.end);
+        match &ast.r#type {
+            ASTNodeType::Int { value } => Ok(Multivector::new(S::from_integer(*value), 0)),
+            ASTNodeType::Identifier { name } => (self.identifier)(ast, name),
+            ASTNodeType::BinaryOpNode { op, left, right } => {
+                let l = self.expand(left)?;
+                let r = self.expand(right)?;
+                match op {
+                    TokenKind::Plus => Ok(l + r),
+                    TokenKind::Minus => Ok(l - r),
+                    TokenKind::Multiply => Ok(l * r),
+                    TokenKind::Dot => Ok(l | r),
+                    TokenKind::Wedge => Ok(l ^ r),
+                    TokenKind::Frac => Ok(l * r.inverse().ok_or_else(unsupported)?),
+                    TokenKind::Caret => {
+                        let n = scalar(&r)
+                            .and_then(|n| n.to_integer())
+                            .ok_or_else(unsupported)?;
+                        l.pow(n).ok_or_else(unsupported)
+                    }
+                    _ => Err(unsupported()),
+                }
+            }
+            ASTNodeType::UnaryOpNode { op, operand } => {
+                let value = self.expand(operand)?;
+                match op {
+                    TokenKind::Plus => Ok(value),
+                    TokenKind::Minus => Ok(-value),
+                    TokenKind::Dagger | TokenKind::Tilde => Ok(value.Reverse()),
+                    TokenKind::Star => Ok(value.Dual()),
+                    TokenKind::Bar => Ok(value.Conjugate()),
+                    TokenKind::Hat => Ok(value.Involute()),
+                    _ => Err(unsupported()),
+                }
+            }
+            _ => Err(unsupported()),
+        }
+    }
+}
+
+fn scalar<S: Scalar>(value: &Multivector<S>) -> Option<&S> {
+    (1..basis_count)
+        .all(|i| value[i].is_zero())
+        .then_some(&value[0])
+}
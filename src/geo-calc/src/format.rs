@@ -0,0 +1,450 @@
+use crate::convention::{Convention, ConventionBlade};
+use crate::r300::{basis, R300, TOLERANCE};
+use serde::Deserialize;
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Notation {
+    /// `\frac{\sqrt{2}}{2} e_{12}`
+    Latex,
+    /// `√2/2 e₁₂`
+    Unicode,
+    /// Presentation MathML.
+    MathML,
+}
+
+/// How basis blades are written after their coefficient.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum BasisNaming {
+    /// `e12`, as named by the convention.
+    Compact,
+    /// `e_{12}` in LaTeX, `e₁₂` in Unicode.
+    Subscript,
+    /// Products of Pauli vectors, `\sigma_1\sigma_2`.
+    Pauli,
+    /// Pauli vectors with bivectors as their duals, `i\sigma_3` for `e12`, and `i` for `e123`.
+    PauliDual,
+    /// One name per blade in `basis` order, the scalar name is ignored. The names are taken to
+    /// match the internal orientation.
+    Custom(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct FormatOptions {
+    pub notation: Notation,
+    /// Decimal places for coefficients without a closed form.
+    pub precision: usize,
+    /// How close a coefficient must be to a closed form to be shown as one.
+    pub tolerance: f64,
+    /// Largest denominator tried for rationals, including those multiplying `√n` and `π`.
+    pub max_denominator: i64,
+    pub basis: BasisNaming,
+    /// Order and orientation of the blades, `e31` instead of `e13` flips that coefficient's sign.
+    pub convention: Convention,
+    /// Wraps grades with more than one term in parentheses, `1 + (e_{1} + e_{2})`.
+    pub group_grades: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            notation: Notation::Latex,
+            precision: 4,
+            tolerance: TOLERANCE,
+            max_denominator: 12,
+            basis: BasisNaming::Subscript,
+            convention: Convention::standard(),
+            group_grades: false,
+        }
+    }
+}
+
+/// A coefficient recognized as `numerator / denominator` times a unit, the denominator is
+/// always positive and the fraction reduced.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClosedForm {
+    Rational {
+        numerator: i64,
+        denominator: i64,
+    },
+    Sqrt {
+        numerator: i64,
+        denominator: i64,
+        radicand: i64,
+    },
+    Pi {
+        numerator: i64,
+        denominator: i64,
+    },
+    Decimal(f64),
+}
+
+/// Square free radicands tried for `√n`, enough for the values rotations and normalizations
+/// usually produce.
+const RADICANDS: &[i64] = &[2, 3, 5, 6, 7, 10, 11];
+
+impl ClosedForm {
+    /// Rationals win over roots and roots over multiples of π, so `0.5` is never `√2/(2√2)`.
+    pub fn recognize(x: f64, options: &FormatOptions) -> ClosedForm {
+        if let Some((numerator, denominator)) = rational(x, options) {
+            return ClosedForm::Rational {
+                numerator,
+                denominator,
+            };
+        }
+        for &radicand in RADICANDS {
+            if let Some((numerator, denominator)) = rational(x / (radicand as f64).sqrt(), options)
+            {
+                return ClosedForm::Sqrt {
+                    numerator,
+                    denominator,
+                    radicand,
+                };
+            }
+        }
+        if let Some((numerator, denominator)) = rational(x / PI, options) {
+            return ClosedForm::Pi {
+                numerator,
+                denominator,
+            };
+        }
+        ClosedForm::Decimal(x)
+    }
+
+    pub fn is_negative(&self) -> bool {
+        match *self {
+            ClosedForm::Rational { numerator, .. }
+            | ClosedForm::Sqrt { numerator, .. }
+            | ClosedForm::Pi { numerator, .. } => numerator < 0,
+            ClosedForm::Decimal(x) => x < 0.0,
+        }
+    }
+
+    /// Whether the coefficient is written as 0 at `precision` decimal places, e.g. 1e-7 at 4.
+    pub fn rounds_to_zero(&self, precision: usize) -> bool {
+        matches!(*self, ClosedForm::Decimal(x) if decimal(x, precision) == "0")
+    }
+
+    pub fn is_one(&self) -> bool {
+        matches!(
+            self,
+            ClosedForm::Rational {
+                numerator: 1,
+                denominator: 1
+            }
+        )
+    }
+
+    pub fn abs(&self) -> ClosedForm {
+        match *self {
+            ClosedForm::Rational {
+                numerator,
+                denominator,
+            } => ClosedForm::Rational {
+                numerator: numerator.abs(),
+                denominator,
+            },
+            ClosedForm::Sqrt {
+                numerator,
+                denominator,
+                radicand,
+            } => ClosedForm::Sqrt {
+                numerator: numerator.abs(),
+                denominator,
+                radicand,
+            },
+            ClosedForm::Pi {
+                numerator,
+                denominator,
+            } => ClosedForm::Pi {
+                numerator: numerator.abs(),
+                denominator,
+            },
+            ClosedForm::Decimal(x) => ClosedForm::Decimal(x.abs()),
+        }
+    }
+
+    /// The coefficient as one LaTeX, Unicode or MathML expression. MathML has no `<math>`
+    /// wrapper so it can be placed inside a larger `<mrow>`.
+    pub fn render(&self, notation: Notation, precision: usize) -> String {
+        let (numerator, denominator, unit) = match *self {
+            ClosedForm::Rational {
+                numerator,
+                denominator,
+            } => (numerator, denominator, String::new()),
+            ClosedForm::Sqrt {
+                numerator,
+                denominator,
+                radicand,
+            } => (
+                numerator,
+                denominator,
+                match notation {
+                    Notation::Latex => format!("\\sqrt{{{}}}", radicand),
+                    Notation::Unicode => format!("√{}", radicand),
+                    Notation::MathML => format!("<msqrt><mn>{}</mn></msqrt>", radicand),
+                },
+            ),
+            ClosedForm::Pi {
+                numerator,
+                denominator,
+            } => (
+                numerator,
+                denominator,
+                match notation {
+                    Notation::Latex => "\\pi".to_string(),
+                    Notation::Unicode => "π".to_string(),
+                    Notation::MathML => "<mi>π</mi>".to_string(),
+                },
+            ),
+            ClosedForm::Decimal(x) => {
+                return match notation {
+                    Notation::MathML if x < 0.0 => {
+                        format!("<mo>-</mo><mn>{}</mn>", decimal(-x, precision))
+                    }
+                    Notation::MathML => format!("<mn>{}</mn>", decimal(x, precision)),
+                    _ => decimal(x, precision),
+                }
+            }
+        };
+
+        let negative = numerator < 0;
+        let top = match (numerator.abs(), unit.is_empty(), notation) {
+            (n, true, Notation::MathML) => format!("<mn>{}</mn>", n),
+            (n, true, _) => n.to_string(),
+            (1, false, _) => unit,
+            (n, false, Notation::MathML) => format!("<mrow><mn>{}</mn>{}</mrow>", n, unit),
+            (n, false, _) => format!("{}{}", n, unit),
+        };
+        let unsigned = match (denominator, notation) {
+            (1, _) => top,
+            (_, Notation::Latex) => format!("\\frac{{{}}}{{{}}}", top, denominator),
+            (_, Notation::Unicode) => format!("{}/{}", top, denominator),
+            (_, Notation::MathML) => format!("<mfrac>{}<mn>{}</mn></mfrac>", top, denominator),
+        };
+        match (negative, notation) {
+            (false, _) => unsigned,
+            (true, Notation::MathML) => format!("<mo>-</mo>{}", unsigned),
+            (true, _) => format!("-{}", unsigned),
+        }
+    }
+}
+
+fn rational(x: f64, options: &FormatOptions) -> Option<(i64, i64)> {
+    if !x.is_finite() {
+        return None;
+    }
+    (1..=options.max_denominator.max(1)).find_map(|denominator| {
+        let numerator = (x * denominator as f64).round();
+        let close = (x - numerator / denominator as f64).abs() <= options.tolerance;
+        (close && numerator.abs() < i64::MAX as f64).then_some((numerator as i64, denominator))
+    })
+}
+
+fn decimal(x: f64, precision: usize) -> String {
+    let formatted = format!("{:.*}", precision, x);
+    let trimmed = if formatted.contains('.') {
+        formatted.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &formatted
+    };
+    if trimmed == "-0" {
+        "0".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+pub fn format_coefficient(x: f64, options: &FormatOptions) -> String {
+    ClosedForm::recognize(x, options).render(options.notation, options.precision)
+}
+
+/// One factor of a blade's name, `e_{12}` is a single factor and `i\sigma_3` two.
+enum Factor {
+    E(String),
+    Sigma(usize),
+    I,
+    Named(String),
+}
+
+impl Factor {
+    fn render(&self, notation: Notation) -> String {
+        match (self, notation) {
+            (Factor::E(index), Notation::Latex) => format!("e_{{{}}}", index),
+            (Factor::E(index), Notation::Unicode) => format!("e{}", subscript(index)),
+            (Factor::E(index), Notation::MathML) => {
+                format!("<msub><mi>e</mi><mn>{}</mn></msub>", index)
+            }
+            (Factor::Sigma(index), Notation::Latex) => format!("\\sigma_{{{}}}", index),
+            (Factor::Sigma(index), Notation::Unicode) => {
+                format!("σ{}", subscript(&index.to_string()))
+            }
+            (Factor::Sigma(index), Notation::MathML) => {
+                format!("<msub><mi>σ</mi><mn>{}</mn></msub>", index)
+            }
+            (Factor::I, Notation::MathML) => "<mi>i</mi>".to_string(),
+            (Factor::I, _) => "i".to_string(),
+            (Factor::Named(name), Notation::MathML) => format!("<mi>{}</mi>", name),
+            (Factor::Named(name), _) => name.clone(),
+        }
+    }
+}
+
+fn subscript(digits: &str) -> String {
+    digits
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .filter_map(|d| char::from_u32('₀' as u32 + d))
+        .collect()
+}
+
+/// The factors naming `blade` and whether they equal minus the internal blade, as `e31` and
+/// `i\sigma_2` do for `e13`. `Compact`, `Subscript` and `Pauli` follow the convention's names,
+/// `PauliDual` and `Custom` only its order.
+fn blade_factors(blade: &ConventionBlade, naming: &BasisNaming) -> (bool, Vec<Factor>) {
+    let index = blade.indices();
+    let negate = blade.sign < 0.0;
+    match naming {
+        _ if blade.grade() == 0 => (negate, vec![]),
+        // A name without indices, e.g. `I` for the pseudoscalar, is written as given.
+        BasisNaming::Compact | BasisNaming::Subscript | BasisNaming::Pauli if index.is_empty() => {
+            (negate, vec![Factor::Named(blade.name.clone())])
+        }
+        BasisNaming::Compact => (negate, vec![Factor::Named(format!("e{}", index))]),
+        BasisNaming::Subscript => (negate, vec![Factor::E(index)]),
+        BasisNaming::Pauli => (
+            negate,
+            index
+                .chars()
+                .filter_map(|c| c.to_digit(10))
+                .map(|d| Factor::Sigma(d as usize))
+                .collect(),
+        ),
+        // i = σ1σ2σ3, so σ1σ2 = iσ3, σ1σ3 = -iσ2 and σ2σ3 = iσ1.
+        BasisNaming::PauliDual => match &basis[blade.index][1..] {
+            "12" => (false, vec![Factor::I, Factor::Sigma(3)]),
+            "13" => (true, vec![Factor::I, Factor::Sigma(2)]),
+            "23" => (false, vec![Factor::I, Factor::Sigma(1)]),
+            "123" => (false, vec![Factor::I]),
+            vector => (
+                false,
+                vector
+                    .chars()
+                    .filter_map(|c| c.to_digit(10))
+                    .map(|d| Factor::Sigma(d as usize))
+                    .collect(),
+            ),
+        },
+        BasisNaming::Custom(names) => (
+            false,
+            vec![Factor::Named(
+                names
+                    .get(blade.index)
+                    .cloned()
+                    .unwrap_or_else(|| basis[blade.index].to_string()),
+            )],
+        ),
+    }
+}
+
+/// How `blade` is written under `options` and whether that name equals minus the internal
+/// blade. The scalar's name is empty.
+pub fn blade_name(blade: &ConventionBlade, options: &FormatOptions) -> (bool, String) {
+    let (negate, factors) = blade_factors(blade, &options.basis);
+    let name = factors
+        .iter()
+        .map(|factor| factor.render(options.notation))
+        .collect::<String>();
+    (negate, name)
+}
+
+/// `blade` as a LaTeX factor, `e_{31}` for the cyclic convention's `e31`.
+pub fn blade_latex(blade: &ConventionBlade) -> String {
+    let (_, factors) = blade_factors(blade, &BasisNaming::Subscript);
+    factors
+        .iter()
+        .map(|factor| factor.render(Notation::Latex))
+        .collect()
+}
+
+fn operator(sign: &str, notation: Notation) -> String {
+    match notation {
+        Notation::MathML => format!("<mo>{}</mo>", sign),
+        _ => format!(" {} ", sign),
+    }
+}
+
+/// Writes `value` as a sum of blades with closed form coefficients, e.g.
+/// `\frac{\sqrt{2}}{2} + \frac{\sqrt{2}}{2} e_{12}`. Coefficients within `tolerance` of zero
+/// or that round to 0 at `precision` are left out, and a blade whose name carries a sign has it
+/// folded into the coefficient. MathML output is a complete `<math>` element.
+pub fn format_multivector(value: &R300, options: &FormatOptions) -> String {
+    let notation = options.notation;
+    let mut groups = Vec::new();
+    for grade in 0..=3 {
+        let mut group = String::new();
+        let mut terms = 0;
+        for blade in options.convention.blades() {
+            let i = blade.index;
+            if blade.grade() != grade || value[i].abs() <= options.tolerance {
+                continue;
+            }
+            let (negate, name) = blade_name(blade, options);
+            let coefficient = if negate { -value[i] } else { value[i] };
+            let coefficient = ClosedForm::recognize(coefficient, options);
+            if coefficient.rounds_to_zero(options.precision) {
+                continue;
+            }
+            let magnitude = coefficient.abs();
+            let rendered = magnitude.render(notation, options.precision);
+            let term = match (i, magnitude.is_one(), notation) {
+                (0, _, _) => rendered,
+                (_, true, _) => name,
+                (_, false, Notation::MathML) => format!("{}<mo>&#x2062;</mo>{}", rendered, name),
+                (_, false, _) => format!("{} {}", rendered, name),
+            };
+            match (terms, coefficient.is_negative(), notation) {
+                (0, true, Notation::MathML) => group.push_str("<mo>-</mo>"),
+                (0, true, _) => group.push('-'),
+                (0, false, _) => {}
+                (_, true, _) => group.push_str(&operator("-", notation)),
+                (_, false, _) => group.push_str(&operator("+", notation)),
+            }
+            group.push_str(&term);
+            terms += 1;
+        }
+        if terms > 1 && options.group_grades {
+            group = match notation {
+                Notation::Latex => format!("\\left({}\\right)", group),
+                Notation::Unicode => format!("({})", group),
+                Notation::MathML => format!("<mrow><mo>(</mo>{}<mo>)</mo></mrow>", group),
+            };
+        }
+        if terms > 0 {
+            groups.push(group);
+        }
+    }
+
+    let mut res = String::new();
+    for group in groups {
+        if res.is_empty() {
+            res = group;
+        } else if let Some(rest) = group.strip_prefix('-') {
+            res.push_str(&operator("-", notation));
+            res.push_str(rest);
+        } else if let Some(rest) = group.strip_prefix("<mo>-</mo>") {
+            res.push_str(&operator("-", notation));
+            res.push_str(rest);
+        } else {
+            res.push_str(&operator("+", notation));
+            res.push_str(&group);
+        }
+    }
+    match notation {
+        Notation::MathML if res.is_empty() => "<math><mn>0</mn></math>".to_string(),
+        Notation::MathML => format!("<math><mrow>{}</mrow></math>", res),
+        _ if res.is_empty() => "0".to_string(),
+        _ => res,
+    }
+}
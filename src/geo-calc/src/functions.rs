@@ -0,0 +1,76 @@
+use crate::r300::R300;
+
+/// A function that can be called from an expression, e.g. `\cos(\theta)` or `\sqrt{a^2}`.
+pub struct Builtin {
+    pub name: &'static str,
+    pub arity: usize,
+    apply: fn(&[R300]) -> Result<R300, &'static str>,
+}
+
+impl Builtin {
+    /// `args` must hold exactly `arity` values, the error is the reason the function is
+    /// undefined for them.
+    pub fn apply(&self, args: &[R300]) -> Result<R300, &'static str> {
+        (self.apply)(args)
+    }
+}
+
+pub(crate) const NOT_A_BLADE: &str =
+    "only defined for a scalar plus a single blade, not mixed grades";
+pub(crate) const NO_LOGARITHM: &str =
+    "only defined for positive scalars and a scalar plus a bivector or trivector";
+
+pub const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "exp",
+        arity: 1,
+        apply: |args| args[0].exp().ok_or(NOT_A_BLADE),
+    },
+    Builtin {
+        name: "sin",
+        arity: 1,
+        apply: |args| args[0].sin().ok_or(NOT_A_BLADE),
+    },
+    Builtin {
+        name: "cos",
+        arity: 1,
+        apply: |args| args[0].cos().ok_or(NOT_A_BLADE),
+    },
+    Builtin {
+        name: "sinh",
+        arity: 1,
+        apply: |args| args[0].sinh().ok_or(NOT_A_BLADE),
+    },
+    Builtin {
+        name: "cosh",
+        arity: 1,
+        apply: |args| args[0].cosh().ok_or(NOT_A_BLADE),
+    },
+    Builtin {
+        name: "log",
+        arity: 1,
+        apply: |args| args[0].log().ok_or(NO_LOGARITHM),
+    },
+    Builtin {
+        name: "ln",
+        arity: 1,
+        apply: |args| args[0].log().ok_or(NO_LOGARITHM),
+    },
+    Builtin {
+        name: "sqrt",
+        arity: 1,
+        apply: |args| args[0].sqrt().ok_or(NO_LOGARITHM),
+    },
+    Builtin {
+        name: "atan2",
+        arity: 2,
+        apply: |args| match args {
+            [y, x] if y.is_scalar() && x.is_scalar() => Ok(R300::new(y[0].atan2(x[0]), 0)),
+            _ => Err("expected two scalars"),
+        },
+    },
+];
+
+pub fn builtin(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
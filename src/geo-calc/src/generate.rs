@@ -0,0 +1,371 @@
+use crate::constraint::{resolve, solve, Condition, ConstraintError};
+use crate::inference::Grades;
+use crate::parameter::{Parameter, ParameterError};
+use crate::r300::{e123, R300, TOLERANCE};
+use crate::rng::Rng;
+use latex_expr_parser::ASTNode;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Candidates tried per identifier before settling for the best conditioned one.
+const ATTEMPTS: usize = 32;
+/// Two values of the same grade closer than this to parallel make the picture ambiguous.
+const MAX_COS: f64 = 0.95;
+/// A constraint that leaves less than this of a unit candidate has nearly nothing to work with.
+const MIN_REMAINDER: f64 = 0.2;
+
+/// What is known about an identifier before it gets a value. Every field is optional in JSON,
+/// `{}` is a vector with no constraints and
+/// `{"constraints": [{"kind": "unit"}, {"kind": "orthogonal", "to": "a"}]}` a unit vector
+/// orthogonal to `a`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Declaration {
+    pub grade: usize,
+    /// The conditions the solver keeps when the user moves values, references being identifiers
+    /// or literals such as `e_3`.
+    pub constraints: Vec<Condition>,
+    /// Makes the identifier a scalar slider within this range, whatever `grade` says.
+    pub parameter: Option<Parameter>,
+}
+
+impl Default for Declaration {
+    fn default() -> Self {
+        Self {
+            grade: 1,
+            constraints: vec![],
+            parameter: None,
+        }
+    }
+}
+
+impl Declaration {
+    /// The grades inference takes the identifier to have, a parameter being a scalar.
+    pub fn grades(&self) -> Grades {
+        if self.parameter.is_some() {
+            Grades::SCALAR
+        } else {
+            Grades::single(self.grade)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GenerateError {
+    Constraint(ConstraintError),
+    /// The identifiers' constraints refer to each other in a cycle.
+    Cycle(String),
+    /// The constraints only leave zero, e.g. a vector orthogonal to three independent vectors.
+    Unsatisfiable(String),
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GenerateError::Constraint(e) => e.fmt(f),
+            GenerateError::Cycle(name) => write!(f, "Constraints of {} refer back to it", name),
+            GenerateError::Unsatisfiable(name) => {
+                write!(f, "The constraints on {} only allow zero", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GenerateError {}
+
+impl From<ConstraintError> for GenerateError {
+    fn from(e: ConstraintError) -> Self {
+        GenerateError::Constraint(e)
+    }
+}
+
+impl From<ParameterError> for GenerateError {
+    fn from(e: ParameterError) -> Self {
+        match e {
+            ParameterError::InvalidRange { name, reason } => {
+                ConstraintError::InvalidConstraint { name, reason }.into()
+            }
+        }
+    }
+}
+
+/// FNV-1a, used to derive seeds from an expression and from identifier names.
+pub fn hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Seed for an expression, hashed without the source spans so that reformatting it, e.g. adding
+/// spaces, keeps the same values.
+pub fn seed(ast: &ASTNode) -> Result<u64, serde_json::Error> {
+    let mut value = serde_json::to_value(ast)?;
+    strip_spans(&mut value);
+    Ok(hash(&value.to_string()))
+}
+
+fn strip_spans(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            fields.remove("start");
+            fields.remove("end");
+            fields.values_mut().for_each(strip_spans);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_spans),
+        _ => {}
+    }
+}
+
+/// Default values for `names`. Each identifier draws from its own stream seeded by `seed` and
+/// its name, so adding an identifier to an expression leaves the others where they were unless
+/// they are constrained against it. Values avoid being near zero and near parallel to an earlier
+/// value of the same grade.
+pub fn generate(
+    names: &[String],
+    declarations: &HashMap<String, Declaration>,
+    seed: u64,
+) -> Result<BTreeMap<String, R300>, GenerateError> {
+    let mut order = Vec::new();
+    let mut visiting = Vec::new();
+    // Declared identifiers missing from `names` go last, sorted so the order is reproducible.
+    let mut declared = declarations.keys().collect::<Vec<_>>();
+    declared.sort();
+    for name in names.iter().chain(declared) {
+        visit(name, names, declarations, &mut visiting, &mut order)?;
+    }
+
+    let mut values = BTreeMap::new();
+    let mut generated = Vec::new();
+    for name in order {
+        let declaration = declarations.get(&name).cloned().unwrap_or_default();
+        let mut rng = Rng::new(seed ^ hash(&name));
+        if let Some(parameter) = declaration.parameter {
+            if !declaration.constraints.is_empty() {
+                return Err(invalid(&name, "a parameter only has a range").into());
+            }
+            parameter.validate(&name)?;
+            values.insert(name, R300::new(parameter.sample(&mut rng), 0));
+            continue;
+        }
+        let constraints = Constraints::new(&name, &declaration, &values)?;
+        let sampled = constraints.sample(&name, &mut rng, &generated)?;
+        // The solver settles what sampling leaves open, such as an angle, the same way it
+        // does when the user later moves a value.
+        let mut current = values
+            .iter()
+            .map(|(name, value)| (name.clone(), *value))
+            .collect::<HashMap<_, _>>();
+        current.insert(name.clone(), sampled);
+        let solution = solve(&current, &declaration.constraints(&name))?;
+        if !solution.converged {
+            return Err(GenerateError::Unsatisfiable(name));
+        }
+        let value = solution.values[&name];
+        generated.push(value);
+        values.insert(name, value);
+    }
+    Ok(values)
+}
+
+/// Depth first, so that an identifier comes after everything its constraints refer to.
+fn visit(
+    name: &String,
+    names: &[String],
+    declarations: &HashMap<String, Declaration>,
+    visiting: &mut Vec<String>,
+    order: &mut Vec<String>,
+) -> Result<(), GenerateError> {
+    if order.contains(name) {
+        return Ok(());
+    }
+    if visiting.contains(name) {
+        return Err(GenerateError::Cycle(name.clone()));
+    }
+    visiting.push(name.clone());
+    if let Some(declaration) = declarations.get(name) {
+        let references = declaration
+            .constraints
+            .iter()
+            .flat_map(Condition::references);
+        for reference in references {
+            if names.contains(reference) || declarations.contains_key(reference) {
+                visit(reference, names, declarations, visiting, order)?;
+            }
+        }
+    }
+    visiting.pop();
+    order.push(name.clone());
+    Ok(())
+}
+
+fn invalid(name: &str, reason: &str) -> ConstraintError {
+    ConstraintError::InvalidConstraint {
+        name: name.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+/// The directions a declaration rules out before sampling. Vectors and bivectors are both
+/// handled as vectors, a bivector being the dual of one, so orthogonality means the same for
+/// both. Conditions that do not rule out directions, such as an angle, are left to the solver.
+struct Constraints {
+    grade: usize,
+    /// The length every condition agrees on, if one fixes it.
+    length: Option<f64>,
+    /// An orthonormal basis of the directions the value has to be orthogonal to.
+    excluded: Vec<R300>,
+}
+
+impl Constraints {
+    fn new(
+        name: &str,
+        declaration: &Declaration,
+        values: &BTreeMap<String, R300>,
+    ) -> Result<Self, ConstraintError> {
+        let grade = declaration.grade;
+        if grade > 3 {
+            return Err(invalid(name, "grades go up to 3"));
+        }
+        let resolve = |reference: &String| resolve(name, reference, values.get(reference));
+        let directed = |reference: &String| {
+            let value = resolve(reference)?;
+            if grade != 1 && grade != 2 {
+                Err(invalid(name, "only vectors and bivectors have a direction"))
+            } else if value != R300::zero() && value.grade() != Some(grade) {
+                Err(invalid(name, "refers to a value of another grade"))
+            } else {
+                Ok(as_vector(value, grade))
+            }
+        };
+        let axes = [
+            R300::vector(1.0, 0.0, 0.0),
+            R300::vector(0.0, 1.0, 0.0),
+            R300::vector(0.0, 0.0, 1.0),
+        ];
+        // Everything orthogonal to `u`, which a value parallel to it has no part along.
+        let complement = |u: R300| {
+            if u.norm() < TOLERANCE {
+                vec![]
+            } else {
+                axes.map(|axis| reject(axis, &[u.normalized()])).to_vec()
+            }
+        };
+
+        let mut length = None;
+        let mut directions = Vec::new();
+        for condition in &declaration.constraints {
+            match condition {
+                Condition::Unit => length = Some(1.0),
+                Condition::Magnitude { magnitude } => length = Some(*magnitude),
+                Condition::Orthogonal { to } => directions.push(directed(to)?),
+                Condition::Parallel { to } => directions.extend(complement(directed(to)?)),
+                Condition::InBlade { spanned_by } => {
+                    if grade != 1 {
+                        return Err(invalid(name, "only vectors lie in a blade"));
+                    }
+                    let blade = spanned_by
+                        .iter()
+                        .map(resolve)
+                        .try_fold(R300::new(1.0, 0), |blade, value| {
+                            value.map(|value| blade.wedge(value))
+                        })?;
+                    match blade.grade() {
+                        // The normal of a plane, which a vector in it is orthogonal to.
+                        Some(2) => directions.push(blade.Dual()),
+                        Some(1) => directions.extend(complement(blade)),
+                        _ if blade.norm() < TOLERANCE => {
+                            return Err(invalid(name, "the blade is zero"))
+                        }
+                        _ => {}
+                    }
+                }
+                Condition::Angle { to, .. } => {
+                    directed(to)?;
+                }
+            }
+        }
+        let mut excluded: Vec<R300> = Vec::new();
+        for direction in directions {
+            let rest = reject(direction, &excluded);
+            if rest.norm() > TOLERANCE.sqrt() {
+                excluded.push(rest.normalized());
+            }
+        }
+        Ok(Self {
+            grade,
+            length,
+            excluded,
+        })
+    }
+
+    fn sample(&self, name: &str, rng: &mut Rng, earlier: &[R300]) -> Result<R300, GenerateError> {
+        let length = |rng: &mut Rng| self.length.unwrap_or_else(|| rng.range(1.5, 3.5));
+        if self.grade == 0 || self.grade == 3 {
+            let sign = if rng.next_f64() < 0.5 { -1.0 } else { 1.0 };
+            let magnitude = self.length.unwrap_or_else(|| rng.range(0.5, 2.0));
+            let value = R300::new(sign * magnitude, 0);
+            return Ok(if self.grade == 3 { value * e123 } else { value });
+        }
+
+        let mut best: Option<(f64, R300)> = None;
+        for _ in 0..ATTEMPTS {
+            let direction = R300::vector(
+                rng.range(-1.0, 1.0),
+                rng.range(-1.0, 1.0),
+                rng.range(-1.0, 1.0),
+            );
+            if direction.norm() < MIN_REMAINDER {
+                continue;
+            }
+            let rest = reject(direction.normalized(), &self.excluded);
+            if rest.norm() < MIN_REMAINDER {
+                continue;
+            }
+            let vector = length(rng) * rest.normalized();
+            let value = if self.grade == 2 {
+                vector.Dual()
+            } else {
+                vector
+            };
+            let closest = earlier
+                .iter()
+                .filter(|other| other.grade() == Some(self.grade))
+                .map(|other| cos(vector, as_vector(*other, self.grade)))
+                .fold(0.0, f64::max);
+            if closest < MAX_COS {
+                return Ok(value);
+            }
+            if best.is_none_or(|(cos, _)| closest < cos) {
+                best = Some((closest, value));
+            }
+        }
+        best.map(|(_, value)| value)
+            .ok_or_else(|| GenerateError::Unsatisfiable(name.to_string()))
+    }
+}
+
+/// Bivectors as their dual vectors, which are orthogonal or parallel exactly when they are.
+pub(crate) fn as_vector(value: R300, grade: usize) -> R300 {
+    if grade == 2 {
+        value.Dual()
+    } else {
+        value
+    }
+}
+
+/// `v` without its components along the orthonormal `basis`.
+pub(crate) fn reject(v: R300, basis: &[R300]) -> R300 {
+    basis.iter().fold(v, |rest, &u| rest - rest.dot(u)[0] * u)
+}
+
+/// The absolute cosine of the angle between two vectors.
+fn cos(a: R300, b: R300) -> f64 {
+    let norms = a.norm() * b.norm();
+    if norms == 0.0 {
+        0.0
+    } else {
+        (a.dot(b)[0] / norms).abs()
+    }
+}
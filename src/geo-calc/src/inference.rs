@@ -0,0 +1,452 @@
+use crate::annotated::Annotated;
+use crate::functions::{self, NOT_A_BLADE, NO_LOGARITHM};
+use crate::r300::{basis_count, basis_grades, R300};
+use crate::symbols::ReservedSymbols;
+use crate::{arguments, is_exponential};
+use latex_expr_parser::{ASTNode, ASTNodeType, TokenKind};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+const max_grade: usize = 3;
+
+/// The grades a value can have, bit k is set when it can have a grade-k part. No grades at all
+/// means the value is always zero.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(into = "Vec<usize>", try_from = "Vec<usize>")]
+pub struct Grades(u8);
+
+impl Grades {
+    pub const NONE: Grades = Grades(0);
+    pub const ALL: Grades = Grades(0b1111);
+    pub const SCALAR: Grades = Grades(0b0001);
+    pub const VECTOR: Grades = Grades(0b0010);
+    pub const BIVECTOR: Grades = Grades(0b0100);
+    pub const TRIVECTOR: Grades = Grades(0b1000);
+
+    pub fn single(grade: usize) -> Self {
+        Grades(1 << grade)
+    }
+
+    /// The grades with a non-zero coefficient in `value`.
+    pub fn of(value: R300) -> Self {
+        (0..basis_count)
+            .filter(|&i| value[i] != 0.0)
+            .fold(Grades::NONE, |grades, i| {
+                grades.union(Grades::single(basis_grades[i]))
+            })
+    }
+
+    pub fn contains(self, grade: usize) -> bool {
+        self.0 & (1 << grade) != 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The only grade, None when there are several or none.
+    pub fn single_grade(self) -> Option<usize> {
+        self.0
+            .is_power_of_two()
+            .then(|| self.0.trailing_zeros() as usize)
+    }
+
+    pub fn is_subset(self, other: Grades) -> bool {
+        self.0 & !other.0 == 0
+    }
+
+    pub fn union(self, other: Grades) -> Self {
+        Grades(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: Grades) -> Self {
+        Grades(self.0 & other.0)
+    }
+
+    pub fn without(self, other: Grades) -> Self {
+        Grades(self.0 & !other.0)
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = usize> {
+        (0..=max_grade).filter(move |&grade| self.contains(grade))
+    }
+
+    /// The grades of a product of values with these grades. The geometric product of an
+    /// r-blade and an s-blade has grades |r - s|, |r - s| + 2, ... up to r + s, folded back
+    /// at the pseudoscalar; `keep` picks the ones the product keeps, like in `Multivector`.
+    pub fn product(self, other: Grades, keep: impl Fn(usize, usize, usize) -> bool) -> Self {
+        let mut res = Grades::NONE;
+        for r in self.iter() {
+            for s in other.iter() {
+                let highest = (r + s).min(2 * max_grade - r - s);
+                for grade in (r.abs_diff(s)..=highest).step_by(2) {
+                    if keep(r, s, grade) {
+                        res = res.union(Grades::single(grade));
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    pub fn geometric(self, other: Grades) -> Self {
+        self.product(other, |_, _, _| true)
+    }
+
+    /// A single grade is its own inverse's grade. Otherwise `A^{-1} = \bar{A} (A \bar{A})^{-1}`
+    /// where `A \bar{A}` is a scalar plus a pseudoscalar in 3D.
+    pub fn inverse(self) -> Self {
+        match self.single_grade() {
+            Some(_) => self,
+            None => self.geometric(Grades::SCALAR.union(Grades::TRIVECTOR)),
+        }
+    }
+
+    /// The grades of `A^n`, an unknown `n` gives every grade reachable by repeated products.
+    pub fn power(self, exponent: Option<i64>) -> Self {
+        let base = match exponent {
+            Some(n) if n < 0 => self.inverse(),
+            _ => self,
+        };
+        match exponent {
+            Some(n) => {
+                // The grades of the powers repeat after a few steps, e.g. even and odd for a
+                // vector, so a large `n` is reduced to the first repetition.
+                let n = n.unsigned_abs() as usize;
+                let mut powers = vec![Grades::SCALAR];
+                while powers.len() <= n {
+                    let next = powers[powers.len() - 1].geometric(base);
+                    if let Some(first) = powers.iter().position(|&grades| grades == next) {
+                        let period = powers.len() - first;
+                        return powers[first + (n - first) % period];
+                    }
+                    powers.push(next);
+                }
+                powers[n]
+            }
+            None => {
+                let mut res = Grades::SCALAR.union(base).union(base.inverse());
+                loop {
+                    let next = res.union(res.geometric(res));
+                    if next == res {
+                        return res;
+                    }
+                    res = next;
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Grades {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl From<Grades> for Vec<usize> {
+    fn from(grades: Grades) -> Self {
+        grades.iter().collect()
+    }
+}
+
+impl TryFrom<Vec<usize>> for Grades {
+    type Error = String;
+
+    fn try_from(grades: Vec<usize>) -> Result<Self, Self::Error> {
+        grades.into_iter().try_fold(Grades::NONE, |acc, grade| {
+            if grade > max_grade {
+                Err(format!("No grade {} in 3D", grade))
+            } else {
+                Ok(acc.union(Grades::single(grade)))
+            }
+        })
+    }
+}
+
+/// Something the UI should warn about before evaluating. None of these stop the analysis.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Issue {
+    /// The operation is zero whatever its operands are, e.g. the wedge of two bivectors.
+    AlwaysZero {
+        start: usize,
+        end: usize,
+    },
+    NonScalarExponent {
+        start: usize,
+        end: usize,
+    },
+    /// The two sides of an equation share no grade, so it only holds when both are zero.
+    DisjointSides {
+        start: usize,
+        end: usize,
+    },
+    UnsupportedOperator {
+        op: TokenKind,
+        start: usize,
+        end: usize,
+    },
+    UnknownFunction {
+        name: String,
+        start: usize,
+        end: usize,
+    },
+    InvalidArgument {
+        function: String,
+        reason: String,
+        start: usize,
+        end: usize,
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Issue::AlwaysZero { .. } => write!(f, "Always zero"),
+            Issue::NonScalarExponent { .. } => write!(f, "Exponent is not a scalar"),
+            Issue::DisjointSides { .. } => {
+                write!(
+                    f,
+                    "The sides have no grade in common, so both have to be zero"
+                )
+            }
+            Issue::UnsupportedOperator { op, .. } => {
+                write!(f, "Unsupported operator: {:?}", op)
+            }
+            Issue::UnknownFunction { name, .. } => write!(f, "Unknown function: {}", name),
+            Issue::InvalidArgument {
+                function, reason, ..
+            } => write!(f, "Invalid argument to {}: {}", function, reason),
+        }
+    }
+}
+
+/// What inference attaches to each node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inferred {
+    pub grades: Grades,
+    pub issues: Vec<Issue>,
+}
+
+pub type Typed<'a> = Annotated<'a, Inferred>;
+
+/// Infers the grades of every node of `ast` without evaluating it. Reserved symbols have the
+/// grades of their value, other identifiers the grades declared for them in `declared`, and any
+/// undeclared identifier is taken to be a vector.
+pub fn infer<'a>(
+    ast: &'a ASTNode,
+    declared: &HashMap<String, Grades>,
+    symbols: &ReservedSymbols,
+) -> Typed<'a> {
+    Annotated::build(ast, &mut |node, children: &[Typed]| {
+        let mut issues = Vec::new();
+        let grades = infer_node(node, children, std::ptr::eq(node, ast), declared, symbols)
+            .unwrap_or_else(|issue| {
+                issues.push(issue);
+                Grades::ALL
+            });
+        // Only the node where zero first appears is flagged, not everything above it.
+        if grades.is_empty()
+            && !children.is_empty()
+            && children.iter().all(|child| !child.meta.grades.is_empty())
+        {
+            issues.push(Issue::AlwaysZero {
+                start: node.start,
+                end: node.end,
+            });
+        }
+        Inferred { grades, issues }
+    })
+}
+
+/// The grades of one node from its children's. An ill-typed node gets an issue and is treated as
+/// if it could have any grade.
+fn infer_node(
+    ast: &ASTNode,
+    children: &[Typed],
+    top: bool,
+    declared: &HashMap<String, Grades>,
+    symbols: &ReservedSymbols,
+) -> Result<Grades, Issue> {
+    let grades = children
+        .iter()
+        .map(|child| child.meta.grades)
+        .collect::<Vec<_>>();
+    match &ast.r#type {
+        ASTNodeType::Int { value: 0 } => Ok(Grades::NONE),
+        ASTNodeType::Int { .. } => Ok(Grades::SCALAR),
+        ASTNodeType::Identifier { name } => Ok(symbols
+            .get(name)
+            .map(Grades::of)
+            .or_else(|| declared.get(name).copied())
+            .unwrap_or(Grades::VECTOR)),
+        ASTNodeType::BinaryOpNode {
+            op: TokenKind::Caret,
+            ..
+        } if is_exponential(ast, symbols) => infer_function(ast, "exp", arguments(ast, &grades)),
+        ASTNodeType::BinaryOpNode {
+            op: TokenKind::Caret,
+            right,
+            ..
+        } => {
+            if !grades[1].is_subset(Grades::SCALAR) {
+                return Err(Issue::NonScalarExponent {
+                    start: right.start,
+                    end: right.end,
+                });
+            }
+            Ok(grades[0].power(literal_integer(right)))
+        }
+        ASTNodeType::BinaryOpNode {
+            op: TokenKind::Equals,
+            ..
+        } if top => {
+            let (left, right) = (grades[0], grades[1]);
+            if !left.is_empty() && !right.is_empty() && left.intersection(right).is_empty() {
+                return Err(Issue::DisjointSides {
+                    start: ast.start,
+                    end: ast.end,
+                });
+            }
+            Ok(left.union(right))
+        }
+        ASTNodeType::BinaryOpNode { op, .. } => {
+            let (left, right) = (grades[0], grades[1]);
+            match op {
+                TokenKind::Plus | TokenKind::Minus => Ok(left.union(right)),
+                TokenKind::Multiply => Ok(left.geometric(right)),
+                TokenKind::Dot => Ok(left.product(right, |r, s, grade| grade == r.abs_diff(s))),
+                TokenKind::Wedge => Ok(left.product(right, |r, s, grade| grade == r + s)),
+                TokenKind::Frac => Ok(left.geometric(right.inverse())),
+                _ => Err(Issue::UnsupportedOperator {
+                    op: *op,
+                    start: ast.start,
+                    end: ast.end,
+                }),
+            }
+        }
+        ASTNodeType::UnaryOpNode { op, .. } => match op {
+            TokenKind::Plus
+            | TokenKind::Minus
+            | TokenKind::Dagger
+            | TokenKind::Tilde
+            | TokenKind::Bar
+            | TokenKind::Hat => Ok(grades[0]),
+            TokenKind::Star => Ok(grades[0].geometric(Grades::TRIVECTOR)),
+            TokenKind::Pipe | TokenKind::DoublePipe => Ok(Grades::SCALAR),
+            _ => Err(Issue::UnsupportedOperator {
+                op: *op,
+                start: ast.start,
+                end: ast.end,
+            }),
+        },
+        ASTNodeType::FunctionCall { name, .. } => infer_function(ast, name, &grades),
+    }
+}
+
+/// Mirrors `calculate_function`: the same names, and an issue wherever evaluation would fail
+/// for every value of these grades.
+fn infer_function(ast: &ASTNode, name: &str, args: &[Grades]) -> Result<Grades, Issue> {
+    let function = name.trim_start_matches('\\');
+    let invalid = |reason: &str| Issue::InvalidArgument {
+        function: function.to_string(),
+        reason: reason.to_string(),
+        start: ast.start,
+        end: ast.end,
+    };
+    match function {
+        "angle" => match args {
+            [left, right] if left.single_grade().is_some() && left == right => Ok(Grades::SCALAR),
+            [_, _] => Err(invalid("expected two blades of the same grade")),
+            _ => Err(invalid("expected two arguments")),
+        },
+        "area" => match args {
+            [operand] if operand.is_subset(Grades::BIVECTOR) => Ok(Grades::SCALAR),
+            [_] => Err(invalid("expected a bivector")),
+            _ => Err(invalid("expected one argument")),
+        },
+        _ => {
+            let builtin = functions::builtin(function).ok_or(Issue::UnknownFunction {
+                name: name.to_string(),
+                start: ast.start,
+                end: ast.end,
+            })?;
+            if args.len() != builtin.arity {
+                return Err(invalid(&format!("expected {} argument(s)", builtin.arity)));
+            }
+            match (function, args) {
+                ("exp" | "sin" | "cos" | "sinh" | "cosh", [arg]) => {
+                    if arg.without(Grades::SCALAR).iter().count() > 1 {
+                        return Err(invalid(NOT_A_BLADE));
+                    }
+                    Ok(arg.union(Grades::SCALAR))
+                }
+                ("log" | "ln" | "sqrt", [arg]) => {
+                    // The blade has to square to a negative scalar, e123 does too.
+                    let bivector = Grades::SCALAR.union(Grades::BIVECTOR);
+                    let trivector = Grades::SCALAR.union(Grades::TRIVECTOR);
+                    if !arg.is_subset(bivector) && !arg.is_subset(trivector) {
+                        return Err(invalid(NO_LOGARITHM));
+                    }
+                    Ok(arg.union(Grades::SCALAR))
+                }
+                ("atan2", [y, x]) => {
+                    if !y.union(*x).is_subset(Grades::SCALAR) {
+                        return Err(invalid("expected two scalars"));
+                    }
+                    Ok(Grades::SCALAR)
+                }
+                _ => Ok(Grades::ALL),
+            }
+        }
+    }
+}
+
+/// `n` or `-n` written as a literal, the only exponents known before evaluation.
+fn literal_integer(ast: &ASTNode) -> Option<i64> {
+    match &ast.r#type {
+        ASTNodeType::Int { value } => Some(*value),
+        ASTNodeType::UnaryOpNode {
+            op: TokenKind::Minus,
+            operand,
+        } => literal_integer(operand).map(|n| -n),
+        _ => None,
+    }
+}
+
+/// The inferred grades of one span of the source.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SpanGrades {
+    pub start: usize,
+    pub end: usize,
+    pub grades: Grades,
+}
+
+/// The result of `analyze_expression`: the grades of every node in evaluation order, and every
+/// issue found on the way.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Analysis {
+    pub spans: Vec<SpanGrades>,
+    pub issues: Vec<Issue>,
+}
+
+impl From<&Typed<'_>> for Analysis {
+    fn from(tree: &Typed) -> Self {
+        let nodes = tree.post_order();
+        Analysis {
+            spans: nodes
+                .iter()
+                .map(|node| SpanGrades {
+                    start: node.start(),
+                    end: node.end(),
+                    grades: node.meta.grades,
+                })
+                .collect(),
+            issues: nodes
+                .iter()
+                .flat_map(|node| node.meta.issues.iter().cloned())
+                .collect(),
+        }
+    }
+}
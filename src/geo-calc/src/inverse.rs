@@ -0,0 +1,271 @@
+use crate::error::EvalError;
+use crate::inference::Grades;
+use crate::r300::{basis_count, basis_grades, R300, TOLERANCE};
+use crate::symbols::ReservedSymbols;
+use crate::{evaluate, Evaluated, Kind};
+use latex_expr_parser::{ASTNode, TokenKind};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+const MAX_ITERATIONS: usize = 50;
+/// Step of the finite differences for nonlinear nodes.
+const STEP: f64 = 1e-7;
+/// Keeps the normal equations solvable when the node ignores part of the variable, e.g. the
+/// component of `b` along `a` in `a \wedge b`. That part is then left as it was.
+const DAMPING: f64 = 1e-12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum Method {
+    /// The node is affine in the variable, so one least squares step solves it exactly.
+    ClosedForm,
+    GaussNewton,
+}
+
+/// A new value for the variable and how close it brings the node to the target. The residual
+/// stays positive when the target is out of reach, e.g. a bivector not containing `a` as the
+/// target of `a \wedge b`; the value is then the closest the node can get.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InverseSolution {
+    pub value: R300,
+    pub residual: f64,
+    pub method: Method,
+    pub iterations: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InverseError {
+    NodeNotFound {
+        start: usize,
+        end: usize,
+    },
+    UnknownIdentifier(String),
+    /// The node's value does not depend on the variable.
+    NotDependent(String),
+    Eval(EvalError),
+}
+
+impl fmt::Display for InverseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InverseError::NodeNotFound { start, end } => {
+                write!(f, "No node spans {}..{}", start, end)
+            }
+            InverseError::UnknownIdentifier(name) => write!(f, "Unknown identifier: {}", name),
+            InverseError::NotDependent(name) => {
+                write!(f, "The node does not depend on {}", name)
+            }
+            InverseError::Eval(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for InverseError {}
+
+impl From<EvalError> for InverseError {
+    fn from(e: EvalError) -> Self {
+        InverseError::Eval(e)
+    }
+}
+
+/// Changes the free identifier `name` so that the node spanning `start..end` evaluates to
+/// `target`, keeping the other variables fixed. Only the grades `name` already has are changed,
+/// so a vector stays a vector, and among the values reaching the target the one closest to the
+/// current value is chosen.
+pub fn solve_for(
+    ast: &ASTNode,
+    vars: &HashMap<String, R300>,
+    symbols: &ReservedSymbols,
+    (start, end): (usize, usize),
+    target: R300,
+    name: &str,
+) -> Result<InverseSolution, InverseError> {
+    let initial = *vars
+        .get(name)
+        .ok_or_else(|| InverseError::UnknownIdentifier(name.to_string()))?;
+    let evaluated = evaluate(ast, vars, symbols);
+    let node = find(&evaluated, start, end).ok_or(InverseError::NodeNotFound { start, end })?;
+    if !depends_on(node, name) {
+        return Err(InverseError::NotDependent(name.to_string()));
+    }
+    let grades = match Grades::of(initial) {
+        grades if grades.is_empty() => Grades::VECTOR,
+        grades => grades,
+    };
+    let components = (0..basis_count)
+        .filter(|&i| grades.contains(basis_grades[i]))
+        .collect::<Vec<_>>();
+
+    let mut vars = vars.clone();
+    let mut value_at = |x: R300| -> Result<R300, InverseError> {
+        vars.insert(name.to_string(), x);
+        let evaluated = evaluate(ast, &vars, symbols);
+        let node = find(&evaluated, start, end).ok_or(InverseError::NodeNotFound { start, end })?;
+        match node.first_error() {
+            Some(e) => Err(e.clone().into()),
+            None => Ok(node.value()),
+        }
+    };
+
+    let (method, step) = if is_affine(node, name) {
+        (Method::ClosedForm, 1.0)
+    } else {
+        (Method::GaussNewton, STEP)
+    };
+    let mut x = initial;
+    let mut current = value_at(x)?;
+    let mut iterations = 0;
+    while iterations < MAX_ITERATIONS {
+        let residual = target - current;
+        if residual.norm() < TOLERANCE {
+            break;
+        }
+        iterations += 1;
+        let jacobian = components
+            .iter()
+            .map(|&i| {
+                let mut probe = x;
+                probe[i] += step;
+                Ok((1.0 / step) * (value_at(probe)? - current))
+            })
+            .collect::<Result<Vec<_>, InverseError>>()?;
+        let Some(delta) = least_squares(&jacobian, residual) else {
+            break;
+        };
+        let step_to = |fraction: f64| {
+            let mut next = x;
+            for (&i, d) in components.iter().zip(&delta) {
+                next[i] += d * fraction;
+            }
+            next
+        };
+        if method == Method::ClosedForm {
+            x = step_to(1.0);
+            current = value_at(x)?;
+            break;
+        }
+        // Halve the Gauss-Newton step until it improves on the current value.
+        let mut fraction = 1.0;
+        let improved = loop {
+            let next = step_to(fraction);
+            let value = value_at(next)?;
+            if (target - value).norm() < residual.norm() {
+                break Some((next, value));
+            }
+            fraction /= 2.0;
+            if fraction < TOLERANCE.sqrt() {
+                break None;
+            }
+        };
+        let Some((next, value)) = improved else {
+            break;
+        };
+        x = next;
+        current = value;
+    }
+    Ok(InverseSolution {
+        value: x,
+        residual: (target - current).norm(),
+        method,
+        iterations,
+    })
+}
+
+/// Solves `J d = r` in the least squares sense through the damped normal equations, `jacobian`
+/// holding one column per unknown.
+fn least_squares(jacobian: &[R300], residual: R300) -> Option<Vec<f64>> {
+    let n = jacobian.len();
+    let dot = |a: R300, b: R300| (0..basis_count).map(|i| a[i] * b[i]).sum::<f64>();
+    let scale = jacobian
+        .iter()
+        .map(|&column| dot(column, column))
+        .sum::<f64>();
+    if scale == 0.0 {
+        return None;
+    }
+    let mut matrix = (0..n)
+        .map(|row| {
+            let mut line = (0..n)
+                .map(|col| dot(jacobian[row], jacobian[col]))
+                .collect::<Vec<_>>();
+            line[row] += DAMPING * scale;
+            line.push(dot(jacobian[row], residual));
+            line
+        })
+        .collect::<Vec<_>>();
+
+    // Gaussian elimination with partial pivoting, the system is at most 8 by 8.
+    for col in 0..n {
+        let pivot =
+            (col..n).max_by(|&a, &b| matrix[a][col].abs().total_cmp(&matrix[b][col].abs()))?;
+        if matrix[pivot][col].abs() < f64::MIN_POSITIVE {
+            return None;
+        }
+        matrix.swap(col, pivot);
+        let (above, below) = matrix.split_at_mut(col + 1);
+        let pivot_row = &above[col];
+        for line in below {
+            let factor = line[col] / pivot_row[col];
+            for (entry, pivot_entry) in line[col..].iter_mut().zip(&pivot_row[col..]) {
+                *entry -= factor * pivot_entry;
+            }
+        }
+    }
+    let mut solution = vec![0.0; n];
+    for row in (0..n).rev() {
+        let known = (row + 1..n)
+            .map(|k| matrix[row][k] * solution[k])
+            .sum::<f64>();
+        solution[row] = (matrix[row][n] - known) / matrix[row][row];
+    }
+    Some(solution)
+}
+
+/// The outermost node spanning `start..end`.
+fn find<'b, 'a>(root: &'b Evaluated<'a>, start: usize, end: usize) -> Option<&'b Evaluated<'a>> {
+    root.post_order()
+        .into_iter()
+        .rev()
+        .find(|node| node.start() == start && node.end() == end)
+}
+
+fn depends_on(node: &Evaluated, name: &str) -> bool {
+    node.post_order()
+        .into_iter()
+        .any(|node| node.kind() == Kind::Identifier(name))
+}
+
+/// Whether the node is a sum of a constant and a linear function of the variable: sums,
+/// products with one side not depending on it, divisions by something not depending on it,
+/// and the linear unary operators.
+fn is_affine(node: &Evaluated, name: &str) -> bool {
+    if !depends_on(node, name) {
+        return true;
+    }
+    let operands = &node.children;
+    match node.kind() {
+        Kind::Identifier(_) => true,
+        Kind::BinaryOp(TokenKind::Plus | TokenKind::Minus) => {
+            operands.iter().all(|operand| is_affine(operand, name))
+        }
+        Kind::BinaryOp(TokenKind::Multiply | TokenKind::Dot | TokenKind::Wedge) => {
+            match (
+                depends_on(&operands[0], name),
+                depends_on(&operands[1], name),
+            ) {
+                (true, false) => is_affine(&operands[0], name),
+                (false, true) => is_affine(&operands[1], name),
+                _ => false,
+            }
+        }
+        Kind::BinaryOp(TokenKind::Frac) => {
+            !depends_on(&operands[1], name) && is_affine(&operands[0], name)
+        }
+        Kind::UnaryOp(TokenKind::Plus | TokenKind::Minus)
+        | Kind::Reverse
+        | Kind::Dual
+        | Kind::Conjugate
+        | Kind::Involute => is_affine(&operands[0], name),
+        _ => false,
+    }
+}
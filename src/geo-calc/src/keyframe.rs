@@ -0,0 +1,251 @@
+use crate::r300::{R300, TOLERANCE};
+use crate::symbols::ReservedSymbols;
+use crate::{evaluate, Evaluated};
+use latex_expr_parser::ASTNode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::fmt;
+
+/// How a value moves between two keyframes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Interpolation {
+    /// Component by component, for vectors and anything without a better rule.
+    Linear,
+    /// Along the rotation between two rotors, `a (a^{-1} b)^t` through log and exp, so a rotated
+    /// vector sweeps an arc at constant speed instead of cutting across the circle.
+    Rotor,
+    /// Magnitude and orientation separately for bivector blades, so an oriented plane turns about
+    /// the line it shares with the next one while its area grows or shrinks.
+    Blade,
+}
+
+impl Interpolation {
+    /// The rule that fits both values: blades for bivectors, rotors for nonzero sums of a scalar
+    /// and a bivector unless both are plain scalars, linear otherwise.
+    pub fn for_values(a: R300, b: R300) -> Self {
+        if a.is_bivector() && b.is_bivector() {
+            Interpolation::Blade
+        } else if is_rotor(a) && is_rotor(b) && !(a.is_scalar() && b.is_scalar()) {
+            Interpolation::Rotor
+        } else {
+            Interpolation::Linear
+        }
+    }
+
+    /// The value at `t` from 0 at `a` to 1 at `b`, None when the values are not what this rule
+    /// needs, e.g. a vector for `Rotor`.
+    pub fn apply(self, a: R300, b: R300, t: f64) -> Option<R300> {
+        match self {
+            Interpolation::Linear => Some(lerp(a, b, t)),
+            Interpolation::Rotor => slerp(a, b, t),
+            Interpolation::Blade => blend_blades(a, b, t),
+        }
+    }
+}
+
+pub fn lerp(a: R300, b: R300, t: f64) -> R300 {
+    (1.0 - t) * a + t * b
+}
+
+/// Interpolates between the rotors `a` and `b`. `b` and `-b` rotate vectors the same way, the
+/// one closer to `a` is used so that the rotation takes the short way around.
+///
+/// The segment then ends at `-b`, so a value used as `R x \tilde{R}` moves continuously through
+/// the keyframe while the rotor itself, and anything using it linearly, changes sign there.
+pub fn slerp(a: R300, b: R300, t: f64) -> Option<R300> {
+    if !is_rotor(a) || !is_rotor(b) {
+        return None;
+    }
+    let mut relative = b * a.inverse()?;
+    if relative[0] < 0.0 {
+        relative = -1.0 * relative;
+    }
+    Some((t * relative.log()?).exp()? * a)
+}
+
+/// Interpolates between bivector blades by turning the plane and scaling its magnitude
+/// linearly. A zero end keeps the orientation of the other one.
+pub fn blend_blades(a: R300, b: R300, t: f64) -> Option<R300> {
+    let is_blade = |value: R300| value == R300::zero() || value.is_bivector();
+    if !is_blade(a) || !is_blade(b) {
+        return None;
+    }
+    let magnitude = (1.0 - t) * a.norm() + t * b.norm();
+    // Planes turn like their normals, the duals of the bivectors.
+    let normal = match (a == R300::zero(), b == R300::zero()) {
+        (true, true) => return Some(R300::zero()),
+        (false, true) => a.Dual().normalized(),
+        (true, false) => b.Dual().normalized(),
+        (false, false) => turn(a.Dual().normalized(), b.Dual().normalized(), t),
+    };
+    // The inverse of the dual, (B e_{123}) e_{123} = -B.
+    Some(-magnitude * normal.Dual())
+}
+
+/// The unit vector a fraction `t` of the way along the arc from `u` to `v`. Opposite vectors
+/// have no plane of their own, they turn in any plane through them.
+fn turn(u: R300, v: R300, t: f64) -> R300 {
+    let cos = u.dot(v)[0].clamp(-1.0, 1.0);
+    let angle = cos.acos();
+    if angle < TOLERANCE {
+        return lerp(u, v, t).normalized();
+    }
+    let perpendicular = if PI - angle < TOLERANCE {
+        [R300::vector(1.0, 0.0, 0.0), R300::vector(0.0, 1.0, 0.0)]
+            .into_iter()
+            .map(|axis| axis - axis.dot(u)[0] * u)
+            .max_by(|a, b| a.norm().total_cmp(&b.norm()))
+            .unwrap()
+            .normalized()
+    } else {
+        (v - cos * u).normalized()
+    };
+    (t * angle).cos() * u + (t * angle).sin() * perpendicular
+}
+
+/// A sum of a scalar and a bivector that is not zero.
+fn is_rotor(value: R300) -> bool {
+    let even = value.grade_part(0) + value.grade_part(2);
+    value != R300::zero() && (value - even).norm() < TOLERANCE
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    pub time: f64,
+    pub value: R300,
+}
+
+/// The keyframes of one variable. Without an `interpolation` each pair of neighbouring
+/// keyframes uses the rule that fits its values.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Track {
+    pub keyframes: Vec<Keyframe>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interpolation: Option<Interpolation>,
+}
+
+impl Track {
+    /// The value at `time`, held at the first and last keyframes outside of them.
+    pub fn value_at(&self, time: f64) -> Result<R300, TimelineError> {
+        if time.is_nan() {
+            return Err(TimelineError::InvalidTime);
+        }
+        let mut keyframes = self.keyframes.clone();
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        let (first, last) = match (keyframes.first(), keyframes.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Err(TimelineError::EmptyTrack),
+        };
+        if time <= first.time {
+            return Ok(first.value);
+        }
+        if time >= last.time {
+            return Ok(last.value);
+        }
+        let next = keyframes
+            .iter()
+            .position(|k| k.time > time)
+            .ok_or(TimelineError::InvalidTime)?;
+        let (from, to) = (keyframes[next - 1], keyframes[next]);
+        let t = (time - from.time) / (to.time - from.time);
+        let interpolation = self
+            .interpolation
+            .unwrap_or_else(|| Interpolation::for_values(from.value, to.value));
+        interpolation
+            .apply(from.value, to.value, t)
+            .ok_or(TimelineError::Unsupported {
+                interpolation,
+                time: from.time,
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimelineError {
+    EmptyTrack,
+    /// The time is NaN.
+    InvalidTime,
+    /// The keyframes at `time` and after it do not fit `interpolation`, e.g. vectors
+    /// interpolated as rotors.
+    Unsupported {
+        interpolation: Interpolation,
+        time: f64,
+    },
+    /// An error in the track of the variable `name`.
+    Track {
+        name: String,
+        error: Box<TimelineError>,
+    },
+}
+
+impl fmt::Display for TimelineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimelineError::EmptyTrack => write!(f, "The track has no keyframes"),
+            TimelineError::InvalidTime => write!(f, "The time is not a number"),
+            TimelineError::Unsupported {
+                interpolation,
+                time,
+            } => write!(
+                f,
+                "The keyframe at {} cannot be interpolated as {:?}",
+                time, interpolation
+            ),
+            TimelineError::Track { name, error } => write!(f, "{}: {}", name, error),
+        }
+    }
+}
+
+impl std::error::Error for TimelineError {}
+
+/// Keyframed variables, e.g. a rotor turning while a vector stays fixed.
+///
+/// In JSON: `{"tracks": {"R": {"keyframes": [{"time": 0, "value": {...}}, ...]}}}`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Timeline {
+    pub tracks: HashMap<String, Track>,
+}
+
+impl Timeline {
+    /// The times of the first and last keyframes, None without keyframes.
+    pub fn span(&self) -> Option<(f64, f64)> {
+        let times = self
+            .tracks
+            .values()
+            .flat_map(|track| track.keyframes.iter().map(|k| k.time));
+        times.fold(None, |span, time| match span {
+            None => Some((time, time)),
+            Some((start, end)) => Some((f64::min(start, time), f64::max(end, time))),
+        })
+    }
+
+    /// `vars` with every keyframed variable at its value at `time`.
+    pub fn values_at(
+        &self,
+        vars: &HashMap<String, R300>,
+        time: f64,
+    ) -> Result<HashMap<String, R300>, TimelineError> {
+        let mut values = vars.clone();
+        for (name, track) in &self.tracks {
+            let value = track.value_at(time).map_err(|error| TimelineError::Track {
+                name: name.clone(),
+                error: Box::new(error),
+            })?;
+            values.insert(name.clone(), value);
+        }
+        Ok(values)
+    }
+
+    /// Evaluates `ast` at `time`, the variables without a track keeping their value in `vars`.
+    pub fn evaluate<'a>(
+        &self,
+        ast: &'a ASTNode,
+        vars: &HashMap<String, R300>,
+        symbols: &ReservedSymbols,
+        time: f64,
+    ) -> Result<Evaluated<'a>, TimelineError> {
+        Ok(evaluate(ast, &self.values_at(vars, time)?, symbols))
+    }
+}
@@ -3,130 +3,760 @@
 #![allow(non_upper_case_globals)]
 #![allow(non_snake_case)]
 #![allow(non_camel_case_types)]
+pub mod annotated;
+pub mod constraint;
+pub mod construction;
+pub mod convention;
+pub mod decompose;
+pub mod derivation;
+pub mod equation;
+pub mod error;
+pub mod exact;
+pub mod expand;
+pub mod format;
+pub mod functions;
+pub mod generate;
+pub mod inference;
+pub mod inverse;
+pub mod keyframe;
+pub mod multivector;
+pub mod parameter;
+pub mod parse;
+pub mod primitive;
 pub mod r300;
+pub mod rng;
+pub mod scalar;
+pub mod scene;
+pub mod schema;
+pub mod sensitivity;
+pub mod symbolic;
+pub mod symbols;
+use annotated::Annotated;
+use derivation::Derivation;
+use error::EvalError;
 use latex_expr_parser::{ASTNode, ASTNodeType, TokenKind};
 use r300::R300;
+use schema::EvaluatedExpression;
+use sensitivity::PartialDerivative;
 use serde::Serialize;
 use serde_wasm_bindgen::from_value;
-use std::{collections::HashMap, ops::Mul};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Mul,
+};
+use symbols::ReservedSymbols;
 use wasm_bindgen::prelude::*;
 
-// TODO: this duplication is not my favorite
-#[derive(Debug, Serialize)]
-pub struct AstNodeWithValue {
-    start: usize,
-    end: usize,
-    value: R300,
-    r#type: AstNodeWithValueType,
+/// What evaluation attaches to each node of an expression.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    /// None when this node or one below it could not be evaluated.
+    pub value: Option<R300>,
+    /// The grade of `value` when it has a single one.
+    pub grade: Option<usize>,
+    /// Why this node could not be evaluated. The nodes above it have no value and no errors.
+    pub errors: Vec<EvalError>,
+    pub derivation: Option<Derivation>,
+    /// How the value changes with each component of the inputs, see `sensitivity::differentiate`.
+    pub jacobian: Option<Vec<PartialDerivative>>,
+    /// A short note for readers, e.g. that an identifier is a reserved symbol.
+    pub description: Option<String>,
+    /// The identifier resolved to a reserved symbol rather than a variable.
+    pub reserved: bool,
 }
 
-#[derive(Debug, Serialize)]
-pub enum AstNodeWithValueType {
-    Identifier {
-        name: String,
-    },
+pub type Evaluated<'a> = Annotated<'a, Metadata>;
+
+/// How the evaluator reads a node. The operands stay in `children`, e.g. `A^{-1}` is an
+/// `Inverse` whose first child is `A`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind<'a> {
+    Identifier(&'a str),
+    Constant(&'a str),
     Int,
-    BinaryOpNode {
-        op: TokenKind,
-        left: Box<AstNodeWithValue>,
-        right: Box<AstNodeWithValue>,
-    },
-    UnaryOpNode {
-        op: TokenKind,
-        operand: Box<AstNodeWithValue>,
-    },
+    BinaryOp(TokenKind),
+    UnaryOp(TokenKind),
+    Reverse,
+    Dual,
+    Conjugate,
+    Involute,
+    Inverse,
+    Power(i32),
+    Magnitude,
+    Angle,
+    Area,
+    FunctionCall(&'a str),
+    Equation { holds: bool },
+}
+
+impl<'a> Evaluated<'a> {
+    /// The value, zero for a node that could not be evaluated.
+    pub fn value(&self) -> R300 {
+        self.meta.value.unwrap_or(R300::zero())
+    }
+
+    pub fn kind(&self) -> Kind<'a> {
+        let node: &'a ASTNode = self.node;
+        match &node.r#type {
+            ASTNodeType::Int { .. } => Kind::Int,
+            ASTNodeType::Identifier { name } if self.meta.reserved => Kind::Constant(name),
+            ASTNodeType::Identifier { name } => Kind::Identifier(name),
+            ASTNodeType::BinaryOpNode {
+                op: TokenKind::Equals,
+                ..
+            } => Kind::Equation {
+                holds: equation::holds(
+                    self.children[0].value(),
+                    self.children[1].value(),
+                    equation::DEFAULT_TOLERANCE,
+                ),
+            },
+            ASTNodeType::BinaryOpNode {
+                op: TokenKind::Caret,
+                ..
+            } if self.children[0].kind() == Kind::Constant("e")
+                && self.children[0].value() == symbols::EULER =>
+            {
+                Kind::FunctionCall("exp")
+            }
+            ASTNodeType::BinaryOpNode {
+                op: TokenKind::Caret,
+                ..
+            } => match integer_exponent(self.children[1].value()) {
+                Some(-1) => Kind::Inverse,
+                Some(n) => Kind::Power(n),
+                None => Kind::BinaryOp(TokenKind::Caret),
+            },
+            ASTNodeType::BinaryOpNode { op, .. } => Kind::BinaryOp(*op),
+            ASTNodeType::UnaryOpNode { op, .. } => match op {
+                TokenKind::Dagger | TokenKind::Tilde => Kind::Reverse,
+                TokenKind::Star => Kind::Dual,
+                TokenKind::Bar => Kind::Conjugate,
+                TokenKind::Hat => Kind::Involute,
+                TokenKind::Pipe | TokenKind::DoublePipe => Kind::Magnitude,
+                _ => Kind::UnaryOp(*op),
+            },
+            ASTNodeType::FunctionCall { name, .. } => match name.trim_start_matches('\\') {
+                "angle" => Kind::Angle,
+                "area" => Kind::Area,
+                function => Kind::FunctionCall(function),
+            },
+        }
+    }
+
+    /// The first error in evaluation order.
+    pub fn first_error(&self) -> Option<&EvalError> {
+        self.post_order()
+            .into_iter()
+            .find_map(|node| node.meta.errors.first())
+    }
 }
 
 #[wasm_bindgen]
-pub fn find_identifiers(expr: &str) -> Vec<String> {
-    let ast = serde_json::from_str::<ASTNode>(expr).unwrap();
+pub fn find_identifiers(expr: &str) -> Result<Vec<String>, JsError> {
+    find_identifiers_with_symbols(expr, &ReservedSymbols::default())
+}
+
+#[wasm_bindgen]
+pub fn find_identifiers_with_symbols(
+    expr: &str,
+    symbols: &ReservedSymbols,
+) -> Result<Vec<String>, JsError> {
+    let ast = serde_json::from_str::<ASTNode>(expr)?;
     let mut identifiers = Vec::new();
-    find_ast_identifiers(&ast, &mut identifiers);
-    identifiers
+    find_ast_identifiers(&ast, symbols, &mut identifiers);
+    Ok(identifiers)
 }
 
-pub fn find_ast_identifiers(ast: &ASTNode, identifiers: &mut Vec<String>) {
+/// Collects the free identifiers of `ast`, skipping the reserved symbols since the evaluator
+/// already knows their values.
+pub fn find_ast_identifiers(
+    ast: &ASTNode,
+    symbols: &ReservedSymbols,
+    identifiers: &mut Vec<String>,
+) {
     match &ast.r#type {
+        ASTNodeType::Identifier { name } if symbols.contains(name) => {}
         ASTNodeType::Identifier { name } => identifiers.push(name.clone()),
         ASTNodeType::BinaryOpNode { left, right, op: _ } => {
-            find_ast_identifiers(&left, identifiers);
-            find_ast_identifiers(&right, identifiers);
+            find_ast_identifiers(left, symbols, identifiers);
+            find_ast_identifiers(right, symbols, identifiers);
         }
         ASTNodeType::UnaryOpNode { operand, op: _ } => {
-            find_ast_identifiers(&operand, identifiers);
+            find_ast_identifiers(operand, symbols, identifiers);
+        }
+        ASTNodeType::FunctionCall { args, name: _ } => {
+            for arg in args {
+                find_ast_identifiers(arg, symbols, identifiers);
+            }
         }
         _ => {}
     }
 }
 
+/// Evaluates `expr` and returns the tree in the format documented in `schema`.
+#[wasm_bindgen]
+pub fn calculate_expression(expr: &str, vars: JsValue) -> Result<String, JsError> {
+    calculate_expression_with_symbols(expr, vars, &ReservedSymbols::default())
+}
+
+#[wasm_bindgen]
+pub fn calculate_expression_with_symbols(
+    expr: &str,
+    vars: JsValue,
+    symbols: &ReservedSymbols,
+) -> Result<String, JsError> {
+    let ast = serde_json::from_str::<ASTNode>(expr)?;
+    let vars: HashMap<String, R300> = from_value(vars)?;
+    let calculated = calculate_ast_expression(&ast, &vars, symbols)?;
+    Ok(serde_json::to_string(&EvaluatedExpression::from(
+        &calculated,
+    ))?)
+}
+
+/// Like `calculate_expression`, with every node carrying the rule and substitutions that
+/// produced its value. `options` is a partial `FormatOptions`, a non-standard `convention` also
+/// writes every value in its blades.
+#[wasm_bindgen]
+pub fn calculate_expression_with_derivation(
+    expr: &str,
+    vars: JsValue,
+    options: JsValue,
+) -> Result<String, JsError> {
+    let ast = serde_json::from_str::<ASTNode>(expr)?;
+    let vars: HashMap<String, R300> = from_value(vars)?;
+    let options = format_options(options)?;
+    let mut calculated = calculate_ast_expression(&ast, &vars, &ReservedSymbols::default())?;
+    derivation::derive(&mut calculated, &options);
+    Ok(serde_json::to_string(
+        &EvaluatedExpression::with_convention(&calculated, &options.convention),
+    )?)
+}
+
+/// Rewrites an evaluated expression saved by any earlier version in the current format.
+#[wasm_bindgen]
+pub fn upgrade_evaluated_expression(json: &str) -> Result<String, JsError> {
+    Ok(serde_json::to_string(&schema::from_json(json)?)?)
+}
+
+/// Default values for the free identifiers of `expr`, as a JSON object of R300 values.
+/// `declarations` maps identifiers to a partial `generate::Declaration`, e.g.
+/// `{"n": {"constraints": [{"kind": "unit"}, {"kind": "orthogonal", "to": "a"}]}}` or
+/// `{"t": {"parameter": {"min": 0, "max": 1}}}`.
+/// Without a `seed` the values only depend on the expression, so reloading the page shows the
+/// same picture.
+#[wasm_bindgen]
+pub fn generate_variables(
+    expr: &str,
+    declarations: JsValue,
+    seed: Option<u32>,
+) -> Result<String, JsError> {
+    let declarations: HashMap<String, generate::Declaration> =
+        if declarations.is_undefined() || declarations.is_null() {
+            HashMap::new()
+        } else {
+            from_value(declarations)?
+        };
+    let ast = serde_json::from_str::<ASTNode>(expr)?;
+    let mut names = Vec::new();
+    find_ast_identifiers(&ast, &ReservedSymbols::default(), &mut names);
+    let seed = match seed {
+        Some(seed) => u64::from(seed),
+        None => generate::seed(&ast)?,
+    };
+    let values = generate::generate(&names, &declarations, seed)?;
+    Ok(serde_json::to_string(&values)?)
+}
+
+/// Evaluates `expr` with the scalar `name` moved across `parameter`, a `parameter::Parameter`
+/// such as `{"min": 0, "max": 6.28, "step": 0.1}`. Returns the value of every node at each
+/// step, for animating the expression or drawing the path of its result.
+#[wasm_bindgen]
+pub fn sweep_parameter(
+    expr: &str,
+    vars: JsValue,
+    name: &str,
+    parameter: JsValue,
+) -> Result<String, JsError> {
+    let ast = serde_json::from_str::<ASTNode>(expr)?;
+    let vars: HashMap<String, R300> = from_value(vars)?;
+    let parameter: parameter::Parameter = from_value(parameter)?;
+    let sweep = parameter::sweep(&ast, &vars, &ReservedSymbols::default(), name, &parameter)?;
+    Ok(serde_json::to_string(&sweep)?)
+}
+
+/// Evaluates `expr` at `time` on `timeline`, a `keyframe::Timeline` giving some of the variables
+/// keyframes. The other variables keep their value in `vars`.
+#[wasm_bindgen]
+pub fn calculate_expression_at_time(
+    expr: &str,
+    vars: JsValue,
+    timeline: JsValue,
+    time: f64,
+) -> Result<String, JsError> {
+    let ast = serde_json::from_str::<ASTNode>(expr)?;
+    let vars: HashMap<String, R300> = from_value(vars)?;
+    let timeline: keyframe::Timeline = from_value(timeline)?;
+    let values = timeline.values_at(&vars, time)?;
+    let calculated = calculate_ast_expression(&ast, &values, &ReservedSymbols::default())?;
+    Ok(serde_json::to_string(&EvaluatedExpression::from(
+        &calculated,
+    ))?)
+}
+
+/// Projects `vars` onto `constraints`, a JSON array of `constraint::Constraint`, e.g. after the
+/// user dragged one of them. Returns the projected values and which constraints still fail.
+#[wasm_bindgen]
+pub fn project_variables(vars: JsValue, constraints: JsValue) -> Result<String, JsError> {
+    let vars: HashMap<String, R300> = from_value(vars)?;
+    let constraints: Vec<constraint::Constraint> = from_value(constraints)?;
+    Ok(serde_json::to_string(&constraint::solve(
+        &vars,
+        &constraints,
+    )?)?)
+}
+
+/// Like `calculate_expression`, with every node carrying its derivatives with respect to each
+/// component of the identifiers in `inputs`, all variables when `inputs` is undefined.
+#[wasm_bindgen]
+pub fn calculate_expression_with_jacobian(
+    expr: &str,
+    vars: JsValue,
+    inputs: JsValue,
+) -> Result<String, JsError> {
+    let ast = serde_json::from_str::<ASTNode>(expr)?;
+    let vars: HashMap<String, R300> = from_value(vars)?;
+    let inputs: Vec<String> = if inputs.is_undefined() || inputs.is_null() {
+        let mut names = vars.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        names
+    } else {
+        from_value(inputs)?
+    };
+    let mut calculated = calculate_ast_expression(&ast, &vars, &ReservedSymbols::default())?;
+    sensitivity::differentiate(&mut calculated, &vars, &inputs);
+    Ok(serde_json::to_string(&EvaluatedExpression::from(
+        &calculated,
+    ))?)
+}
+
+/// Like `calculate_expression`, with `vars` first projected onto `constraints`.
+#[wasm_bindgen]
+pub fn calculate_expression_with_constraints(
+    expr: &str,
+    vars: JsValue,
+    constraints: JsValue,
+) -> Result<String, JsError> {
+    let ast = serde_json::from_str::<ASTNode>(expr)?;
+    let vars: HashMap<String, R300> = from_value(vars)?;
+    let constraints: Vec<constraint::Constraint> = from_value(constraints)?;
+    let solution = constraint::solve(&vars, &constraints)?;
+    let calculated = calculate_ast_expression(&ast, &solution.values, &ReservedSymbols::default())?;
+    Ok(serde_json::to_string(&EvaluatedExpression::from(
+        &calculated,
+    ))?)
+}
+
+/// Solves for the free identifier `name` so that the node spanning `start..end` evaluates to
+/// `target`, e.g. while the user drags that node's result in the 3D view. Returns the new value
+/// of `name` and how far the node still is from `target`.
+#[wasm_bindgen]
+pub fn solve_for_variable(
+    expr: &str,
+    vars: JsValue,
+    start: usize,
+    end: usize,
+    target: &R300,
+    name: &str,
+) -> Result<String, JsError> {
+    let ast = serde_json::from_str::<ASTNode>(expr)?;
+    let vars: HashMap<String, R300> = from_value(vars)?;
+    let solution = inverse::solve_for(
+        &ast,
+        &vars,
+        &ReservedSymbols::default(),
+        (start, end),
+        *target,
+        name,
+    )?;
+    Ok(serde_json::to_string(&solution)?)
+}
+
+/// Infers the grades of every node of `expr` without evaluating it. `grades` maps identifiers
+/// to the grades they can have, e.g. `{"B": [2]}`. `declarations` are those of
+/// `generate_variables`, so a parameter is a scalar, and `grades` wins where both name an
+/// identifier. Undeclared identifiers are vectors.
+#[wasm_bindgen]
+pub fn analyze_expression(
+    expr: &str,
+    grades: JsValue,
+    declarations: JsValue,
+) -> Result<String, JsError> {
+    let ast = serde_json::from_str::<ASTNode>(expr)?;
+    let grades: HashMap<String, inference::Grades> = if grades.is_undefined() || grades.is_null() {
+        HashMap::new()
+    } else {
+        from_value(grades)?
+    };
+    let declarations: HashMap<String, generate::Declaration> =
+        if declarations.is_undefined() || declarations.is_null() {
+            HashMap::new()
+        } else {
+            from_value(declarations)?
+        };
+    let declared = declarations
+        .iter()
+        .map(|(name, declaration)| (name.clone(), declaration.grades()))
+        .chain(grades)
+        .collect::<HashMap<_, _>>();
+    let typed = inference::infer(&ast, &declared, &ReservedSymbols::default());
+    Ok(serde_json::to_string(&inference::Analysis::from(&typed))?)
+}
+
+/// How each operation in `expr` builds its value, as frames of plain shapes the 3D view can
+/// play back, e.g. the parallelogram of `a \wedge b` being swept. Nodes without a picture
+/// are left out.
+#[wasm_bindgen]
+pub fn construct_expression(expr: &str, vars: JsValue) -> Result<String, JsError> {
+    let ast = serde_json::from_str::<ASTNode>(expr)?;
+    let vars: HashMap<String, R300> = from_value(vars)?;
+    let evaluated = evaluate(&ast, &vars, &ReservedSymbols::default());
+    Ok(serde_json::to_string(&construction::construct(&evaluated))?)
+}
+
+/// The shapes that draw each of `vars`, a `scene::Scene`. `options` is a partial
+/// `scene::SceneOptions`, e.g. `{"bivectors": "disc"}`.
+#[wasm_bindgen]
+pub fn describe_scene(vars: JsValue, options: JsValue) -> Result<String, JsError> {
+    let vars: BTreeMap<String, R300> = from_value(vars)?;
+    let options: scene::SceneOptions = if options.is_undefined() || options.is_null() {
+        Default::default()
+    } else {
+        from_value(options)?
+    };
+    Ok(serde_json::to_string(&scene::Scene::new(&vars, &options))?)
+}
+
+/// Like `describe_scene`, for every node of `expr` that has a value. Each item carries the span
+/// of its node, so that clicking it in the 3D view can select the node.
+#[wasm_bindgen]
+pub fn describe_expression_scene(
+    expr: &str,
+    vars: JsValue,
+    options: JsValue,
+) -> Result<String, JsError> {
+    let ast = serde_json::from_str::<ASTNode>(expr)?;
+    let vars: HashMap<String, R300> = from_value(vars)?;
+    let options: scene::SceneOptions = if options.is_undefined() || options.is_null() {
+        Default::default()
+    } else {
+        from_value(options)?
+    };
+    let evaluated = evaluate(&ast, &vars, &ReservedSymbols::default());
+    Ok(serde_json::to_string(&scene::Scene::from_evaluated(
+        &evaluated, &options,
+    ))?)
+}
+
+/// Splits `value` into its grade parts, each with the shapes that draw it, and reads a sum of a
+/// scalar and a bivector as an angle in a plane. Returns a `decompose::Decomposition` with a
+/// legend in LaTeX.
+#[wasm_bindgen]
+pub fn decompose_value(name: &str, value: &R300, options: JsValue) -> Result<String, JsError> {
+    let options: scene::SceneOptions = if options.is_undefined() || options.is_null() {
+        Default::default()
+    } else {
+        from_value(options)?
+    };
+    Ok(serde_json::to_string(&decompose::decompose(
+        name, *value, &options,
+    ))?)
+}
+
+/// Expands `expr` in terms of the components of its variables, `vars` only decides the grade
+/// of each variable. Blades are named by the `convention` of the partial `FormatOptions`.
+#[wasm_bindgen]
+pub fn calculate_symbolic_expression(
+    expr: &str,
+    vars: JsValue,
+    options: JsValue,
+) -> Result<String, JsError> {
+    let ast = serde_json::from_str::<ASTNode>(expr)?;
+    let vars: HashMap<String, R300> = from_value(vars)?;
+    let options = format_options(options)?;
+    let symbolic = symbolic::calculate_symbolic(
+        &ast,
+        &vars,
+        &ReservedSymbols::default(),
+        &options.convention,
+    )?;
+    Ok(serde_json::to_string(&symbolic)?)
+}
+
+/// Evaluates `expr` with exact rational coefficients, variable values are read as the decimals
+/// they print as. Blades are named by the `convention` of the partial `FormatOptions`.
+#[wasm_bindgen]
+pub fn calculate_exact_expression(
+    expr: &str,
+    vars: JsValue,
+    options: JsValue,
+) -> Result<String, JsError> {
+    let ast = serde_json::from_str::<ASTNode>(expr)?;
+    let vars: HashMap<String, R300> = from_value(vars)?;
+    let options = format_options(options)?;
+    let exact = exact::calculate_exact(
+        &ast,
+        &vars,
+        &ReservedSymbols::default(),
+        &options.convention,
+    )?;
+    Ok(serde_json::to_string(&exact)?)
+}
+
+/// Writes `value` with closed form coefficients such as `\\frac{\\sqrt{2}}{2}`. `options` is a
+/// partial `FormatOptions`, missing fields keep their defaults.
+#[wasm_bindgen]
+pub fn format_multivector(value: JsValue, options: JsValue) -> Result<String, JsError> {
+    let value: R300 = from_value(value)?;
+    Ok(value.to_closed_form(&format_options(options)?))
+}
+
+/// A partial `FormatOptions`, the defaults when `options` is undefined or null.
+fn format_options(options: JsValue) -> Result<format::FormatOptions, JsError> {
+    if options.is_undefined() || options.is_null() {
+        Ok(Default::default())
+    } else {
+        Ok(from_value(options)?)
+    }
+}
+
+/// Checks the equation in `expr` under `trials` random assignments of its variables, `vars`
+/// only decides the grade of each variable.
 #[wasm_bindgen]
-pub fn calculate_expression(expr: &str, vars: JsValue) -> String {
-    let ast = serde_json::from_str::<ASTNode>(expr).unwrap();
-    let vars: HashMap<String, R300> = from_value(vars).unwrap();
-    let calculated = calculate_ast_expression(&ast, &vars);
-    serde_json::to_string(&calculated).unwrap()
+pub fn check_identity(
+    expr: &str,
+    vars: JsValue,
+    trials: usize,
+    seed: u32,
+) -> Result<String, JsError> {
+    let ast = serde_json::from_str::<ASTNode>(expr)?;
+    let vars: HashMap<String, R300> = from_value(vars)?;
+    let check = equation::check_identity(
+        &ast,
+        &vars,
+        &ReservedSymbols::default(),
+        trials,
+        seed as u64,
+    )?;
+    Ok(serde_json::to_string(&check)?)
+}
+
+/// Evaluates `ast`, failing with the first error in evaluation order.
+fn calculate_ast_expression<'a>(
+    ast: &'a ASTNode,
+    vars: &HashMap<String, R300>,
+    symbols: &ReservedSymbols,
+) -> Result<Evaluated<'a>, EvalError> {
+    let evaluated = evaluate(ast, vars, symbols);
+    match evaluated.first_error() {
+        Some(error) => Err(error.clone()),
+        None => Ok(evaluated),
+    }
+}
+
+/// Evaluates every node of `ast`. A top-level `=` is an assertion: both sides are evaluated and
+/// the node's value is the residual `left - right`.
+pub fn evaluate<'a>(
+    ast: &'a ASTNode,
+    vars: &HashMap<String, R300>,
+    symbols: &ReservedSymbols,
+) -> Evaluated<'a> {
+    Annotated::build(ast, &mut |node, children: &[Evaluated]| {
+        let mut meta = Metadata::default();
+        let Some(values) = children
+            .iter()
+            .map(|child| child.meta.value)
+            .collect::<Option<Vec<_>>>()
+        else {
+            return meta;
+        };
+        let top = std::ptr::eq(node, ast);
+        match calculate_ast_node(node, &values, top, vars, symbols, &mut meta) {
+            Ok(value) => {
+                meta.grade = value.grade();
+                meta.value = Some(value);
+            }
+            Err(error) => meta.errors.push(error),
+        }
+        meta
+    })
 }
 
-fn calculate_ast_expression(ast: &ASTNode, vars: &HashMap<String, R300>) -> AstNodeWithValue {
+/// The value of one node from its children's `values`. Reserved symbols such as `i` resolve
+/// before `vars`, which like `find_ast_identifiers` never hold a value for them.
+fn calculate_ast_node(
+    ast: &ASTNode,
+    values: &[R300],
+    top: bool,
+    vars: &HashMap<String, R300>,
+    symbols: &ReservedSymbols,
+    meta: &mut Metadata,
+) -> Result<R300, EvalError> {
     match &ast.r#type {
-        ASTNodeType::Int { value } => AstNodeWithValue {
-            start: ast.start,
-            end: ast.end,
-            value: R300::new(*value as f64, 0),
-            r#type: AstNodeWithValueType::Int,
-        },
-        ASTNodeType::Identifier { name } => AstNodeWithValue {
-            start: ast.start,
-            end: ast.end,
-            value: vars[name].clone(),
-            r#type: AstNodeWithValueType::Identifier { name: name.clone() },
-        },
-        ASTNodeType::BinaryOpNode { op, left, right } => {
-            let left = calculate_ast_expression(&left, vars);
-            let right = calculate_ast_expression(&right, vars);
-            let left_val = left.value;
-            let right_val = right.value;
-            let value = match op {
-                TokenKind::Plus => left_val.add(right_val),
-                TokenKind::Minus => left_val.sub(right_val),
-                TokenKind::Multiply => left_val.mul(right_val),
-                TokenKind::Dot => left_val.dot(right_val),
-                TokenKind::Wedge => left_val.wedge(right_val),
-                TokenKind::Frac => left_val.divide(right_val),
-                _ => panic!("Unsupported binary operator: {:?}", op),
-            };
-            AstNodeWithValue {
+        ASTNodeType::Int { value } => Ok(R300::new(*value as f64, 0)),
+        ASTNodeType::Identifier { name } => match (symbols.get(name), vars.get(name)) {
+            (Some(value), _) => {
+                meta.reserved = true;
+                meta.description = Some("reserved symbol".to_string());
+                Ok(value)
+            }
+            (None, Some(value)) => Ok(*value),
+            (None, None) => Err(EvalError::UnboundIdentifier {
+                name: name.clone(),
                 start: ast.start,
                 end: ast.end,
-                value,
-                r#type: AstNodeWithValueType::BinaryOpNode {
+            }),
+        },
+        ASTNodeType::BinaryOpNode {
+            op: TokenKind::Caret,
+            ..
+        } if is_exponential(ast, symbols) => calculate_function(ast, "exp", arguments(ast, values)),
+        ASTNodeType::BinaryOpNode {
+            op: TokenKind::Caret,
+            left,
+            right,
+        } => {
+            let n = integer_exponent(values[1]).ok_or(EvalError::InvalidExponent {
+                start: right.start,
+                end: right.end,
+            })?;
+            values[0].pow(n).ok_or(EvalError::NotInvertible {
+                start: left.start,
+                end: left.end,
+            })
+        }
+        ASTNodeType::BinaryOpNode {
+            op: TokenKind::Equals,
+            ..
+        } if top => Ok(values[0] - values[1]),
+        ASTNodeType::BinaryOpNode { op, right, .. } => {
+            let (left_val, right_val) = (values[0], values[1]);
+            match op {
+                TokenKind::Plus => Ok(left_val + right_val),
+                TokenKind::Minus => Ok(left_val - right_val),
+                TokenKind::Multiply => Ok(left_val.mul(right_val)),
+                TokenKind::Dot => Ok(left_val.dot(right_val)),
+                TokenKind::Wedge => Ok(left_val.wedge(right_val)),
+                TokenKind::Frac => left_val.divide(right_val).ok_or(EvalError::NotInvertible {
+                    start: right.start,
+                    end: right.end,
+                }),
+                _ => Err(EvalError::UnsupportedOperator {
                     op: *op,
-                    left: Box::new(left),
-                    right: Box::new(right),
-                },
+                    start: ast.start,
+                    end: ast.end,
+                }),
+            }
+        }
+        ASTNodeType::UnaryOpNode { op, .. } => {
+            let operand_val = values[0];
+            match op {
+                TokenKind::Plus => Ok(operand_val),
+                TokenKind::Minus => Ok(-1.0 * operand_val),
+                TokenKind::Dagger | TokenKind::Tilde => Ok(operand_val.Reverse()),
+                TokenKind::Star => Ok(operand_val.Dual()),
+                TokenKind::Bar => Ok(operand_val.Conjugate()),
+                TokenKind::Hat => Ok(operand_val.Involute()),
+                TokenKind::Pipe | TokenKind::DoublePipe => Ok(R300::new(operand_val.norm(), 0)),
+                _ => Err(EvalError::UnsupportedOperator {
+                    op: *op,
+                    start: ast.start,
+                    end: ast.end,
+                }),
             }
         }
-        ASTNodeType::UnaryOpNode { op, operand } => {
-            let operand = calculate_ast_expression(&operand, vars);
-            let operand_val = operand.value;
-            let value = match op {
-                TokenKind::Plus => operand_val,
-                TokenKind::Minus => -1.0 * operand_val,
-                _ => panic!("Unsupported unary operator: {:?}", op),
+        ASTNodeType::FunctionCall { name, .. } => calculate_function(ast, name, values),
+    }
+}
+
+/// Applies a named function to its already evaluated arguments. Names are matched without the
+/// leading backslash, so `\angle` and `\operatorname{area}` both arrive here as plain names.
+fn calculate_function(ast: &ASTNode, name: &str, args: &[R300]) -> Result<R300, EvalError> {
+    let function = name.trim_start_matches('\\');
+    let invalid = |reason: &str| EvalError::InvalidArgument {
+        function: function.to_string(),
+        reason: reason.to_string(),
+        start: ast.start,
+        end: ast.end,
+    };
+    match function {
+        "angle" => {
+            let [left, right] = args else {
+                return Err(invalid("expected two arguments"));
             };
-            AstNodeWithValue {
+            let angle = left
+                .angle(*right)
+                .ok_or_else(|| invalid("expected two non-zero blades of the same grade"))?;
+            Ok(R300::new(angle, 0))
+        }
+        "area" => {
+            let [operand] = args else {
+                return Err(invalid("expected one argument"));
+            };
+            if !operand.is_bivector() {
+                return Err(invalid("expected a bivector"));
+            }
+            Ok(R300::new(operand.norm(), 0))
+        }
+        _ => {
+            let builtin = functions::builtin(function).ok_or(EvalError::UnknownFunction {
+                name: name.to_string(),
                 start: ast.start,
                 end: ast.end,
-                value,
-                r#type: AstNodeWithValueType::UnaryOpNode {
-                    op: *op,
-                    operand: Box::new(operand),
-                },
+            })?;
+            if args.len() != builtin.arity {
+                return Err(invalid(&format!("expected {} argument(s)", builtin.arity)));
             }
+            builtin.apply(args).map_err(invalid)
         }
     }
 }
 
+/// `e^{x}` with Euler's number as the base is the exponential of `x`, which unlike other powers
+/// is defined for more than whole scalar exponents.
+pub(crate) fn is_exponential(ast: &ASTNode, symbols: &ReservedSymbols) -> bool {
+    match &ast.r#type {
+        ASTNodeType::BinaryOpNode {
+            op: TokenKind::Caret,
+            left,
+            ..
+        } => matches!(
+            &left.r#type,
+            ASTNodeType::Identifier { name } if symbols.get(name) == Some(symbols::EULER)
+        ),
+        _ => false,
+    }
+}
+
+/// What a `Kind::FunctionCall` node applies to: every child of a call, only the exponent of
+/// `e^{x}`.
+pub fn arguments<'s, T>(ast: &ASTNode, children: &'s [T]) -> &'s [T] {
+    match &ast.r#type {
+        ASTNodeType::BinaryOpNode { .. } => &children[1..],
+        _ => children,
+    }
+}
+
+/// Exponents have to evaluate to a whole scalar, `A^{b}` for a vector `b` has no meaning here.
+fn integer_exponent(value: R300) -> Option<i32> {
+    let n = value[0];
+    if value.is_scalar() && n.fract() == 0.0 && n.abs() <= i32::MAX as f64 {
+        Some(n as i32)
+    } else {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -157,8 +787,1825 @@ mod tests {
         let mut vars = HashMap::new();
         vars.insert("a".to_string(), R300::vector(1.0, 0.0, 0.0));
         vars.insert("b".to_string(), R300::vector(2.0, 0.0, 0.0));
-        let result = calculate_ast_expression(&ast, &vars);
+        let result = calculate_ast_expression(&ast, &vars, &ReservedSymbols::default()).unwrap();
+
+        insta::assert_snapshot!(
+            serde_json::to_string_pretty(&EvaluatedExpression::from(&result)).unwrap()
+        );
+    }
+
+    fn identifier(name: &str) -> Box<ASTNode> {
+        Box::new(ASTNode {
+            start: 0,
+            end: 0,
+            r#type: ASTNodeType::Identifier {
+                name: name.to_string(),
+            },
+        })
+    }
+
+    fn int(value: i64) -> Box<ASTNode> {
+        Box::new(ASTNode {
+            start: 0,
+            end: 0,
+            r#type: ASTNodeType::Int { value },
+        })
+    }
+
+    fn unary(op: TokenKind, operand: Box<ASTNode>) -> Box<ASTNode> {
+        Box::new(ASTNode {
+            start: 0,
+            end: 0,
+            r#type: ASTNodeType::UnaryOpNode { op, operand },
+        })
+    }
+
+    fn binary(op: TokenKind, left: Box<ASTNode>, right: Box<ASTNode>) -> Box<ASTNode> {
+        Box::new(ASTNode {
+            start: 0,
+            end: 0,
+            r#type: ASTNodeType::BinaryOpNode { op, left, right },
+        })
+    }
+
+    #[test]
+    fn test_basis_blades_are_reserved() {
+        let ast = binary(
+            TokenKind::Wedge,
+            identifier("e_{3}"),
+            binary(
+                TokenKind::Multiply,
+                identifier("a"),
+                identifier("\\sigma_1"),
+            ),
+        );
+        let mut identifiers = Vec::new();
+        find_ast_identifiers(&ast, &ReservedSymbols::default(), &mut identifiers);
+        assert_eq!(identifiers, vec!["a".to_string()]);
+
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), R300::vector(0.0, 1.0, 0.0));
+        let result = calculate_ast_expression(&ast, &vars, &ReservedSymbols::default()).unwrap();
+        // e3 ^ (e2 e1) = e3 ^ -e12 = -e123
+        assert_eq!(result.value(), -1.0 * r300::e123);
+    }
+
+    #[test]
+    fn test_reserved_symbols_aliases() {
+        let symbols = ReservedSymbols::default();
+        assert_eq!(symbols.get("e_{31}"), Some(-1.0 * r300::e13));
+        assert_eq!(symbols.get("e_{2 3}"), Some(r300::e23));
+        assert_eq!(symbols.get("I"), Some(r300::e123));
+        assert_eq!(symbols.get("e_{11}"), None);
+
+        // A variable named like a reserved symbol is neither offered a value nor used.
+        let ast = binary(TokenKind::Plus, identifier("i"), identifier("a"));
+        let mut identifiers = Vec::new();
+        find_ast_identifiers(&ast, &symbols, &mut identifiers);
+        assert_eq!(identifiers, ["a"]);
+        let vars = HashMap::from([
+            ("i".to_string(), R300::vector(1.0, 0.0, 0.0)),
+            ("a".to_string(), R300::vector(0.0, 1.0, 0.0)),
+        ]);
+        let result = evaluate(&ast, &vars, &symbols);
+        assert_eq!(result.children[0].kind(), Kind::Constant("i"));
+        assert_eq!(result.value(), r300::e123 + R300::vector(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_unary_ga_operators() {
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), R300::vector(2.0, 0.0, 0.0));
+        vars.insert("B".to_string(), R300::bivector(1.0, 2.0, 3.0));
+        let symbols = ReservedSymbols::default();
+
+        let reverse = unary(TokenKind::Dagger, identifier("B"));
+        let result = calculate_ast_expression(&reverse, &vars, &symbols).unwrap();
+        assert_eq!(result.value(), R300::bivector(-1.0, -2.0, -3.0));
+        assert_eq!(result.kind(), Kind::Reverse);
+
+        let inverse = binary(
+            TokenKind::Caret,
+            identifier("a"),
+            unary(TokenKind::Minus, int(1)),
+        );
+        let result = calculate_ast_expression(&inverse, &vars, &symbols).unwrap();
+        assert_eq!(result.value(), R300::vector(0.5, 0.0, 0.0));
+        assert_eq!(result.kind(), Kind::Inverse);
+
+        let square = binary(TokenKind::Caret, identifier("a"), int(2));
+        let result = calculate_ast_expression(&square, &vars, &symbols).unwrap();
+        assert_eq!(result.value(), R300::new(4.0, 0));
+        assert_eq!(result.kind(), Kind::Power(2));
+    }
+
+    #[test]
+    fn test_inverse_and_division() {
+        let a = R300::vector(1.0, 2.0, 0.0) + R300::new(3.0, 0) + R300::bivector(0.0, 1.0, 0.0);
+        let product = a * a.inverse().unwrap();
+        for i in 0..8 {
+            let expected = if i == 0 { 1.0 } else { 0.0 };
+            assert!((product[i] - expected).abs() < 1e-12);
+        }
+        assert_eq!(R300::vector(1.0, 1.0, 0.0).pow(-2), Some(R300::new(0.5, 0)));
+        assert_eq!((R300::new(1.0, 0) + r300::e1).inverse(), None);
+
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), R300::vector(1.0, 0.0, 0.0));
+        vars.insert("b".to_string(), R300::vector(0.0, 2.0, 0.0));
+        let fraction = binary(TokenKind::Frac, identifier("a"), identifier("b"));
+        let result = calculate_ast_expression(&fraction, &vars, &ReservedSymbols::default());
+        assert_eq!(result.unwrap().value(), R300::bivector(0.5, 0.0, 0.0));
+
+        let bad_exponent = binary(TokenKind::Caret, identifier("a"), identifier("b"));
+        let result = calculate_ast_expression(&bad_exponent, &vars, &ReservedSymbols::default());
+        assert!(matches!(result, Err(EvalError::InvalidExponent { .. })));
+    }
+
+    fn call(name: &str, args: Vec<ASTNode>) -> Box<ASTNode> {
+        Box::new(ASTNode {
+            start: 0,
+            end: 0,
+            r#type: ASTNodeType::FunctionCall {
+                name: name.to_string(),
+                args,
+            },
+        })
+    }
+
+    #[test]
+    fn test_norm_angle_and_area() {
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), R300::vector(3.0, 0.0, 0.0));
+        vars.insert("b".to_string(), R300::vector(1.0, 1.0, 0.0));
+        let symbols = ReservedSymbols::default();
+
+        let norm = unary(TokenKind::Pipe, identifier("b"));
+        let result = calculate_ast_expression(&norm, &vars, &symbols).unwrap();
+        assert_eq!(result.value(), R300::new(2.0_f64.sqrt(), 0));
+
+        let angle = call("\\angle", vec![*identifier("a"), *identifier("b")]);
+        let result = calculate_ast_expression(&angle, &vars, &symbols).unwrap();
+        assert!((result.value()[0] - std::f64::consts::FRAC_PI_4).abs() < 1e-12);
+
+        let area = call(
+            "area",
+            vec![*binary(TokenKind::Wedge, identifier("a"), identifier("b"))],
+        );
+        let result = calculate_ast_expression(&area, &vars, &symbols).unwrap();
+        assert!((result.value()[0] - 3.0).abs() < 1e-12);
+
+        let area_of_vector = call("area", vec![*identifier("a")]);
+        let result = calculate_ast_expression(&area_of_vector, &vars, &symbols);
+        assert!(matches!(result, Err(EvalError::InvalidArgument { .. })));
+    }
+
+    #[test]
+    fn test_magnitude_is_euclidean() {
+        let a = R300::new(1.0, 0) + r300::e1;
+        assert_eq!(a.magnitude_squared(), 2.0);
+        assert_eq!(R300::bivector(0.0, 3.0, 4.0).norm(), 5.0);
+        assert_eq!(r300::e123.norm(), r300::e123.inorm());
+    }
+
+    #[test]
+    fn test_builtin_functions() {
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), R300::vector(3.0, 4.0, 0.0));
+        vars.insert(
+            "B".to_string(),
+            R300::bivector(std::f64::consts::FRAC_PI_2, 0.0, 0.0),
+        );
+        let symbols = ReservedSymbols::default();
+
+        let sqrt = call(
+            "\\sqrt",
+            vec![*binary(TokenKind::Caret, identifier("a"), int(2))],
+        );
+        let result = calculate_ast_expression(&sqrt, &vars, &symbols).unwrap();
+        assert_eq!(result.value(), R300::new(5.0, 0));
+        assert_eq!(result.kind(), Kind::FunctionCall("sqrt"));
+
+        // exp(pi/2 e12) = e12, a quarter turn in the e1e2 plane
+        let exp = call("exp", vec![*identifier("B")]);
+        let result = calculate_ast_expression(&exp, &vars, &symbols).unwrap();
+        assert!((result.value() - r300::e12).norm() < 1e-12);
+
+        let log = call("log", vec![*exp]);
+        let result = calculate_ast_expression(&log, &vars, &symbols).unwrap();
+        assert!((result.value() - vars["B"]).norm() < 1e-12);
+
+        let mixed = call(
+            "cos",
+            vec![*binary(TokenKind::Plus, identifier("a"), identifier("B"))],
+        );
+        let result = calculate_ast_expression(&mixed, &vars, &symbols);
+        assert!(matches!(result, Err(EvalError::InvalidArgument { .. })));
+
+        let unknown = call("\\foo", vec![*identifier("a")]);
+        let result = calculate_ast_expression(&unknown, &vars, &symbols);
+        assert!(matches!(result, Err(EvalError::UnknownFunction { .. })));
+    }
+
+    #[test]
+    fn test_blade_closed_forms_match_scalar_identities() {
+        let b = R300::bivector(0.3, -0.4, 1.2);
+        let (cos, sin) = (b.cos().unwrap(), b.sin().unwrap());
+        // cos^2 + sin^2 = 1 also holds for a single blade since it commutes with itself
+        assert!((cos * cos + sin * sin - 1.0).norm() < 1e-12);
+        let v = R300::vector(0.5, 0.0, 0.2) + 0.7;
+        let (cosh, sinh) = (v.cosh().unwrap(), v.sinh().unwrap());
+        assert!((cosh * cosh - sinh * sinh - 1.0).norm() < 1e-12);
+        assert!((v.exp().unwrap() - cosh - sinh).norm() < 1e-12);
+        assert_eq!(R300::new(-4.0, 0).sqrt(), None);
+    }
+
+    #[test]
+    fn test_equation_checking() {
+        // a . (b ^ c) = (a . b) c - (a . c) b
+        let identity = binary(
+            TokenKind::Equals,
+            binary(
+                TokenKind::Dot,
+                identifier("a"),
+                binary(TokenKind::Wedge, identifier("b"), identifier("c")),
+            ),
+            binary(
+                TokenKind::Minus,
+                binary(
+                    TokenKind::Multiply,
+                    binary(TokenKind::Dot, identifier("a"), identifier("b")),
+                    identifier("c"),
+                ),
+                binary(
+                    TokenKind::Multiply,
+                    binary(TokenKind::Dot, identifier("a"), identifier("c")),
+                    identifier("b"),
+                ),
+            ),
+        );
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), R300::vector(1.0, 2.0, 3.0));
+        vars.insert("b".to_string(), R300::vector(-1.0, 0.5, 2.0));
+        vars.insert("c".to_string(), R300::vector(0.0, 4.0, -1.0));
+        let symbols = ReservedSymbols::default();
+
+        let result = calculate_ast_expression(&identity, &vars, &symbols).unwrap();
+        assert_eq!(result.kind(), Kind::Equation { holds: true });
+        let check = equation::check_identity(&identity, &vars, &symbols, 50, 7).unwrap();
+        assert!(check.holds);
+        assert_eq!(check.failures, 0);
+
+        // a ^ b = b ^ a only holds when a and b are parallel
+        let not_identity = binary(
+            TokenKind::Equals,
+            binary(TokenKind::Wedge, identifier("a"), identifier("b")),
+            binary(TokenKind::Wedge, identifier("b"), identifier("a")),
+        );
+        let check = equation::check_identity(&not_identity, &vars, &symbols, 10, 7).unwrap();
+        assert!(!check.holds);
+        assert!(check.counterexample.is_some());
+
+        let nested = binary(TokenKind::Plus, identifier("a"), not_identity);
+        let result = calculate_ast_expression(&nested, &vars, &symbols);
+        assert!(matches!(
+            result,
+            Err(EvalError::UnsupportedOperator {
+                op: TokenKind::Equals,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_derivation_trace() {
+        let ast = binary(
+            TokenKind::Dot,
+            identifier("a"),
+            binary(TokenKind::Wedge, identifier("b"), identifier("c")),
+        );
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), R300::vector(1.0, 0.0, 0.0));
+        vars.insert("b".to_string(), R300::vector(1.0, 1.0, 0.0));
+        vars.insert("c".to_string(), R300::vector(0.0, 2.0, 0.0));
+        let mut result =
+            calculate_ast_expression(&ast, &vars, &ReservedSymbols::default()).unwrap();
+        assert_eq!(result.meta.derivation, None);
+
+        derivation::derive(&mut result, &format::FormatOptions::default());
+        let derivation = result.meta.derivation.clone().unwrap();
+        assert_eq!(
+            derivation.rule,
+            "a \\cdot (b \\wedge c) = (a \\cdot b)c - (a \\cdot c)b"
+        );
+        assert_eq!(
+            derivation.steps,
+            vec![
+                "= 1 (2 e_{2}) - 0 (e_{1} + e_{2})".to_string(),
+                "= 2 e_{2}".to_string()
+            ]
+        );
+
+        let wedge = result.children[1].meta.derivation.clone().unwrap();
+        assert_eq!(
+            wedge.steps[0],
+            "= (1 \\cdot 2 - 1 \\cdot 0) e_{12} + (1 \\cdot 0 - 0 \\cdot 0) e_{13} + (1 \\cdot 0 - 0 \\cdot 2) e_{23}"
+        );
+        // Powers multiply the base, or its inverse for negative exponents.
+        let inverse_square = binary(
+            TokenKind::Caret,
+            identifier("b"),
+            unary(TokenKind::Minus, int(2)),
+        );
+        let mut result =
+            calculate_ast_expression(&inverse_square, &vars, &ReservedSymbols::default()).unwrap();
+        derivation::derive(&mut result, &format::FormatOptions::default());
+        assert_eq!(
+            result.meta.derivation.unwrap().rule,
+            "b^{-2} = \\underbrace{b^{-1} \\cdots b^{-1}}_{2}"
+        );
+        let one = binary(TokenKind::Caret, identifier("b"), int(0));
+        let mut result =
+            calculate_ast_expression(&one, &vars, &ReservedSymbols::default()).unwrap();
+        derivation::derive(&mut result, &format::FormatOptions::default());
+        assert_eq!(result.meta.derivation.unwrap().rule, "b^{0} = 1");
+
+        vars.insert("B".to_string(), R300::zero());
+        let rotor = call("exp", vec![*identifier("B")]);
+        let mut result =
+            calculate_ast_expression(&rotor, &vars, &ReservedSymbols::default()).unwrap();
+        derivation::derive(&mut result, &format::FormatOptions::default());
+        assert_eq!(
+            result.meta.derivation.unwrap(),
+            Derivation {
+                rule: "e^{B} = e^{0} = 1".to_string(),
+                steps: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_partial_evaluation() {
+        // a + (b \wedge x) with x unbound
+        let ast = binary(
+            TokenKind::Plus,
+            identifier("a"),
+            binary(TokenKind::Wedge, identifier("e_1"), identifier("x")),
+        );
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), R300::vector(1.0, 0.0, 0.0));
+        let evaluated = evaluate(&ast, &vars, &ReservedSymbols::default());
+
+        assert_eq!(evaluated.meta.value, None);
+        assert!(evaluated.meta.errors.is_empty());
+        assert_eq!(evaluated.children[0].meta.grade, Some(1));
+        let wedge = &evaluated.children[1];
+        assert_eq!(wedge.children[0].kind(), Kind::Constant("e_1"));
+        assert_eq!(
+            wedge.children[0].meta.description.as_deref(),
+            Some("reserved symbol")
+        );
+        assert!(matches!(
+            evaluated.first_error(),
+            Some(EvalError::UnboundIdentifier { name, .. }) if name == "x"
+        ));
+
+        let grades = evaluated.map(&mut |node| node.meta.grade);
+        let order = grades
+            .post_order()
+            .iter()
+            .map(|node| node.meta)
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec![Some(1), Some(1), None, None, None]);
+    }
+
+    #[test]
+    fn test_grade_inference() {
+        use inference::{infer, Grades, Issue};
+        let symbols = ReservedSymbols::default();
+        let mut declared = HashMap::new();
+        declared.insert("B".to_string(), Grades::BIVECTOR);
+        let grades_of = |ast: &ASTNode| infer(ast, &declared, &symbols).meta.grades;
+
+        let wedge = binary(TokenKind::Wedge, identifier("a"), identifier("b"));
+        assert_eq!(grades_of(&wedge), Grades::BIVECTOR);
+        let product = binary(TokenKind::Multiply, identifier("a"), identifier("b"));
+        assert_eq!(grades_of(&product), Grades::SCALAR.union(Grades::BIVECTOR));
+        let contraction = binary(TokenKind::Dot, identifier("a"), identifier("B"));
+        assert_eq!(grades_of(&contraction), Grades::VECTOR);
+        let dual = unary(TokenKind::Star, identifier("e_{12}"));
+        assert_eq!(grades_of(&dual), Grades::VECTOR);
+        let rotor = call("exp", vec![*identifier("B")]);
+        assert_eq!(grades_of(&rotor), Grades::SCALAR.union(Grades::BIVECTOR));
+        let inverse = binary(
+            TokenKind::Caret,
+            binary(TokenKind::Plus, identifier("a"), int(1)),
+            unary(TokenKind::Minus, int(1)),
+        );
+        assert_eq!(grades_of(&inverse), Grades::ALL);
+        // Large exponents stop once the grades repeat: even for even powers of a vector.
+        let power = binary(TokenKind::Caret, identifier("a"), int(1_000_000_000_000));
+        assert_eq!(grades_of(&power), Grades::SCALAR.union(Grades::BIVECTOR));
+        let power = binary(TokenKind::Caret, identifier("a"), int(1_000_000_000_001));
+        assert_eq!(grades_of(&power), Grades::VECTOR.union(Grades::TRIVECTOR));
+        // I^2 = -1, so the pseudoscalar has a logarithm.
+        let log = call("log", vec![*identifier("I")]);
+        let typed = infer(&log, &declared, &symbols);
+        assert!(typed.meta.issues.is_empty());
+        assert_eq!(typed.meta.grades, Grades::SCALAR.union(Grades::TRIVECTOR));
+
+        // (a ^ b) ^ (c ^ d) has grade 4, which does not exist in 3D
+        let four = binary(
+            TokenKind::Wedge,
+            binary(TokenKind::Wedge, identifier("a"), identifier("b")),
+            binary(TokenKind::Wedge, identifier("c"), identifier("d")),
+        );
+        let typed = infer(&four, &declared, &symbols);
+        assert!(typed.meta.grades.is_empty());
+        assert!(matches!(typed.meta.issues[..], [Issue::AlwaysZero { .. }]));
+        let outer = binary(TokenKind::Plus, identifier("a"), four);
+        let analysis = inference::Analysis::from(&infer(&outer, &declared, &symbols));
+        assert_eq!(analysis.issues.len(), 1);
+        assert_eq!(analysis.spans.last().unwrap().grades, Grades::VECTOR);
+
+        let area = call("area", vec![*identifier("a")]);
+        let typed = infer(&area, &declared, &symbols);
+        assert!(matches!(
+            typed.meta.issues[..],
+            [Issue::InvalidArgument { .. }]
+        ));
+        let equation = binary(TokenKind::Equals, identifier("a"), identifier("B"));
+        let typed = infer(&equation, &declared, &symbols);
+        assert!(matches!(
+            typed.meta.issues[..],
+            [Issue::DisjointSides { .. }]
+        ));
+
+        let json = serde_json::to_value(&analysis).unwrap();
+        assert_eq!(json["spans"][0]["grades"], serde_json::json!([1]));
+        assert_eq!(
+            serde_json::from_value::<Grades>(serde_json::json!([0, 2])).unwrap(),
+            Grades::SCALAR.union(Grades::BIVECTOR)
+        );
+        assert!(serde_json::from_value::<Grades>(serde_json::json!([4])).is_err());
+    }
+
+    #[test]
+    fn test_generated_variables() {
+        use constraint::{Condition, ConstraintError};
+        use generate::{generate, Declaration, GenerateError};
+        let names = ["a", "b", "n", "p"].map(String::from);
+        let mut declarations = HashMap::new();
+        declarations.insert(
+            "n".to_string(),
+            Declaration {
+                constraints: vec![
+                    Condition::Unit,
+                    Condition::Orthogonal {
+                        to: "a".to_string(),
+                    },
+                    Condition::Orthogonal {
+                        to: "b".to_string(),
+                    },
+                ],
+                ..Default::default()
+            },
+        );
+        declarations.insert(
+            "p".to_string(),
+            Declaration {
+                constraints: vec![Condition::InBlade {
+                    spanned_by: vec!["e_{12}".to_string()],
+                }],
+                ..Default::default()
+            },
+        );
+        declarations.insert(
+            "B".to_string(),
+            Declaration {
+                grade: 2,
+                ..Default::default()
+            },
+        );
+
+        let values = generate(&names, &declarations, 42).unwrap();
+        assert_eq!(values, generate(&names, &declarations, 42).unwrap());
+        assert_ne!(values, generate(&names, &declarations, 43).unwrap());
+        let (a, b, n, p) = (values["a"], values["b"], values["n"], values["p"]);
+        assert!(a.is_vector() && a.norm() >= 1.0);
+        assert!(a.dot(b)[0].abs() < 0.95 * a.norm() * b.norm());
+        assert!((n.norm() - 1.0).abs() < 1e-12);
+        assert!(n.dot(a)[0].abs() < 1e-12 && n.dot(b)[0].abs() < 1e-12);
+        assert_eq!(p[3], 0.0);
+        assert!(values["B"].is_bivector());
+
+        // Adding an identifier leaves the unconstrained ones alone.
+        let fewer = generate(&names[..1], &HashMap::new(), 42).unwrap();
+        assert_eq!(fewer["a"], a);
+
+        // The default seed ignores where the nodes sit in the source.
+        let spaced = ASTNode {
+            start: 0,
+            end: 6,
+            r#type: ASTNodeType::BinaryOpNode {
+                op: TokenKind::Plus,
+                left: identifier("a"),
+                right: Box::new(ASTNode {
+                    start: 6,
+                    end: 6,
+                    r#type: ASTNodeType::Identifier {
+                        name: "b".to_string(),
+                    },
+                }),
+            },
+        };
+        let compact = binary(TokenKind::Plus, identifier("a"), identifier("b"));
+        assert_eq!(
+            generate::seed(&spaced).unwrap(),
+            generate::seed(&compact).unwrap()
+        );
+        let other = binary(TokenKind::Plus, identifier("a"), identifier("c"));
+        assert_ne!(
+            generate::seed(&compact).unwrap(),
+            generate::seed(&other).unwrap()
+        );
+
+        let mut impossible = HashMap::new();
+        impossible.insert(
+            "d".to_string(),
+            Declaration {
+                constraints: ["e_1", "e_2", "e_3"]
+                    .map(|to| Condition::Orthogonal { to: to.to_string() })
+                    .to_vec(),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            generate(&[], &impossible, 1),
+            Err(GenerateError::Unsatisfiable("d".to_string()))
+        );
+        let mut cycle = HashMap::new();
+        for (name, other) in [("u", "v"), ("v", "u")] {
+            cycle.insert(
+                name.to_string(),
+                Declaration {
+                    constraints: vec![Condition::Orthogonal {
+                        to: other.to_string(),
+                    }],
+                    ..Default::default()
+                },
+            );
+        }
+        assert!(matches!(
+            generate(&[], &cycle, 1),
+            Err(GenerateError::Cycle(_))
+        ));
+
+        // The solver's conditions that sampling alone cannot meet.
+        let mut related = HashMap::new();
+        related.insert(
+            "c".to_string(),
+            Declaration {
+                constraints: vec![
+                    Condition::Angle {
+                        to: "a".to_string(),
+                        angle: 1.0,
+                    },
+                    Condition::Magnitude { magnitude: 2.0 },
+                ],
+                ..Default::default()
+            },
+        );
+        related.insert(
+            "d".to_string(),
+            Declaration {
+                constraints: vec![Condition::Parallel {
+                    to: "e_3".to_string(),
+                }],
+                ..Default::default()
+            },
+        );
+        let values = generate(&names[..1], &related, 42).unwrap();
+        let (a, c, d) = (values["a"], values["c"], values["d"]);
+        assert!((c.angle(a).unwrap() - 1.0).abs() < 1e-9);
+        assert!((c.norm() - 2.0).abs() < 1e-9);
+        assert!(d[1] == 0.0 && d[2] == 0.0 && d[3] != 0.0);
+
+        // Only blades are literals, an unknown `I` is not the pseudoscalar.
+        for reference in ["x", "I", "i", "e_1 + e_2", "e_{11}"] {
+            let mut unknown = HashMap::new();
+            unknown.insert(
+                "n".to_string(),
+                Declaration {
+                    constraints: vec![Condition::Orthogonal {
+                        to: reference.to_string(),
+                    }],
+                    ..Default::default()
+                },
+            );
+            assert_eq!(
+                generate(&[], &unknown, 1),
+                Err(GenerateError::Constraint(
+                    ConstraintError::UnknownReference {
+                        name: "n".to_string(),
+                        reference: reference.to_string(),
+                    }
+                ))
+            );
+        }
+        assert_eq!(R300::parse_blade("\\sigma_2"), Some(r300::e2));
+        assert_eq!(R300::parse_blade("e_{31}"), Some(-1.0 * r300::e13));
+    }
+
+    #[test]
+    fn test_constraint_solver() {
+        use constraint::{solve, Condition, Constraint};
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), R300::vector(2.0, 0.0, 0.0));
+        vars.insert("b".to_string(), R300::vector(1.0, 1.0, 0.5));
+        vars.insert("c".to_string(), R300::vector(0.0, 1.0, 0.0));
+        vars.insert("n".to_string(), R300::vector(0.3, 0.2, 2.0));
+        vars.insert("B".to_string(), R300::bivector(1.0, 1.0, 0.0));
+        let constraints: Vec<Constraint> = serde_json::from_str(
+            r#"[
+                {"kind": "unit", "name": "n"},
+                {"kind": "orthogonal", "name": "n", "to": "a"},
+                {"kind": "in_blade", "name": "b", "spanned_by": ["a", "c"]},
+                {"kind": "magnitude", "name": "b", "magnitude": 2},
+                {"kind": "angle", "name": "c", "to": "a", "angle": 0.5},
+                {"kind": "parallel", "name": "B", "to": "e_{12}"}
+            ]"#,
+        )
+        .unwrap();
+
+        let solution = solve(&vars, &constraints).unwrap();
+        assert!(solution.converged);
+        let values = &solution.values;
+        let (a, b, c, n) = (values["a"], values["b"], values["c"], values["n"]);
+        assert_eq!(a, vars["a"]);
+        assert!((n.norm() - 1.0).abs() < 1e-9 && n.dot(a)[0].abs() < 1e-9);
+        assert!((b.norm() - 2.0).abs() < 1e-9 && b[3].abs() < 1e-9);
+        assert!((c.angle(a).unwrap() - 0.5).abs() < 1e-9);
+        assert!((c.norm() - 1.0).abs() < 1e-9);
+        assert!((values["B"] - r300::e12).norm() < 1e-9);
+        assert_eq!(solve(values, &constraints).unwrap().values, *values);
+
+        // b cannot be both parallel and orthogonal to a non-zero a
+        let contradiction = [
+            Condition::Parallel {
+                to: "a".to_string(),
+            },
+            Condition::Magnitude { magnitude: 1.0 },
+            Condition::Orthogonal {
+                to: "a".to_string(),
+            },
+        ]
+        .map(|condition| condition.on("b"));
+        let solution = solve(&vars, &contradiction).unwrap();
+        assert!(!solution.converged);
+        assert!(!solution.violated.is_empty());
+
+        let declared: generate::Declaration = serde_json::from_str(
+            r#"{"constraints": [{"kind": "unit"}, {"kind": "in_blade", "spanned_by": ["e_{12}"]}]}"#,
+        )
+        .unwrap();
+        let solution = solve(&vars, &declared.constraints("n")).unwrap();
+        assert!((solution.values["n"] - R300::vector(0.3, 0.2, 0.0).normalized()).norm() < 1e-9);
+    }
+
+    #[test]
+    fn test_inverse_manipulation() {
+        use inverse::{solve_for, InverseError, Method};
+        let symbols = ReservedSymbols::default();
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), R300::vector(1.0, 0.0, 0.0));
+        vars.insert("b".to_string(), R300::vector(1.0, 1.0, 1.0));
+
+        // a \wedge b at 0..8, dragged to 2 e12
+        let mut wedge = binary(TokenKind::Wedge, identifier("a"), identifier("b"));
+        wedge.end = 8;
+        let target = 2.0 * r300::e12;
+        let solution = solve_for(&wedge, &vars, &symbols, (0, 8), target, "b").unwrap();
+        assert_eq!(solution.method, Method::ClosedForm);
+        assert!(solution.residual < 1e-9);
+        // The component along a does not change the wedge, so it stays where it was.
+        assert!((solution.value - R300::vector(1.0, 2.0, 0.0)).norm() < 1e-6);
+
+        // Out of reach: a \wedge b never has an e23 part.
+        let solution = solve_for(&wedge, &vars, &symbols, (0, 8), r300::e23, "b").unwrap();
+        assert!((solution.residual - 1.0).abs() < 1e-6);
+
+        // |b| at 0..2 is not linear in b
+        let mut norm = unary(TokenKind::Pipe, identifier("b"));
+        norm.end = 2;
+        let solution = solve_for(&norm, &vars, &symbols, (0, 2), R300::new(6.0, 0), "b").unwrap();
+        assert_eq!(solution.method, Method::GaussNewton);
+        assert!(solution.residual < 1e-9);
+        let scaled = 2.0 * 3.0_f64.sqrt() * R300::vector(1.0, 1.0, 1.0);
+        assert!((solution.value - scaled).norm() < 1e-3);
+
+        assert_eq!(
+            solve_for(&wedge, &vars, &symbols, (0, 8), target, "c"),
+            Err(InverseError::UnknownIdentifier("c".to_string()))
+        );
+        assert_eq!(
+            solve_for(&wedge, &vars, &symbols, (3, 4), target, "b"),
+            Err(InverseError::NodeNotFound { start: 3, end: 4 })
+        );
+    }
+
+    #[test]
+    fn test_jacobian() {
+        let symbols = ReservedSymbols::default();
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), R300::vector(1.0, 0.0, 0.0));
+        vars.insert("b".to_string(), R300::vector(1.0, 2.0, 2.0));
+        let inputs = ["a".to_string(), "b".to_string()];
+        let derivative = |node: &Evaluated, input: &str, blade: &str| -> R300 {
+            let jacobian = node.meta.jacobian.as_ref().unwrap();
+            let partial = jacobian
+                .iter()
+                .find(|partial| partial.input == input && partial.blade == blade)
+                .unwrap();
+            partial.derivative.into()
+        };
+
+        let wedge = binary(TokenKind::Wedge, identifier("a"), identifier("b"));
+        let mut result = evaluate(&wedge, &vars, &symbols);
+        sensitivity::differentiate(&mut result, &vars, &inputs);
+        assert_eq!(result.meta.jacobian.as_ref().unwrap().len(), 6);
+        assert_eq!(derivative(&result, "b", "e1"), R300::zero());
+        assert_eq!(derivative(&result, "b", "e2"), r300::e12);
+        assert_eq!(
+            derivative(&result, "a", "e3"),
+            -1.0 * r300::e13 - 2.0 * r300::e23
+        );
+        // The leaves carry the seeds.
+        assert_eq!(derivative(&result.children[1], "b", "e3"), r300::e3);
+        assert_eq!(derivative(&result.children[1], "a", "e3"), R300::zero());
+
+        // d|b|/db = b / |b|
+        let norm = unary(TokenKind::Pipe, identifier("b"));
+        let mut result = evaluate(&norm, &vars, &symbols);
+        sensitivity::differentiate(&mut result, &vars, &inputs);
+        assert!((derivative(&result, "b", "e2") - R300::new(2.0 / 3.0, 0)).norm() < 1e-12);
+
+        // Builtins are chained with the algebra: d exp(a b) against finite differences, also
+        // along a2 which gives a b an e23 part it does not have at this point.
+        let exp = call(
+            "exp",
+            vec![*binary(
+                TokenKind::Multiply,
+                identifier("a"),
+                identifier("b"),
+            )],
+        );
+        let mut result = evaluate(&exp, &vars, &symbols);
+        sensitivity::differentiate(&mut result, &vars, &inputs);
+        for (input, blade, shifted) in [
+            ("b", "e3", R300::vector(1.0, 2.0, 2.0 + 1e-6)),
+            ("a", "e2", R300::vector(1.0, 1e-6, 0.0)),
+        ] {
+            let mut moved = vars.clone();
+            moved.insert(input.to_string(), shifted);
+            let difference = 1e6 * (evaluate(&exp, &moved, &symbols).value() - result.value());
+            assert!((derivative(&result, input, blade) - difference).norm() < 1e-4);
+        }
+        // The e23 component.
+        assert!(derivative(&result, "a", "e2")[6].abs() > 0.1);
+
+        // d exp(t B) / dt = B exp(t B) exactly, also near t = 0 where (t B)² is summed as a series.
+        let rotor = call(
+            "exp",
+            vec![*binary(
+                TokenKind::Multiply,
+                identifier("t"),
+                identifier("B"),
+            )],
+        );
+        let scalar = ["t".to_string()];
+        for t in [1e-4, 0.7] {
+            let mut vars = HashMap::new();
+            vars.insert("t".to_string(), R300::new(t, 0));
+            vars.insert("B".to_string(), r300::e12);
+            let mut result = evaluate(&rotor, &vars, &symbols);
+            sensitivity::differentiate(&mut result, &vars, &scalar);
+            let expected = r300::e12 * result.value();
+            assert!((derivative(&result, "t", "1") - expected).norm() < 1e-12);
+        }
+
+        // d log(R) and d sqrt(R) against finite differences, also out of the plane of R.
+        let mut vars = HashMap::new();
+        vars.insert("R".to_string(), R300::new(1.0, 0) + r300::e12);
+        let around = ["R".to_string()];
+        for name in ["log", "sqrt"] {
+            let function = call(name, vec![*identifier("R")]);
+            let mut result = evaluate(&function, &vars, &symbols);
+            sensitivity::differentiate(&mut result, &vars, &around);
+            for (blade, direction) in [
+                ("1", R300::new(1.0, 0)),
+                ("e12", r300::e12),
+                ("e13", r300::e13),
+            ] {
+                let mut moved = vars.clone();
+                moved.insert("R".to_string(), vars["R"] + 1e-6 * direction);
+                let difference =
+                    1e6 * (evaluate(&function, &moved, &symbols).value() - result.value());
+                assert!((derivative(&result, "R", blade) - difference).norm() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parameter_sweep() {
+        use parameter::{sweep, Parameter, ParameterError};
+        let symbols = ReservedSymbols::default();
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), R300::vector(1.0, 0.0, 0.0));
+
+        let range = Parameter {
+            min: 0.0,
+            max: 1.0,
+            step: Some(0.3),
+        };
+        let values = range.values().collect::<Vec<_>>();
+        assert_eq!(values.len(), 5);
+        assert!((values[3] - 0.9).abs() < 1e-12 && values[4] == 1.0);
+        let exact = Parameter {
+            step: Some(0.25),
+            ..range
+        };
+        assert_eq!(exact.count(), 5);
+
+        // t a at 0..3
+        let mut scaled = binary(TokenKind::Multiply, identifier("t"), identifier("a"));
+        scaled.end = 3;
+        let result = sweep(&scaled, &vars, &symbols, "t", &range).unwrap();
+        assert_eq!(result.samples.len(), 5);
+        let trajectory = result.trajectory(0, 3);
+        assert_eq!(trajectory[1], Some(R300::vector(0.3, 0.0, 0.0)));
+        assert_eq!(trajectory[4], Some(R300::vector(1.0, 0.0, 0.0)));
+
+        // t^{-1} has no value at t = 0 and one everywhere else.
+        let inverse = binary(
+            TokenKind::Caret,
+            identifier("t"),
+            unary(TokenKind::Minus, int(1)),
+        );
+        let result = sweep(&inverse, &vars, &symbols, "t", &range).unwrap();
+        let roots = result
+            .samples
+            .iter()
+            .map(|sample| sample.nodes.last().unwrap().value)
+            .collect::<Vec<_>>();
+        assert_eq!(roots[0], None);
+        assert_eq!(roots[4], Some(R300::new(1.0, 0)));
+
+        let backwards = Parameter {
+            min: 1.0,
+            max: 0.0,
+            step: None,
+        };
+        assert!(matches!(
+            sweep(&scaled, &vars, &symbols, "t", &backwards),
+            Err(ParameterError::InvalidRange { .. })
+        ));
+
+        // A declared parameter gets a value on its slider.
+        let declaration = generate::Declaration {
+            parameter: Some(range),
+            ..Default::default()
+        };
+        let declarations = HashMap::from([("t".to_string(), declaration)]);
+        let values = generate::generate(&[], &declarations, 7).unwrap();
+        assert!(range.values().any(|t| R300::new(t, 0) == values["t"]));
+
+        // e^{-\frac{\theta}{2}B}, the rotor by θ in B, with θ declared as a parameter.
+        let rotor = binary(
+            TokenKind::Caret,
+            identifier("e"),
+            binary(
+                TokenKind::Multiply,
+                unary(
+                    TokenKind::Minus,
+                    binary(TokenKind::Frac, identifier("\\theta"), int(2)),
+                ),
+                identifier("B"),
+            ),
+        );
+        let mut identifiers = Vec::new();
+        find_ast_identifiers(&rotor, &symbols, &mut identifiers);
+        assert_eq!(identifiers, ["\\theta", "B"]);
+
+        // Inference reads the declaration as a scalar, so the power is a rotor.
+        let declared = HashMap::from([
+            ("\\theta".to_string(), declarations["t"].grades()),
+            ("B".to_string(), inference::Grades::BIVECTOR),
+        ]);
+        let typed = inference::infer(&rotor, &declared, &symbols);
+        assert_eq!(
+            typed.meta.grades,
+            inference::Grades::SCALAR.union(inference::Grades::BIVECTOR)
+        );
+
+        vars.insert("B".to_string(), r300::e12);
+        let turn = Parameter {
+            min: 0.0,
+            max: std::f64::consts::PI,
+            step: Some(std::f64::consts::FRAC_PI_2),
+        };
+        let result = sweep(&rotor, &vars, &symbols, "\\theta", &turn).unwrap();
+        assert_eq!(result.samples.len(), 3);
+        for sample in &result.samples {
+            let half = sample.parameter / 2.0;
+            let expected = R300::new(half.cos(), 0) - half.sin() * r300::e12;
+            let value = sample.nodes.last().unwrap().value.unwrap();
+            assert!((value - expected).norm() < 1e-12);
+        }
+        vars.insert("\\theta".to_string(), R300::new(1.0, 0));
+        assert_eq!(
+            evaluate(&rotor, &vars, &symbols).kind(),
+            Kind::FunctionCall("exp")
+        );
+    }
+
+    #[test]
+    fn test_keyframe_interpolation() {
+        use keyframe::{blend_blades, lerp, slerp, Interpolation, Keyframe, Timeline, Track};
+        let close = |a: R300, b: R300| (a - b).norm() < 1e-9;
+        let (u, v) = (R300::vector(1.0, 0.0, 0.0), R300::vector(3.0, 2.0, 0.0));
+        assert_eq!(lerp(u, v, 0.5), R300::vector(2.0, 1.0, 0.0));
+
+        // Halfway between no rotation and a quarter turn in e12 is an eighth of a turn.
+        let quarter = (-std::f64::consts::FRAC_PI_4 * r300::e12).exp().unwrap();
+        let half = slerp(R300::new(1.0, 0), quarter, 0.5).unwrap();
+        let rotated = half * u * half.Reverse();
+        let diagonal = std::f64::consts::FRAC_1_SQRT_2 * R300::vector(1.0, 1.0, 0.0);
+        assert!(close(rotated, diagonal));
+        // -quarter is the same rotation, the short way is still taken.
+        let half = slerp(R300::new(1.0, 0), -1.0 * quarter, 0.5).unwrap();
+        assert!(close(half * u * half.Reverse(), diagonal));
+        assert_eq!(slerp(u, quarter, 0.5), None);
+
+        // The e12 plane turning into the e23 plane keeps unit area halfway and grows to 2.
+        let blade = blend_blades(r300::e12, 3.0 * r300::e23, 0.5).unwrap();
+        assert!(blade.is_bivector());
+        assert!((blade.norm() - 2.0).abs() < 1e-9);
+        assert!(close(
+            blade,
+            std::f64::consts::SQRT_2 * (r300::e12 + r300::e23)
+        ));
+        assert!(close(
+            blend_blades(R300::zero(), r300::e13, 0.25).unwrap(),
+            0.25 * r300::e13
+        ));
+        assert_eq!(
+            Interpolation::for_values(r300::e12, r300::e23),
+            Interpolation::Blade
+        );
+        assert_eq!(Interpolation::for_values(u, v), Interpolation::Linear);
+        // Scalars, e.g. parameters, move linearly through zero instead of turning.
+        assert_eq!(
+            Interpolation::for_values(R300::new(2.0, 0), R300::new(-3.0, 0)),
+            Interpolation::Linear
+        );
+        assert_eq!(
+            Interpolation::for_values(R300::new(1.0, 0), quarter),
+            Interpolation::Rotor
+        );
+
+        let keyframes = vec![
+            Keyframe {
+                time: 0.0,
+                value: R300::new(1.0, 0),
+            },
+            Keyframe {
+                time: 2.0,
+                value: quarter,
+            },
+        ];
+        let mut timeline = Timeline::default();
+        timeline.tracks.insert(
+            "R".to_string(),
+            Track {
+                keyframes,
+                interpolation: None,
+            },
+        );
+        assert_eq!(timeline.span(), Some((0.0, 2.0)));
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), u);
+        // R a \tilde{R}
+        let rotation = binary(
+            TokenKind::Multiply,
+            binary(TokenKind::Multiply, identifier("R"), identifier("a")),
+            unary(TokenKind::Tilde, identifier("R")),
+        );
+        let symbols = ReservedSymbols::default();
+        let result = timeline.evaluate(&rotation, &vars, &symbols, 1.0).unwrap();
+        assert!(close(result.value(), diagonal));
+        let result = timeline.evaluate(&rotation, &vars, &symbols, 5.0).unwrap();
+        assert!(close(result.value(), R300::vector(0.0, 1.0, 0.0)));
+
+        timeline.tracks.get_mut("R").unwrap().interpolation = Some(Interpolation::Blade);
+        assert!(timeline.values_at(&vars, 1.0).is_err());
+        assert!(timeline.values_at(&vars, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_construction_animations() {
+        use primitive::{Primitive, Role, Shape};
+        let symbols = ReservedSymbols::default();
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), R300::vector(1.0, 0.0, 1.0));
+        vars.insert("b".to_string(), R300::vector(0.0, 2.0, 0.0));
+        vars.insert("B".to_string(), 2.0 * r300::e12);
+        let last = |step: &construction::Step| step.frames.last().unwrap().clone();
+
+        // (a \wedge b) + 1 at 0..5, the wedge at 0..3 and the sum without a picture.
+        let mut wedge = binary(TokenKind::Wedge, identifier("a"), identifier("b"));
+        wedge.end = 3;
+        let mut sum = binary(TokenKind::Plus, wedge, int(1));
+        sum.end = 5;
+        let constructions = construction::construct(&evaluate(&sum, &vars, &symbols));
+        assert_eq!(constructions.len(), 1);
+        assert_eq!((constructions[0].start, constructions[0].end), (0, 3));
+        let sweep = &constructions[0].steps[0];
+        assert!(sweep.frames.iter().all(|frame| frame.len() == 3));
+        assert_eq!(
+            last(sweep)[2],
+            Shape::parallelogram(Role::Result, vars["a"], vars["b"])
+        );
+        let first = sweep.frames[0][2].primitive;
+        assert!(matches!(
+            first,
+            Primitive::Parallelogram {
+                v: [0.0, 0.0, 0.0],
+                ..
+            }
+        ));
+
+        // a \cdot B projects a to e1 and turns it to 2 e2.
+        let dot = binary(TokenKind::Dot, identifier("a"), identifier("B"));
+        let constructions = construction::construct(&evaluate(&dot, &vars, &symbols));
+        let steps = &constructions[0].steps;
+        assert_eq!(steps.len(), 2);
+        assert_eq!(
+            last(&steps[0])[1],
+            Shape::arrow(Role::Left, R300::zero(), r300::e1)
+        );
+        let Primitive::Arrow { vector, .. } = last(&steps[1])[1].primitive else {
+            panic!("expected an arrow");
+        };
+        let expected = r300::e1.dot(vars["B"]);
+        assert!((0..3).all(|i| (vector[i] - expected[i + 1]).abs() < 1e-9));
+
+        // Too small a plane to invert gives no construction rather than one without steps.
+        vars.insert("B".to_string(), R300::bivector(1e-4, 0.0, 0.0));
+        assert!(construction::construct(&evaluate(&dot, &vars, &symbols)).is_empty());
+    }
+
+    #[test]
+    fn test_scene_description() {
+        use primitive::Primitive;
+        use scene::{describe, BivectorStyle, Scene, SceneOptions};
+        let options = SceneOptions::default();
+        let area = |u: [f64; 3], v: [f64; 3]| {
+            R300::vector(u[0], u[1], u[2]).wedge(R300::vector(v[0], v[1], v[2]))
+        };
+
+        let a = describe("a", R300::vector(1.0, 2.0, 3.0), &options);
+        assert_eq!(a.grade, Some(1));
+        assert_eq!(a.color, scene::color(Some(1)));
+        assert_eq!(
+            a.shapes,
+            [Primitive::Arrow {
+                origin: [0.0; 3],
+                vector: [1.0, 2.0, 3.0]
+            }]
+        );
+        assert_eq!(a.label.position, [1.0, 2.0, 3.0]);
+
+        // The parallelogram spans exactly the bivector, orientation included.
+        let value = R300::bivector(1.0, -2.0, 0.5);
+        let Primitive::Parallelogram { u, v, .. } = describe("B", value, &options).shapes[0] else {
+            panic!("expected a parallelogram");
+        };
+        assert!((area(u, v) - value).norm() < 1e-12);
+
+        let discs = SceneOptions {
+            bivectors: BivectorStyle::Disc,
+            ..Default::default()
+        };
+        let Primitive::Disc { normal, radius, .. } =
+            describe("B", 3.0 * r300::e12, &discs).shapes[0]
+        else {
+            panic!("expected a disc");
+        };
+        assert!(normal[0] == 0.0 && normal[1] == 0.0 && normal[2] > 0.0);
+        assert!((std::f64::consts::PI * radius * radius - 3.0).abs() < 1e-12);
 
-        insta::assert_debug_snapshot!(result);
+        let Primitive::Parallelepiped { w, .. } =
+            describe("T", -8.0 * r300::e123, &options).shapes[0]
+        else {
+            panic!("expected a parallelepiped");
+        };
+        assert_eq!(w, [0.0, 0.0, -2.0]);
+
+        // A rotor is drawn as its decomposition, the arc being the spinor's angle in its plane.
+        let rotor = (-0.3 * r300::e23).exp().unwrap();
+        let item = describe("R", rotor, &options);
+        assert_eq!(
+            item.shapes,
+            decompose::decompose("R", rotor, &options).all_shapes()
+        );
+        let Some(&Primitive::Arc { u, v, angle, .. }) = item.shapes.last() else {
+            panic!("expected an arc");
+        };
+        let (u, v) = (
+            R300::vector(u[0], u[1], u[2]),
+            R300::vector(v[0], v[1], v[2]),
+        );
+        assert!((angle - 0.3).abs() < 1e-12);
+        assert!((u.wedge(v) + r300::e23).norm() < 1e-12);
+
+        let values = BTreeMap::from([
+            ("b".to_string(), r300::e2),
+            ("a".to_string(), R300::new(2.0, 0)),
+        ]);
+        let scene = Scene::new(&values, &options);
+        assert_eq!(scene.items[0].name, "a");
+        assert!(scene.items[0].shapes.is_empty());
+    }
+
+    #[test]
+    fn test_trivector_primitives() {
+        use primitive::{factor_trivector, signed_volume, Handedness, Primitive};
+        use scene::{describe, Scene, SceneOptions, TrivectorStyle};
+        let symbols = ReservedSymbols::default();
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), R300::vector(1.0, 0.0, 0.0));
+        vars.insert("b".to_string(), R300::vector(1.0, 2.0, 0.0));
+        vars.insert("c".to_string(), R300::vector(0.0, 1.0, -3.0));
+
+        // a \wedge b \wedge c at 0..5 is left handed with volume 6.
+        let mut ab = binary(TokenKind::Wedge, identifier("a"), identifier("b"));
+        ab.end = 3;
+        let mut abc = binary(TokenKind::Wedge, ab, identifier("c"));
+        abc.end = 5;
+        let evaluated = evaluate(&abc, &vars, &symbols);
+        assert_eq!(signed_volume(evaluated.value()), Some(-6.0));
+        assert_eq!(signed_volume(vars["a"]), None);
+
+        let (u, v, w) = factor_trivector(evaluated.value()).unwrap();
+        assert!((u.wedge(v).wedge(w) - evaluated.value()).norm() < 1e-12);
+
+        let scene = Scene::from_evaluated(&evaluated, &SceneOptions::default());
+        let volume = scene.items.last().unwrap();
+        assert_eq!((volume.start, volume.end), (Some(0), Some(5)));
+        assert_eq!(volume.volume, Some(-6.0));
+        // Drawn on the factors of the wedge rather than on a cube.
+        assert_eq!(
+            volume.shapes,
+            [Primitive::Parallelepiped {
+                origin: [0.0; 3],
+                u: [1.0, 0.0, 0.0],
+                v: [1.0, 2.0, 0.0],
+                w: [0.0, 1.0, -3.0],
+            }]
+        );
+
+        let spheres = SceneOptions {
+            trivectors: TrivectorStyle::Sphere,
+            ..Default::default()
+        };
+        let Primitive::Sphere {
+            radius, handedness, ..
+        } = describe("T", evaluated.value(), &spheres).shapes[0]
+        else {
+            panic!("expected a sphere");
+        };
+        assert_eq!(handedness, Handedness::Left);
+        assert!((4.0 / 3.0 * std::f64::consts::PI * radius.powi(3) - 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_mixed_grade_decomposition() {
+        use decompose::decompose;
+        use primitive::Primitive;
+        let options = scene::SceneOptions::default();
+
+        // a b = |a||b| (cos 45° + sin 45° e12) for a = e1, b = e1 + e2.
+        let (a, b) = (R300::vector(1.0, 0.0, 0.0), R300::vector(1.0, 1.0, 0.0));
+        let product = a * b;
+        let decomposition = decompose("ab", product, &options);
+        let grades = decomposition.parts.iter().map(|part| part.grade);
+        assert_eq!(grades.collect::<Vec<_>>(), [0, 2]);
+        let spinor = decomposition.spinor.clone().unwrap();
+        assert!((spinor.angle - std::f64::consts::FRAC_PI_4).abs() < 1e-12);
+        assert!((spinor.magnitude - 2.0_f64.sqrt()).abs() < 1e-12);
+        assert_eq!(spinor.plane, r300::e12);
+        // The arc starts on a vector of the plane and ends on the one at 45° from it.
+        let Primitive::Arc { u, v, angle, .. } = decomposition.shapes[0] else {
+            panic!("expected an arc");
+        };
+        let (u, v) = (
+            R300::vector(u[0], u[1], u[2]),
+            R300::vector(v[0], v[1], v[2]),
+        );
+        assert!((u.wedge(v) - r300::e12).norm() < 1e-12);
+        assert!((u.dot(angle.cos() * u + angle.sin() * v)[0] - angle.cos()).abs() < 1e-12);
+        assert_eq!(decomposition.legend.len(), 3);
+        assert_eq!(decomposition.legend[0].text, "\\text{scalar, } 1");
+        // The scene draws the same arc for the same value.
+        let item = scene::describe("ab", product, &options);
+        assert_eq!(item.shapes.last(), decomposition.shapes.last());
+        assert!(item.shapes.len() > 1);
+
+        // Without spinor structure every grade part is still drawn.
+        let mixed = R300::new(1.0, 0) + a + 2.0 * r300::e123;
+        assert_eq!(decompose::spinor(mixed), None);
+        let item = scene::describe("M", mixed, &options);
+        assert_eq!(item.grade, None);
+        assert!(matches!(item.shapes[0], Primitive::Arrow { .. }));
+        assert!(matches!(item.shapes[1], Primitive::Parallelepiped { .. }));
+    }
+
+    #[test]
+    fn test_symbolic_expansion() {
+        let standard = convention::Convention::standard();
+        let vars = HashMap::new();
+        let symbols = ReservedSymbols::default();
+
+        let wedge = binary(TokenKind::Wedge, identifier("a"), identifier("b"));
+        let result = symbolic::calculate_symbolic(&wedge, &vars, &symbols, &standard).unwrap();
+        assert_eq!(
+            result.latex,
+            "(a_{1} b_{2} - a_{2} b_{1}) e_{12} + (a_{1} b_{3} - a_{3} b_{1}) e_{13} + (a_{2} b_{3} - a_{3} b_{2}) e_{23}"
+        );
+        assert_eq!(result.blades[0].blade, "e12");
+
+        let square = binary(TokenKind::Multiply, identifier("a"), identifier("a"));
+        let result = symbolic::calculate_symbolic(&square, &vars, &symbols, &standard).unwrap();
+        assert_eq!(result.latex, "a_{1}^{2} + a_{2}^{2} + a_{3}^{2}");
+
+        let anticommute = binary(
+            TokenKind::Equals,
+            binary(TokenKind::Wedge, identifier("a"), identifier("b")),
+            unary(
+                TokenKind::Minus,
+                binary(TokenKind::Wedge, identifier("b"), identifier("a")),
+            ),
+        );
+        let result =
+            symbolic::calculate_symbolic(&anticommute, &vars, &symbols, &standard).unwrap();
+        assert_eq!(result.latex, "0");
+        assert!(result.blades.is_empty());
+
+        let mut vars = HashMap::new();
+        vars.insert("B".to_string(), R300::bivector(1.0, 0.0, 0.0));
+        let with_basis = binary(TokenKind::Dot, identifier("e_1"), identifier("B"));
+        let result = symbolic::calculate_symbolic(&with_basis, &vars, &symbols, &standard).unwrap();
+        assert_eq!(result.latex, "B_{12} e_{2} + B_{13} e_{3}");
+    }
+
+    #[test]
+    fn test_blade_products() {
+        let symbols = ReservedSymbols::default();
+        let blade = |name: &str| symbols.get(name).unwrap();
+        assert_eq!(blade("e_1") * blade("e_2"), R300::new(1.0, 4));
+        assert_eq!(blade("e_2") * blade("e_1"), R300::new(-1.0, 4));
+        assert_eq!(blade("e_1") * blade("e_1"), R300::new(1.0, 0));
+        assert_eq!(blade("e_{12}") * blade("e_{12}"), R300::new(-1.0, 0));
+        assert_eq!(blade("e_{123}") * blade("e_{123}"), R300::new(-1.0, 0));
+        assert_eq!(blade("e_1").wedge(blade("e_{23}")), blade("e_{123}"));
+        assert_eq!(blade("e_1").wedge(blade("e_{12}")), R300::zero());
+        assert_eq!(blade("e_1").dot(blade("e_{12}")), blade("e_2"));
+        assert_eq!(blade("e_1").Dual(), blade("e_{23}"));
+        assert_eq!(
+            blade("e_{12}").Reverse(),
+            R300::new(-1.0, 0) * blade("e_{12}")
+        );
+        assert_eq!(
+            blade("e_{123}").Involute(),
+            R300::new(-1.0, 0) * blade("e_{123}")
+        );
+        assert_eq!(
+            blade("e_{12}").Conjugate(),
+            R300::new(-1.0, 0) * blade("e_{12}")
+        );
+    }
+
+    #[test]
+    fn test_exact_mode() {
+        let standard = convention::Convention::standard();
+        let symbols = ReservedSymbols::default();
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), R300::vector(1.0, 0.1, 0.0));
+
+        let third = binary(TokenKind::Frac, identifier("a"), int(3));
+        let result = exact::calculate_exact(&third, &vars, &symbols, &standard).unwrap();
+        assert_eq!(result.latex, "\\frac{1}{3} e_{1} + \\frac{1}{30} e_{2}");
+        assert_eq!(result.blades[1].numerator, "1");
+        assert_eq!(result.blades[1].denominator, "30");
+
+        // a^{-1} = a / |a|^2 = a / 1.01
+        let inverse = binary(
+            TokenKind::Caret,
+            identifier("a"),
+            unary(TokenKind::Minus, int(1)),
+        );
+        let result = exact::calculate_exact(&inverse, &vars, &symbols, &standard).unwrap();
+        assert_eq!(
+            result.latex,
+            "\\frac{100}{101} e_{1} + \\frac{10}{101} e_{2}"
+        );
+
+        let norm = unary(TokenKind::Pipe, identifier("a"));
+        assert!(matches!(
+            exact::calculate_exact(&norm, &vars, &symbols, &standard),
+            Err(EvalError::NotExact { .. })
+        ));
+    }
+
+    #[test]
+    fn test_closed_form_formatting() {
+        use format::{BasisNaming, FormatOptions, Notation};
+
+        let half_root = std::f64::consts::FRAC_1_SQRT_2;
+        let rotor = R300::new(half_root, 0) + R300::new(-half_root, 4);
+        let latex = FormatOptions::default();
+        assert_eq!(
+            rotor.to_closed_form(&latex),
+            "\\frac{\\sqrt{2}}{2} - \\frac{\\sqrt{2}}{2} e_{12}"
+        );
+
+        let unicode = FormatOptions {
+            notation: Notation::Unicode,
+            ..Default::default()
+        };
+        assert_eq!(rotor.to_closed_form(&unicode), "√2/2 - √2/2 e₁₂");
+
+        let value = R300::vector(
+            1.0 / 3.0,
+            3.0 * std::f64::consts::PI / 4.0,
+            2.0 * 3f64.sqrt(),
+        );
+        assert_eq!(
+            value.to_closed_form(&latex),
+            "\\frac{1}{3} e_{1} + \\frac{3\\pi}{4} e_{2} + 2\\sqrt{3} e_{3}"
+        );
+        let compact = FormatOptions {
+            notation: Notation::Unicode,
+            basis: BasisNaming::Compact,
+            precision: 2,
+            ..Default::default()
+        };
+        assert_eq!(value.to_closed_form(&compact), "1/3 e1 + 3π/4 e2 + 2√3 e3");
+        assert_eq!(R300::new(0.123456, 7).to_closed_form(&compact), "0.12 e123");
+        assert_eq!(R300::zero().to_closed_form(&latex), "0");
+        let tiny = R300::new(1e-7, 1) + R300::new(-1e-7, 2) + R300::new(0.5, 3);
+        assert_eq!(tiny.to_closed_form(&latex), "\\frac{1}{2} e_{3}");
+        assert_eq!(R300::new(1e-7, 1).to_closed_form(&latex), "0");
+    }
+
+    #[test]
+    fn test_latex_and_mathml() {
+        use format::{BasisNaming, FormatOptions, Notation};
+
+        let value =
+            R300::new(2.0, 0) + R300::vector(1.0, -0.5, 0.0) + R300::bivector(1.0, 1.0, 0.0);
+        assert_eq!(
+            value.to_latex(),
+            "2 + e_{1} - \\frac{1}{2} e_{2} + e_{12} + e_{13}"
+        );
+
+        let pauli = FormatOptions {
+            basis: BasisNaming::PauliDual,
+            group_grades: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            value.to_closed_form(&pauli),
+            "2 + \\left(\\sigma_{1} - \\frac{1}{2} \\sigma_{2}\\right) + \\left(i\\sigma_{3} - i\\sigma_{2}\\right)"
+        );
+        let unicode = FormatOptions {
+            notation: Notation::Unicode,
+            basis: BasisNaming::Pauli,
+            ..Default::default()
+        };
+        assert_eq!(R300::new(-1.0, 7).to_closed_form(&unicode), "-σ₁σ₂σ₃");
+
+        assert_eq!(
+            (R300::new(-0.5, 1) + R300::new(2.0, 4)).to_mathml(),
+            "<math><mrow><mo>-</mo><mfrac><mn>1</mn><mn>2</mn></mfrac><mo>&#x2062;</mo>\
+             <msub><mi>e</mi><mn>1</mn></msub><mo>+</mo><mn>2</mn><mo>&#x2062;</mo>\
+             <msub><mi>e</mi><mn>12</mn></msub></mrow></math>"
+        );
+        assert_eq!(R300::zero().to_mathml(), "<math><mn>0</mn></math>");
+    }
+
+    #[test]
+    fn test_parse_multivector_literals() {
+        use parse::ParseErrorKind;
+
+        let value = R300::parse("1 + 2e1 - 0.5e23").unwrap();
+        assert_eq!(
+            value,
+            R300::new(1.0, 0) + R300::new(2.0, 1) + R300::new(-0.5, 6)
+        );
+        assert_eq!("e31".parse::<R300>().unwrap(), R300::new(-1.0, 5));
+        assert_eq!(
+            R300::parse("-e_{21} + 3 * I").unwrap(),
+            R300::new(1.0, 4) + R300::new(3.0, 7)
+        );
+        assert_eq!(
+            R300::parse("\\sigma_1\\sigma_2 - σ₃").unwrap(),
+            R300::new(1.0, 4) + R300::new(-1.0, 3)
+        );
+        assert_eq!(R300::parse("2 e1 e2 e1").unwrap(), R300::new(-2.0, 2));
+        assert_eq!(R300::parse("1.5E2").unwrap(), R300::new(150.0, 0));
+
+        let error = |text: &str| R300::parse(text).unwrap_err();
+        assert_eq!(error("").kind, ParseErrorKind::Empty);
+        assert_eq!(error("1 + e4").kind, ParseErrorKind::InvalidBladeIndex('4'));
+        assert_eq!(error("1 + e4").position, 5);
+        assert_eq!(error("2e1 +").kind, ParseErrorKind::UnexpectedEnd);
+        assert_eq!(
+            error("2e1 x").kind,
+            ParseErrorKind::UnexpectedCharacter('x')
+        );
+        assert_eq!(error("2e1 x").position, 4);
+        assert_eq!(error("e_{12").kind, ParseErrorKind::UnexpectedEnd);
+        assert_eq!(error("3 e").kind, ParseErrorKind::MissingBladeIndex);
+        assert_eq!(error("e11").kind, ParseErrorKind::RepeatedBladeIndex('1'));
+        assert_eq!(error("e11").position, 2);
+        assert_eq!(error("e_{3 1 3}").position, 7);
+
+        // Literals and reserved symbols accept the same blade names.
+        let symbols = ReservedSymbols::default();
+        for name in ["e_{1 2}", "e_{31}", "e_2", "e_{123}"] {
+            assert_eq!(R300::parse(name).ok(), symbols.get(name), "{}", name);
+        }
+        assert_eq!(symbols.get("e_{11}"), None);
+    }
+
+    #[test]
+    fn test_basis_conventions() {
+        use convention::{Convention, ConventionError, ConventionalValue};
+        use format::FormatOptions;
+
+        let cyclic = Convention::cyclic();
+        let value = R300::new(1.0, 3) + R300::bivector(2.0, 3.0, 4.0);
+        assert_eq!(
+            cyclic.components(&value),
+            vec![0.0, 0.0, 0.0, 1.0, 4.0, -3.0, 2.0, 0.0]
+        );
+        assert_eq!(cyclic.value(&cyclic.components(&value)), Some(value));
+        // The dual of e2 is -e13, which the cyclic basis reads as +e31.
+        let dual = R300::new(1.0, 2).Dual();
+        assert_eq!(Convention::standard().components(&dual)[5], -1.0);
+        assert_eq!(cyclic.components(&dual)[5], 1.0);
+
+        let options = FormatOptions {
+            convention: cyclic.clone(),
+            ..Default::default()
+        };
+        assert_eq!(
+            value.to_closed_form(&options),
+            "e_{3} + 4 e_{23} - 3 e_{31} + 2 e_{12}"
+        );
+        let left = FormatOptions {
+            convention: Convention::standard().left_handed(),
+            ..Default::default()
+        };
+        assert_eq!(R300::new(2.0, 7).to_closed_form(&left), "-2 e_{321}");
+        let named = FormatOptions {
+            convention: Convention::from_names(&["1", "e1", "e2", "e3", "e23", "e31", "e12", "I"])
+                .unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(R300::new(2.0, 7).to_closed_form(&named), "2 I");
+
+        // Derivations, symbolic and exact results and documents follow the convention too.
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), R300::vector(0.0, 0.0, 1.0));
+        vars.insert("b".to_string(), R300::vector(1.0, 0.0, 0.0));
+        let wedge = binary(TokenKind::Wedge, identifier("a"), identifier("b"));
+        let symbols = ReservedSymbols::default();
+        let mut calculated = calculate_ast_expression(&wedge, &vars, &symbols).unwrap();
+        derivation::derive(&mut calculated, &options);
+        let derivation = calculated.meta.derivation.clone().unwrap();
+        assert!(derivation.rule.contains("(a_3 b_1 - a_1 b_3) e_{31}"));
+        assert!(derivation.steps[0].contains("(1 \\cdot 1 - 0 \\cdot 0) e_{31}"));
+        assert_eq!(derivation.steps[1], "= e_{31}");
+        let document = EvaluatedExpression::with_convention(&calculated, &cyclic);
+        assert_eq!(document.basis, Some(cyclic.names()));
+        assert_eq!(
+            document.root.components,
+            Some(vec![0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0])
+        );
+        assert_eq!(
+            EvaluatedExpression::with_convention(&calculated, &Convention::standard()).basis,
+            None
+        );
+
+        let symbolic = symbolic::calculate_symbolic(&wedge, &vars, &symbols, &cyclic).unwrap();
+        assert_eq!(symbolic.blades[1].blade, "e31");
+        assert!(symbolic.latex.contains("e_{31}"));
+        let exact = exact::calculate_exact(&wedge, &vars, &symbols, &cyclic).unwrap();
+        assert_eq!(exact.blades[0].blade, "e31");
+        assert_eq!(exact.latex, "e_{31}");
+
+        let saved = cyclic.serialize(&value);
+        let json = serde_json::to_string(&saved).unwrap();
+        let restored: ConventionalValue = serde_json::from_str(&json).unwrap();
+        assert_eq!(R300::try_from(restored), Ok(value));
+
+        assert_eq!(
+            Convention::from_names(&["1", "e1", "e2", "e3", "e12", "e31", "e13", "e123"]),
+            Err(ConventionError::DuplicateBlade("e13".to_string()))
+        );
+        assert_eq!(
+            Convention::from_names(&["1", "e1", "e2", "e3", "e12", "e13", "e1 + e2"]),
+            Err(ConventionError::InvalidBlade("e1 + e2".to_string()))
+        );
+        assert_eq!(
+            Convention::from_names(&["1", "e1", "e2", "e3", "e12", "e13", "e23"]),
+            Err(ConventionError::MissingBlade("e123".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_versioned_output() {
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), R300::vector(1.0, 0.0, 0.0));
+        let ast = binary(TokenKind::Wedge, identifier("a"), identifier("e_2"));
+        let calculated =
+            calculate_ast_expression(&ast, &vars, &ReservedSymbols::default()).unwrap();
+        let document = EvaluatedExpression::from(&calculated);
+
+        let json: serde_json::Value = serde_json::to_value(&document).unwrap();
+        assert_eq!(json["version"], 2);
+        assert_eq!(json["algebra"], "R300");
+        assert_eq!(json["root"]["kind"], "BinaryOp");
+        assert_eq!(json["root"]["op"], "Wedge");
+        assert_eq!(json["root"]["value"]["e12"], 1.0);
+        assert_eq!(json["root"]["children"][0]["kind"], "Identifier");
+        assert_eq!(schema::from_json(&json.to_string()).unwrap(), document);
+
+        // The unversioned format written before the schema existed.
+        let legacy = r#"{"start": 0, "end": 0, "value": {"mvec": [0, 0, 0, 0, 1, 0, 0, 0]},
+            "type": {"BinaryOpNode": {"op": "Wedge",
+                "left": {"start": 0, "end": 0, "value": {"mvec": [0, 1, 0, 0, 0, 0, 0, 0]},
+                    "type": {"Identifier": {"name": "a"}}},
+                "right": {"start": 0, "end": 0, "value": {"mvec": [0, 0, 1, 0, 0, 0, 0, 0]},
+                    "type": {"Constant": {"name": "e_2"}}}}}}"#;
+        let upgraded = schema::from_json(legacy).unwrap();
+        assert_eq!(upgraded.root.kind, document.root.kind);
+        assert_eq!(upgraded.root.grade, Some(2));
+        assert_eq!(
+            upgraded.root.children[1].kind,
+            document.root.children[1].kind
+        );
+
+        assert!(matches!(
+            schema::from_json(r#"{"version": 3}"#),
+            Err(schema::SchemaError::UnsupportedVersion(3))
+        ));
+
+        // Nodes that could not be evaluated say why.
+        let unbound = binary(TokenKind::Plus, identifier("a"), identifier("c"));
+        let document =
+            EvaluatedExpression::from(&evaluate(&unbound, &vars, &ReservedSymbols::default()));
+        assert_eq!(
+            document.root.children[1].errors.as_ref().unwrap()[0].message,
+            "Unbound identifier: c"
+        );
+        assert_eq!(document.root.errors, None);
+    }
+
+    /// The checked-in schema and TypeScript definitions match the types they are generated
+    /// from. `UPDATE_GENERATED=1 cargo test` rewrites them instead.
+    #[test]
+    fn test_generated_files_are_current() {
+        use std::path::{Component, Path, PathBuf};
+        use ts_rs::TS;
+        let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+        // `export_to` is relative to ts-rs's `bindings` directory, which need not exist, so
+        // the `..` steps are resolved by hand.
+        fn typescript<T: TS + 'static>() -> (PathBuf, String) {
+            let mut path = Path::new(env!("CARGO_MANIFEST_DIR")).join("bindings");
+            for component in T::output_path().unwrap().components() {
+                match component {
+                    Component::ParentDir => {
+                        path.pop();
+                    }
+                    component => path.push(component),
+                }
+            }
+            (path, T::export_to_string().unwrap())
+        }
+        let schema = schemars::schema_for!(EvaluatedExpression);
+        let files = [
+            (
+                manifest.join("schema/evaluated-expression.schema.json"),
+                serde_json::to_string_pretty(&schema).unwrap() + "\n",
+            ),
+            typescript::<EvaluatedExpression>(),
+            typescript::<schema::Blades>(),
+            typescript::<schema::Node>(),
+            typescript::<schema::NodeKind>(),
+            typescript::<schema::Operator>(),
+            typescript::<schema::NodeError>(),
+            typescript::<derivation::Derivation>(),
+            typescript::<PartialDerivative>(),
+            typescript::<primitive::Primitive>(),
+            typescript::<primitive::Handedness>(),
+            typescript::<scene::BivectorStyle>(),
+            typescript::<scene::TrivectorStyle>(),
+            typescript::<scene::SceneOptions>(),
+            typescript::<scene::Label>(),
+            typescript::<scene::Item>(),
+            typescript::<scene::Scene>(),
+            typescript::<parameter::Parameter>(),
+        ];
+        let update = std::env::var_os("UPDATE_GENERATED").is_some();
+        let mut stale = Vec::new();
+        for (path, contents) in files {
+            if update {
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                std::fs::write(&path, contents).unwrap();
+            } else if std::fs::read_to_string(&path).ok() != Some(contents) {
+                stale.push(path.display().to_string());
+            }
+        }
+        assert!(
+            stale.is_empty(),
+            "out of date, run UPDATE_GENERATED=1 cargo test: {:?}",
+            stale
+        );
+    }
+
+    #[test]
+    fn test_generic_products_match_reference() {
+        let values = (0..8)
+            .map(|i| R300::new(1.0, i))
+            .chain([
+                R300::from(multivector::Multivector::from_fn(|i| i as f64 - 3.5)),
+                R300::from(multivector::Multivector::from_fn(|i| (i * i) as f64 / 7.0)),
+            ])
+            .collect::<Vec<_>>();
+        for &a in &values {
+            for &b in &values {
+                assert_eq!(a * b, reference::geometric_product(a, b));
+                assert_eq!(a.wedge(b), reference::wedge(a, b));
+                assert_eq!(a.dot(b), reference::dot(a, b));
+            }
+            assert_eq!(a.Reverse(), reference::reverse(a));
+            assert_eq!(a.Dual(), reference::dual(a));
+            assert_eq!(a.Conjugate(), reference::conjugate(a));
+            assert_eq!(a.Involute(), reference::involute(a));
+        }
+    }
+
+    /// The products and involutions as generated for R300 before they went through the generic
+    /// `Multivector`, kept to check it against.
+    mod reference {
+        use crate::r300::R300;
+
+        pub fn reverse(a: R300) -> R300 {
+            let mut res = R300::zero();
+            res[0] = a[0];
+            res[1] = a[1];
+            res[2] = a[2];
+            res[3] = a[3];
+            res[4] = -a[4];
+            res[5] = -a[5];
+            res[6] = -a[6];
+            res[7] = -a[7];
+            res
+        }
+
+        pub fn dual(a: R300) -> R300 {
+            let mut res = R300::zero();
+            res[0] = -a[7];
+            res[1] = -a[6];
+            res[2] = a[5];
+            res[3] = -a[4];
+            res[4] = a[3];
+            res[5] = -a[2];
+            res[6] = a[1];
+            res[7] = a[0];
+            res
+        }
+
+        pub fn conjugate(a: R300) -> R300 {
+            let mut res = R300::zero();
+            res[0] = a[0];
+            res[1] = -a[1];
+            res[2] = -a[2];
+            res[3] = -a[3];
+            res[4] = -a[4];
+            res[5] = -a[5];
+            res[6] = -a[6];
+            res[7] = a[7];
+            res
+        }
+
+        pub fn involute(a: R300) -> R300 {
+            let mut res = R300::zero();
+            res[0] = a[0];
+            res[1] = -a[1];
+            res[2] = -a[2];
+            res[3] = -a[3];
+            res[4] = a[4];
+            res[5] = a[5];
+            res[6] = a[6];
+            res[7] = -a[7];
+            res
+        }
+
+        pub fn dot(a: R300, b: R300) -> R300 {
+            let mut res = R300::zero();
+            res[0] = b[0] * a[0] + b[1] * a[1] + b[2] * a[2] + b[3] * a[3]
+                - b[4] * a[4]
+                - b[5] * a[5]
+                - b[6] * a[6]
+                - b[7] * a[7];
+            res[1] =
+                b[1] * a[0] + b[0] * a[1] - b[4] * a[2] - b[5] * a[3] + b[2] * a[4] + b[3] * a[5]
+                    - b[7] * a[6]
+                    - b[6] * a[7];
+            res[2] = b[2] * a[0] + b[4] * a[1] + b[0] * a[2] - b[6] * a[3] - b[1] * a[4]
+                + b[7] * a[5]
+                + b[3] * a[6]
+                + b[5] * a[7];
+            res[3] = b[3] * a[0] + b[5] * a[1] + b[6] * a[2] + b[0] * a[3]
+                - b[7] * a[4]
+                - b[1] * a[5]
+                - b[2] * a[6]
+                - b[4] * a[7];
+            res[4] = b[4] * a[0] + b[7] * a[3] + b[0] * a[4] + b[3] * a[7];
+            res[5] = b[5] * a[0] - b[7] * a[2] + b[0] * a[5] - b[2] * a[7];
+            res[6] = b[6] * a[0] + b[7] * a[1] + b[0] * a[6] + b[1] * a[7];
+            res[7] = b[7] * a[0] + b[0] * a[7];
+            res
+        }
+
+        pub fn wedge(a: R300, b: R300) -> R300 {
+            let mut res = R300::zero();
+            res[0] = b[0] * a[0];
+            res[1] = b[1] * a[0] + b[0] * a[1];
+            res[2] = b[2] * a[0] + b[0] * a[2];
+            res[3] = b[3] * a[0] + b[0] * a[3];
+            res[4] = b[4] * a[0] + b[2] * a[1] - b[1] * a[2] + b[0] * a[4];
+            res[5] = b[5] * a[0] + b[3] * a[1] - b[1] * a[3] + b[0] * a[5];
+            res[6] = b[6] * a[0] + b[3] * a[2] - b[2] * a[3] + b[0] * a[6];
+            res[7] = b[7] * a[0] + b[6] * a[1] - b[5] * a[2] + b[4] * a[3] + b[3] * a[4]
+                - b[2] * a[5]
+                + b[1] * a[6]
+                + b[0] * a[7];
+            res
+        }
+
+        pub fn geometric_product(a: R300, b: R300) -> R300 {
+            let mut res = R300::zero();
+            res[0] = b[0] * a[0] + b[1] * a[1] + b[2] * a[2] + b[3] * a[3]
+                - b[4] * a[4]
+                - b[5] * a[5]
+                - b[6] * a[6]
+                - b[7] * a[7];
+            res[1] =
+                b[1] * a[0] + b[0] * a[1] - b[4] * a[2] - b[5] * a[3] + b[2] * a[4] + b[3] * a[5]
+                    - b[7] * a[6]
+                    - b[6] * a[7];
+            res[2] = b[2] * a[0] + b[4] * a[1] + b[0] * a[2] - b[6] * a[3] - b[1] * a[4]
+                + b[7] * a[5]
+                + b[3] * a[6]
+                + b[5] * a[7];
+            res[3] = b[3] * a[0] + b[5] * a[1] + b[6] * a[2] + b[0] * a[3]
+                - b[7] * a[4]
+                - b[1] * a[5]
+                - b[2] * a[6]
+                - b[4] * a[7];
+            res[4] = b[4] * a[0] + b[2] * a[1] - b[1] * a[2] + b[7] * a[3] + b[0] * a[4]
+                - b[6] * a[5]
+                + b[5] * a[6]
+                + b[3] * a[7];
+            res[5] =
+                b[5] * a[0] + b[3] * a[1] - b[7] * a[2] - b[1] * a[3] + b[6] * a[4] + b[0] * a[5]
+                    - b[4] * a[6]
+                    - b[2] * a[7];
+            res[6] = b[6] * a[0] + b[7] * a[1] + b[3] * a[2] - b[2] * a[3] - b[5] * a[4]
+                + b[4] * a[5]
+                + b[0] * a[6]
+                + b[1] * a[7];
+            res[7] = b[7] * a[0] + b[6] * a[1] - b[5] * a[2] + b[4] * a[3] + b[3] * a[4]
+                - b[2] * a[5]
+                + b[1] * a[6]
+                + b[0] * a[7];
+            res
+        }
     }
 }
@@ -0,0 +1,238 @@
+use crate::r300::{basis_count, basis_grades, R300};
+use crate::scalar::Scalar;
+use std::ops::{Add, BitOr, BitXor, Index, IndexMut, Mul, Neg, Sub};
+
+// Basis blades as bitmasks of the vectors they contain, in the same order as `basis` in r300.rs.
+// Every blade is stored with ascending indices (e13, not e31), so e_A e_B only needs the sign of
+// the reordering and the metric is Euclidean.
+const basis_bitmasks: [usize; basis_count] =
+    [0b000, 0b001, 0b010, 0b100, 0b011, 0b101, 0b110, 0b111];
+
+/// A multivector of R300 over any `Scalar`, used where coefficients are not plain numbers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Multivector<S> {
+    pub mvec: [S; basis_count],
+}
+
+impl<S: Scalar> Multivector<S> {
+    pub fn zero() -> Self {
+        Self::from_fn(|_| S::zero())
+    }
+
+    pub fn new(value: S, idx: usize) -> Self {
+        let mut res = Self::zero();
+        res.mvec[idx] = value;
+        res
+    }
+
+    pub fn from_fn(f: impl FnMut(usize) -> S) -> Self {
+        Self {
+            mvec: std::array::from_fn(f),
+        }
+    }
+
+    pub fn map<T: Scalar>(&self, mut f: impl FnMut(&S) -> T) -> Multivector<T> {
+        Multivector::from_fn(|i| f(&self.mvec[i]))
+    }
+
+    pub fn geometric_product(&self, b: &Self) -> Self {
+        self.product(b, |_, _, _| true)
+    }
+
+    // The grade r + s part of A_r B_s.
+    pub fn wedge(&self, b: &Self) -> Self {
+        self.product(b, |r, s, grade| grade == r + s)
+    }
+
+    // The grade |r - s| part of A_r B_s, scalars included, matching `R300::dot`.
+    pub fn dot(&self, b: &Self) -> Self {
+        self.product(b, |r, s, grade| grade == r.abs_diff(s))
+    }
+
+    pub fn scale(&self, factor: &S) -> Self {
+        self.map(|coefficient| coefficient.clone() * factor.clone())
+    }
+
+    pub fn Reverse(&self) -> Self {
+        self.grade_signs(|grade| grade * (grade.saturating_sub(1)) / 2)
+    }
+
+    pub fn Involute(&self) -> Self {
+        self.grade_signs(|grade| grade)
+    }
+
+    pub fn Conjugate(&self) -> Self {
+        self.grade_signs(|grade| grade * (grade + 1) / 2)
+    }
+
+    // A e123, the same duality as `R300::Dual`.
+    pub fn Dual(&self) -> Self {
+        self.geometric_product(&Self::new(S::one(), 7))
+    }
+
+    pub fn grade_part(&self, grade: usize) -> Self {
+        Self::from_fn(|i| {
+            if basis_grades[i] == grade {
+                self.mvec[i].clone()
+            } else {
+                S::zero()
+            }
+        })
+    }
+
+    // The same construction as `R300::inverse`: A conj(A) = s + p e123 is central and
+    // (s + p e123)^-1 = (s - p e123) / (s^2 + p^2).
+    pub fn inverse(&self) -> Option<Self> {
+        let conjugate = self.Conjugate();
+        let center = self.geometric_product(&conjugate);
+        let (s, p) = (center[0].clone(), center[7].clone());
+        let denominator = (s.clone() * s.clone() + p.clone() * p.clone()).recip()?;
+        let center_inverse = Self::new(s, 0) + Self::new(-p, 7);
+        Some(conjugate.geometric_product(&center_inverse.scale(&denominator)))
+    }
+
+    // Integer powers by repeated squaring, negative powers go through the inverse.
+    pub fn pow(&self, n: i64) -> Option<Self> {
+        let mut base = if n < 0 { self.inverse()? } else { self.clone() };
+        let mut exponent = n.unsigned_abs();
+        let mut res = Self::new(S::one(), 0);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                res = res.geometric_product(&base);
+            }
+            base = base.geometric_product(&base);
+            exponent >>= 1;
+        }
+        Some(res)
+    }
+
+    fn grade_signs(&self, exponent: fn(usize) -> usize) -> Self {
+        Self::from_fn(|i| {
+            if exponent(basis_grades[i]) & 1 == 0 {
+                self.mvec[i].clone()
+            } else {
+                -self.mvec[i].clone()
+            }
+        })
+    }
+
+    fn product(&self, b: &Self, keep: fn(usize, usize, usize) -> bool) -> Self {
+        let mut res = Self::zero();
+        for (i, a_i) in self
+            .mvec
+            .iter()
+            .enumerate()
+            .filter(|(_, a_i)| !a_i.is_zero())
+        {
+            for (j, b_j) in b.mvec.iter().enumerate().filter(|(_, b_j)| !b_j.is_zero()) {
+                let (k, sign) = blade_product(i, j);
+                if !keep(basis_grades[i], basis_grades[j], basis_grades[k]) {
+                    continue;
+                }
+                let term = a_i.clone() * b_j.clone();
+                res.mvec[k] = if sign > 0 {
+                    res.mvec[k].clone() + term
+                } else {
+                    res.mvec[k].clone() - term
+                };
+            }
+        }
+        res
+    }
+}
+
+/// e_i e_j = sign e_k for basis indices i, j.
+fn blade_product(i: usize, j: usize) -> (usize, i32) {
+    let (a, b) = (basis_bitmasks[i], basis_bitmasks[j]);
+    // each vector of b has to move past the vectors of a with a higher index
+    let mut swaps = 0;
+    let mut higher = a >> 1;
+    while higher != 0 {
+        swaps += (higher & b).count_ones();
+        higher >>= 1;
+    }
+    let k = basis_bitmasks
+        .iter()
+        .position(|&mask| mask == a ^ b)
+        .unwrap();
+    (k, if swaps % 2 == 0 { 1 } else { -1 })
+}
+
+impl From<R300> for Multivector<f64> {
+    fn from(value: R300) -> Self {
+        Self::from_fn(|i| value[i])
+    }
+}
+
+impl From<Multivector<f64>> for R300 {
+    fn from(value: Multivector<f64>) -> Self {
+        let mut res = R300::zero();
+        for (i, coefficient) in value.mvec.into_iter().enumerate() {
+            res[i] = coefficient;
+        }
+        res
+    }
+}
+
+impl<S> Index<usize> for Multivector<S> {
+    type Output = S;
+
+    fn index(&self, index: usize) -> &S {
+        &self.mvec[index]
+    }
+}
+
+impl<S> IndexMut<usize> for Multivector<S> {
+    fn index_mut(&mut self, index: usize) -> &mut S {
+        &mut self.mvec[index]
+    }
+}
+
+impl<S: Scalar> Add for Multivector<S> {
+    type Output = Self;
+
+    fn add(self, b: Self) -> Self {
+        Self::from_fn(|i| self.mvec[i].clone() + b.mvec[i].clone())
+    }
+}
+
+impl<S: Scalar> Sub for Multivector<S> {
+    type Output = Self;
+
+    fn sub(self, b: Self) -> Self {
+        Self::from_fn(|i| self.mvec[i].clone() - b.mvec[i].clone())
+    }
+}
+
+impl<S: Scalar> Neg for Multivector<S> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.map(|coefficient| -coefficient.clone())
+    }
+}
+
+// The geometric product.
+impl<S: Scalar> Mul for Multivector<S> {
+    type Output = Self;
+
+    fn mul(self, b: Self) -> Self {
+        self.geometric_product(&b)
+    }
+}
+
+impl<S: Scalar> BitXor for Multivector<S> {
+    type Output = Self;
+
+    fn bitxor(self, b: Self) -> Self {
+        self.wedge(&b)
+    }
+}
+
+impl<S: Scalar> BitOr for Multivector<S> {
+    type Output = Self;
+
+    fn bitor(self, b: Self) -> Self {
+        self.dot(&b)
+    }
+}
@@ -0,0 +1,183 @@
+use crate::r300::{R300, TOLERANCE};
+use crate::rng::Rng;
+use crate::symbols::ReservedSymbols;
+use crate::{evaluate, Evaluated};
+use latex_expr_parser::ASTNode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Sweeps longer than this are refused rather than freezing the page.
+const MAX_SAMPLES: usize = 10_000;
+/// Samples a range is cut into when it has no step.
+const DEFAULT_SAMPLES: f64 = 100.0;
+
+/// A scalar identifier shown as a slider, such as the angle in `e^{-\frac{\theta}{2}B}`.
+///
+/// In JSON: `{"min": 0, "max": 6.28, "step": 0.01}`, the step being optional.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+#[cfg_attr(test, ts(export_to = "../../components/generated/"))]
+pub struct Parameter {
+    pub min: f64,
+    pub max: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub step: Option<f64>,
+}
+
+impl Parameter {
+    /// The distance between two slider positions, a hundredth of the range by default.
+    pub fn step(&self) -> f64 {
+        self.step.unwrap_or((self.max - self.min) / DEFAULT_SAMPLES)
+    }
+
+    pub fn validate(&self, name: &str) -> Result<(), ParameterError> {
+        let invalid = |reason: &str| {
+            Err(ParameterError::InvalidRange {
+                name: name.to_string(),
+                reason: reason.to_string(),
+            })
+        };
+        if !self.min.is_finite() || !self.max.is_finite() {
+            return invalid("the bounds are not finite");
+        }
+        if self.min > self.max {
+            return invalid("min is greater than max");
+        }
+        if self
+            .step
+            .is_some_and(|step| !(step > 0.0 && step.is_finite()))
+        {
+            return invalid("the step is not positive");
+        }
+        if self.count() > MAX_SAMPLES {
+            return invalid("too many steps");
+        }
+        Ok(())
+    }
+
+    /// The number of slider positions, both ends included. The last step may be shorter when
+    /// `step` does not divide the range.
+    pub fn count(&self) -> usize {
+        let span = self.max - self.min;
+        if span == 0.0 {
+            return 1;
+        }
+        // A step that divides the range up to rounding does not add a sliver at the end.
+        let steps = span / self.step() - TOLERANCE.sqrt();
+        steps.ceil().max(0.0) as usize + 1
+    }
+
+    /// The slider positions from `min` to `max`.
+    pub fn values(&self) -> impl Iterator<Item = f64> + '_ {
+        let count = self.count();
+        (0..count).map(move |i| {
+            if i + 1 == count {
+                self.max
+            } else {
+                self.min + i as f64 * self.step()
+            }
+        })
+    }
+
+    /// A slider position drawn from `rng`.
+    pub fn sample(&self, rng: &mut Rng) -> f64 {
+        let i = (rng.next_f64() * self.count() as f64) as usize;
+        self.values().nth(i).unwrap_or(self.min)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParameterError {
+    InvalidRange { name: String, reason: String },
+}
+
+impl fmt::Display for ParameterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParameterError::InvalidRange { name, reason } => {
+                write!(f, "Invalid range for {}: {}", name, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParameterError {}
+
+/// The value of a node at one sample, None when it could not be evaluated there, e.g. an
+/// inverse that passes through zero.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodeValue {
+    pub start: usize,
+    pub end: usize,
+    pub value: Option<R300>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Sample {
+    pub parameter: f64,
+    /// Every node in post order, so the last one is the root.
+    pub nodes: Vec<NodeValue>,
+}
+
+/// An expression evaluated along the range of one parameter, for animating it or drawing the
+/// path its result traces.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Sweep {
+    pub name: String,
+    pub samples: Vec<Sample>,
+}
+
+impl Sweep {
+    /// The values of the node spanning `start..end` along the sweep.
+    pub fn trajectory(&self, start: usize, end: usize) -> Vec<Option<R300>> {
+        self.samples
+            .iter()
+            .map(|sample| {
+                sample
+                    .nodes
+                    .iter()
+                    .rev()
+                    .find(|node| node.start == start && node.end == end)
+                    .and_then(|node| node.value)
+            })
+            .collect()
+    }
+}
+
+/// Evaluates `ast` with the scalar `name` set to each position of `parameter` in turn and the
+/// other variables fixed.
+pub fn sweep(
+    ast: &ASTNode,
+    vars: &HashMap<String, R300>,
+    symbols: &ReservedSymbols,
+    name: &str,
+    parameter: &Parameter,
+) -> Result<Sweep, ParameterError> {
+    parameter.validate(name)?;
+    let mut vars = vars.clone();
+    let samples = parameter
+        .values()
+        .map(|value| {
+            vars.insert(name.to_string(), R300::new(value, 0));
+            let evaluated = evaluate(ast, &vars, symbols);
+            Sample {
+                parameter: value,
+                nodes: evaluated
+                    .post_order()
+                    .into_iter()
+                    .map(|node: &Evaluated| NodeValue {
+                        start: node.start(),
+                        end: node.end(),
+                        value: node.meta.value,
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    Ok(Sweep {
+        name: name.to_string(),
+        samples,
+    })
+}
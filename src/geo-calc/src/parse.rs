@@ -0,0 +1,304 @@
+use crate::r300::{e1, e2, e3, R300};
+use crate::symbols::normalize_symbol;
+use std::fmt;
+use std::str::FromStr;
+
+/// Why a multivector literal could not be read, `position` is the byte offset into the text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub position: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    Empty,
+    UnexpectedCharacter(char),
+    UnexpectedEnd,
+    InvalidNumber(String),
+    InvalidBladeIndex(char),
+    /// An index used twice in one blade, e.g. `e11`.
+    RepeatedBladeIndex(char),
+    MissingBladeIndex,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            ParseErrorKind::Empty => write!(f, "Expected a multivector"),
+            ParseErrorKind::UnexpectedCharacter(c) => {
+                write!(f, "Unexpected '{}' at position {}", c, self.position)
+            }
+            ParseErrorKind::UnexpectedEnd => write!(f, "Unexpected end of input"),
+            ParseErrorKind::InvalidNumber(number) => {
+                write!(
+                    f,
+                    "Invalid number '{}' at position {}",
+                    number, self.position
+                )
+            }
+            ParseErrorKind::InvalidBladeIndex(c) => write!(
+                f,
+                "Invalid basis index '{}' at position {}, expected 1, 2 or 3",
+                c, self.position
+            ),
+            ParseErrorKind::RepeatedBladeIndex(c) => write!(
+                f,
+                "Basis index '{}' repeated at position {}",
+                c, self.position
+            ),
+            ParseErrorKind::MissingBladeIndex => {
+                write!(f, "Expected a basis index at position {}", self.position)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, prefix: &str) -> bool {
+        if self.text[self.position..].starts_with(prefix) {
+            self.position += prefix.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            kind,
+            position: self.position,
+        }
+    }
+
+    fn unexpected(&self) -> ParseError {
+        match self.peek() {
+            Some(c) => self.error(ParseErrorKind::UnexpectedCharacter(c)),
+            None => self.error(ParseErrorKind::UnexpectedEnd),
+        }
+    }
+
+    /// `literal := sign? term (('+' | '-') term)*`
+    fn literal(&mut self) -> Result<R300, ParseError> {
+        self.skip_whitespace();
+        if self.peek().is_none() {
+            return Err(self.error(ParseErrorKind::Empty));
+        }
+        let mut sign = self.sign();
+        let mut res = R300::zero();
+        loop {
+            let term = self.term()?;
+            res = res + R300::new(sign, 0) * term;
+            self.skip_whitespace();
+            match self.peek() {
+                None => return Ok(res),
+                Some('+' | '-' | '−') => sign = self.sign(),
+                Some(_) => return Err(self.unexpected()),
+            }
+        }
+    }
+
+    fn sign(&mut self) -> f64 {
+        let mut sign = 1.0;
+        loop {
+            self.skip_whitespace();
+            if self.eat("-") || self.eat("−") {
+                sign = -sign;
+            } else if !self.eat("+") {
+                return sign;
+            }
+        }
+    }
+
+    /// `term := number? ('*'? blade)*`, with at least a number or a blade. Blades multiply, so
+    /// `2 e1 e2` is `2e12`.
+    fn term(&mut self) -> Result<R300, ParseError> {
+        self.skip_whitespace();
+        let start = self.position;
+        let mut res = R300::new(self.number()?.unwrap_or(1.0), 0);
+        let mut factors = self.position > start;
+        loop {
+            self.skip_whitespace();
+            let before = self.position;
+            let star = self.eat("*");
+            if star {
+                self.skip_whitespace();
+            }
+            match self.blade()? {
+                Some(blade) => {
+                    res = res * blade;
+                    factors = true;
+                }
+                None if star => return Err(self.unexpected()),
+                None => {
+                    self.position = before;
+                    break;
+                }
+            }
+        }
+        if factors {
+            Ok(res)
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn number(&mut self) -> Result<Option<f64>, ParseError> {
+        let start = self.position;
+        let digits = |parser: &mut Self| {
+            while parser.peek().is_some_and(|c| c.is_ascii_digit()) {
+                parser.bump();
+            }
+        };
+        digits(self);
+        if self.eat(".") {
+            digits(self);
+        }
+        if self.position == start {
+            return Ok(None);
+        }
+        // An exponent only counts when digits follow, `2e1` is 2 times e1.
+        let mantissa_end = self.position;
+        if self.eat("E") {
+            self.eat("-");
+            self.eat("+");
+            let exponent_start = self.position;
+            digits(self);
+            if self.position == exponent_start {
+                self.position = mantissa_end;
+            }
+        }
+        let number = &self.text[start..self.position];
+        number.parse().map(Some).map_err(|_| ParseError {
+            kind: ParseErrorKind::InvalidNumber(number.to_string()),
+            position: start,
+        })
+    }
+
+    /// `e12`, `e_12`, `e_{12}`, `e_{1 2}`, `\sigma_1`, `σ1`, `σ₁`, `I` or `i`. Indices in any
+    /// order, so `e31` is `-e13`, but each at most once, `e11` is not a blade.
+    fn blade(&mut self) -> Result<Option<R300>, ParseError> {
+        if self.eat("I") || self.eat("i") {
+            return Ok(Some(e1 * e2 * e3));
+        }
+        let sigma = if self.eat("e") {
+            false
+        } else if self.eat("\\sigma") || self.eat("σ") {
+            true
+        } else {
+            return Ok(None);
+        };
+        self.eat("_");
+        let braced = self.eat("{");
+        let start = self.position;
+        let end = if braced {
+            match self.text[start..].find('}') {
+                Some(offset) => start + offset,
+                None => {
+                    self.position = self.text.len();
+                    return Err(self.unexpected());
+                }
+            }
+        } else {
+            let digits = self.text[start..]
+                .char_indices()
+                .take_while(|&(_, c)| index_digit(c).is_some())
+                .take(if sigma { 1 } else { usize::MAX })
+                .last();
+            digits.map_or(start, |(offset, c)| start + offset + c.len_utf8())
+        };
+        let text = &self.text[start..end];
+        // The same spellings as reserved symbols, so `e_{1 2}` is `e_{12}` here too.
+        let indices = normalize_symbol(text);
+        if indices.is_empty() {
+            return Err(self.error(ParseErrorKind::MissingBladeIndex));
+        }
+        let mut res = R300::new(1.0, 0);
+        let mut seen = Vec::new();
+        for (n, c) in indices.chars().enumerate() {
+            // The byte offset of the `n`th index in the text as written.
+            let position = text
+                .char_indices()
+                .filter(|&(_, c)| !c.is_whitespace())
+                .nth(n)
+                .map_or(start, |(offset, _)| start + offset);
+            let error = |kind| ParseError { kind, position };
+            let index = index_digit(c);
+            let vector = match index {
+                Some(1) => e1,
+                Some(2) => e2,
+                Some(3) => e3,
+                _ => return Err(error(ParseErrorKind::InvalidBladeIndex(c))),
+            };
+            if seen.contains(&index) {
+                return Err(error(ParseErrorKind::RepeatedBladeIndex(c)));
+            }
+            seen.push(index);
+            res = res * vector;
+        }
+        self.position = if braced { end + 1 } else { end };
+        Ok(Some(res))
+    }
+}
+
+/// The value of an index digit, `1` and `₁` both being 1.
+fn index_digit(c: char) -> Option<u32> {
+    match c {
+        '0'..='9' => c.to_digit(10),
+        '₀'..='₉' => Some(c as u32 - '₀' as u32),
+        _ => None,
+    }
+}
+
+impl R300 {
+    /// Reads a sum of scaled basis blades such as `1 + 2e1 - 0.5e23` or `\sigma_1 + 3I`.
+    pub fn parse(text: &str) -> Result<R300, ParseError> {
+        Parser { text, position: 0 }.literal()
+    }
+
+    /// Reads a single basis blade written with `e` or `\sigma`, e.g. `e_3` or `\sigma_1`.
+    pub(crate) fn parse_blade(text: &str) -> Option<R300> {
+        if !["e", "\\sigma", "σ"]
+            .iter()
+            .any(|prefix| text.starts_with(prefix))
+        {
+            return None;
+        }
+        let mut parser = Parser { text, position: 0 };
+        match parser.blade() {
+            Ok(Some(blade)) if parser.position == text.len() => Some(blade),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for R300 {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        R300::parse(text)
+    }
+}
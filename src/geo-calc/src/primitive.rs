@@ -0,0 +1,177 @@
+use crate::r300::R300;
+use serde::Serialize;
+
+/// A point or direction in the coordinates of the 3D view.
+pub type Point = [f64; 3];
+
+/// The vector part of `value` as coordinates.
+pub fn point(value: R300) -> Point {
+    [value[1], value[2], value[3]]
+}
+
+/// Plain geometry, what the 3D view draws without knowing any geometric algebra.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+#[cfg_attr(test, ts(export_to = "../../components/generated/"))]
+#[serde(tag = "primitive", rename_all = "snake_case")]
+pub enum Primitive {
+    /// An arrow from `origin` to `origin + vector`.
+    Arrow { origin: Point, vector: Point },
+    /// A helper line, e.g. the drop from a vector to its projection.
+    Segment { from: Point, to: Point },
+    /// The oriented parallelogram with corner `origin` and sides `u` then `v`.
+    Parallelogram { origin: Point, u: Point, v: Point },
+    /// The oriented box with corner `origin` and edges `u`, `v` then `w`, right handed when
+    /// `u \wedge v \wedge w` is a positive multiple of e123.
+    Parallelepiped {
+        origin: Point,
+        u: Point,
+        v: Point,
+        w: Point,
+    },
+    /// A disc around `center` facing `normal`, circulating counterclockwise seen from the tip of
+    /// `normal`.
+    Disc {
+        center: Point,
+        normal: Point,
+        radius: f64,
+    },
+    /// A ball around `center`, for a trivector whose volume has no preferred shape.
+    Sphere {
+        center: Point,
+        radius: f64,
+        handedness: Handedness,
+    },
+    /// The circular arc `center + cos(t) u + sin(t) v` for `t` from 0 to `angle`, `u` and `v`
+    /// being orthogonal and of the same length. Its end carries an arrowhead.
+    Arc {
+        center: Point,
+        u: Point,
+        v: Point,
+        angle: f64,
+    },
+}
+
+/// The orientation of a volume, the sign of its e123 component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+#[cfg_attr(test, ts(export_to = "../../components/generated/"))]
+#[serde(rename_all = "snake_case")]
+pub enum Handedness {
+    Right,
+    Left,
+}
+
+impl Handedness {
+    pub fn of(volume: f64) -> Self {
+        if volume < 0.0 {
+            Handedness::Left
+        } else {
+            Handedness::Right
+        }
+    }
+}
+
+/// What a shape stands for, so the view can colour operands and results consistently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Left,
+    Right,
+    /// The only operand of a unary operation.
+    Operand,
+    Result,
+    /// A construction line that is not a value.
+    Guide,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Shape {
+    pub role: Role,
+    #[serde(flatten)]
+    pub primitive: Primitive,
+}
+
+impl Shape {
+    pub fn arrow(role: Role, origin: R300, vector: R300) -> Self {
+        Self {
+            role,
+            primitive: Primitive::Arrow {
+                origin: point(origin),
+                vector: point(vector),
+            },
+        }
+    }
+
+    pub fn segment(role: Role, from: R300, to: R300) -> Self {
+        Self {
+            role,
+            primitive: Primitive::Segment {
+                from: point(from),
+                to: point(to),
+            },
+        }
+    }
+
+    pub fn parallelogram(role: Role, u: R300, v: R300) -> Self {
+        Self {
+            role,
+            primitive: Primitive::Parallelogram {
+                origin: [0.0; 3],
+                u: point(u),
+                v: point(v),
+            },
+        }
+    }
+
+    pub fn parallelepiped(role: Role, u: R300, v: R300, w: R300) -> Self {
+        Self {
+            role,
+            primitive: Primitive::Parallelepiped {
+                origin: [0.0; 3],
+                u: point(u),
+                v: point(v),
+                w: point(w),
+            },
+        }
+    }
+}
+
+/// Two orthogonal vectors whose wedge is the bivector `blade`, the first one of unit length,
+/// None when `blade` is not a nonzero bivector.
+pub fn factor(blade: R300) -> Option<(R300, R300)> {
+    if !blade.is_bivector() || blade.norm() == 0.0 {
+        return None;
+    }
+    let normal = blade.Dual().normalized();
+    // The axis furthest from the normal gives the best conditioned vector in the plane.
+    let u = [
+        R300::vector(1.0, 0.0, 0.0),
+        R300::vector(0.0, 1.0, 0.0),
+        R300::vector(0.0, 0.0, 1.0),
+    ]
+    .into_iter()
+    .map(|axis| axis - axis.dot(normal)[0] * normal)
+    .max_by(|a, b| a.norm().total_cmp(&b.norm()))?
+    .normalized();
+    // u in the plane of B gives u B = u . B, and u (u B) = B.
+    Some((u, (u * blade).grade_part(1)))
+}
+
+/// The e123 component of a trivector, the volume of any three vectors whose wedge it is,
+/// negative for left handed ones. None for values of other grades.
+pub fn signed_volume(value: R300) -> Option<f64> {
+    (value.grade() == Some(3)).then_some(value[7])
+}
+
+/// Three orthogonal edges of equal length whose wedge is the trivector `value`, along the axes
+/// and with the last one flipped for a negative volume.
+pub fn factor_trivector(value: R300) -> Option<(R300, R300, R300)> {
+    let volume = signed_volume(value)?;
+    let side = volume.abs().cbrt();
+    Some((
+        R300::vector(side, 0.0, 0.0),
+        R300::vector(0.0, side, 0.0),
+        R300::vector(0.0, 0.0, side.copysign(volume)),
+    ))
+}
@@ -1,14 +1,22 @@
+use crate::format::{format_multivector, FormatOptions, Notation};
+use crate::multivector::Multivector;
+use crate::schema::Blades;
 use serde::{Deserialize, Serialize};
 use serde_wasm_bindgen::{from_value, to_value};
 use std::f64::consts::PI;
 /// taken from https://bivector.net/tools.html?p=3&q=0&r=0
-// Written by a generator written by enki.
+// Written by a generator written by enki. The products now go through the generic
+// `Multivector` so that the same code also runs over exact and symbolic coefficients.
 use std::fmt;
 use std::ops::{Add, BitAnd, BitOr, BitXor, Index, IndexMut, Mul, Not, Sub};
 use wasm_bindgen::prelude::*;
 
-const basis: &'static [&'static str] = &["1", "e1", "e2", "e3", "e12", "e13", "e23", "e123"];
-const basis_count: usize = basis.len();
+pub const basis: &[&str] = &["1", "e1", "e2", "e3", "e12", "e13", "e23", "e123"];
+pub const basis_count: usize = basis.len();
+pub const basis_grades: [usize; basis_count] = [0, 1, 1, 1, 2, 2, 2, 3];
+
+/// Below this a norm, angle or residual counts as zero.
+pub const TOLERANCE: f64 = 1e-12;
 
 #[wasm_bindgen]
 #[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
@@ -59,6 +67,22 @@ impl R300 {
         format!("R300({})", parts.join(", "))
     }
 
+    /// The value in standard notation for KaTeX, e.g. `\frac{\sqrt{2}}{2} - \frac{\sqrt{2}}{2}
+    /// e_{12}`. `format_multivector` takes `FormatOptions` for other basis symbols or grade grouping.
+    #[wasm_bindgen(js_name = toLatex)]
+    pub fn to_latex(&self) -> String {
+        self.to_closed_form(&FormatOptions::default())
+    }
+
+    /// Like `to_latex`, as a `<math>` element.
+    #[wasm_bindgen(js_name = toMathML)]
+    pub fn to_mathml(&self) -> String {
+        self.to_closed_form(&FormatOptions {
+            notation: Notation::MathML,
+            ..Default::default()
+        })
+    }
+
     pub fn get(&self, idx: usize) -> f64 {
         self.mvec[idx]
     }
@@ -73,6 +97,21 @@ impl R300 {
         from_value(json).map_err(|e| JsValue::from_str(&format!("Failed to parse JSON: {}", e)))
     }
 
+    /// Reads the named blade components of a node value, see `schema::Blades`.
+    #[wasm_bindgen(js_name = fromBlades)]
+    pub fn from_blades(json: JsValue) -> Result<Self, JsValue> {
+        from_value::<Blades>(json)
+            .map(R300::from)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse blades: {}", e)))
+    }
+
+    /// Reads a literal such as `1 + 2e1 - 0.5e23`, see `R300::parse`.
+    #[wasm_bindgen(js_name = fromString)]
+    pub fn from_string(text: &str) -> Result<R300, JsValue> {
+        R300::parse(text)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse multivector: {}", e)))
+    }
+
     #[wasm_bindgen(js_name = isScalar)]
     pub fn is_scalar(&self) -> bool {
         self.mvec[1] == 0.0
@@ -104,24 +143,24 @@ impl R300 {
 }
 
 // basis vectors are available as global constants.
-const e1: R300 = R300::new(1.0, 1);
-const e2: R300 = R300::new(1.0, 2);
-const e3: R300 = R300::new(1.0, 3);
-const e12: R300 = R300::new(1.0, 4);
-const e13: R300 = R300::new(1.0, 5);
-const e23: R300 = R300::new(1.0, 6);
-const e123: R300 = R300::new(1.0, 7);
+pub const e1: R300 = R300::new(1.0, 1);
+pub const e2: R300 = R300::new(1.0, 2);
+pub const e3: R300 = R300::new(1.0, 3);
+pub const e12: R300 = R300::new(1.0, 4);
+pub const e13: R300 = R300::new(1.0, 5);
+pub const e23: R300 = R300::new(1.0, 6);
+pub const e123: R300 = R300::new(1.0, 7);
 
 impl Index<usize> for R300 {
     type Output = f64;
 
-    fn index<'a>(&'a self, index: usize) -> &'a Self::Output {
+    fn index(&self, index: usize) -> &Self::Output {
         &self.mvec[index]
     }
 }
 
 impl IndexMut<usize> for R300 {
-    fn index_mut<'a>(&'a mut self, index: usize) -> &'a mut Self::Output {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         &mut self.mvec[index]
     }
 }
@@ -134,7 +173,7 @@ impl fmt::Display for R300 {
             .iter()
             .enumerate()
             .filter_map(|(i, &coeff)| {
-                if coeff > 0.00001 || coeff < -0.00001 {
+                if !(-0.00001..=0.00001).contains(&coeff) {
                     n = 1;
                     Some(format!(
                         "{}{}",
@@ -158,221 +197,231 @@ impl fmt::Display for R300 {
 }
 
 impl R300 {
+    /// Writes the coefficients in closed form where possible, `display` and `Display` always
+    /// use decimals.
+    pub fn to_closed_form(&self, options: &FormatOptions) -> String {
+        format_multivector(self, options)
+    }
+
     // Reverse
     // Reverse the order of the basis blades.
-    pub fn Reverse(self: Self) -> R300 {
-        let mut res = R300::zero();
-        let a = self;
-        res[0] = a[0];
-        res[1] = a[1];
-        res[2] = a[2];
-        res[3] = a[3];
-        res[4] = -a[4];
-        res[5] = -a[5];
-        res[6] = -a[6];
-        res[7] = -a[7];
-        res
+    pub fn Reverse(self) -> R300 {
+        Multivector::from(self).Reverse().into()
     }
 
     // Dual
     // Poincare duality operator.
-    pub fn Dual(self: Self) -> R300 {
-        let mut res = R300::zero();
-        let a = self;
-        res[0] = -a[7];
-        res[1] = -a[6];
-        res[2] = a[5];
-        res[3] = -a[4];
-        res[4] = a[3];
-        res[5] = -a[2];
-        res[6] = a[1];
-        res[7] = a[0];
-        res
+    pub fn Dual(self) -> R300 {
+        Multivector::from(self).Dual().into()
     }
 
     // Conjugate
     // Clifford Conjugation
-    pub fn Conjugate(self: Self) -> R300 {
-        let mut res = R300::zero();
-        let a = self;
-        res[0] = a[0];
-        res[1] = -a[1];
-        res[2] = -a[2];
-        res[3] = -a[3];
-        res[4] = -a[4];
-        res[5] = -a[5];
-        res[6] = -a[6];
-        res[7] = a[7];
-        res
+    pub fn Conjugate(self) -> R300 {
+        Multivector::from(self).Conjugate().into()
     }
 
     // Involute
     // Main involution
-    pub fn Involute(self: Self) -> R300 {
-        let mut res = R300::zero();
-        let a = self;
-        res[0] = a[0];
-        res[1] = -a[1];
-        res[2] = -a[2];
-        res[3] = -a[3];
-        res[4] = a[4];
-        res[5] = a[5];
-        res[6] = a[6];
-        res[7] = -a[7];
-        res
+    pub fn Involute(self) -> R300 {
+        Multivector::from(self).Involute().into()
     }
     pub fn bracket(self, blade: u8) -> R300 {
         R300::new(self[blade as usize], blade as usize)
     }
 
     // taken from New Foundation for Classical Mechanics - David Hestenes - page 61 (Magnitude)
-    pub fn magnitude_squared(self: Self) -> f64 {
-        let scalar_part = (self * self.Reverse())[0];
-
-        scalar_part
+    // |A|^2 = <A reverse(A)>_0, which in R300 is the sum of the squared coefficients. This is the
+    // only magnitude used in the crate, `norm`, `|A|`, `\|A\|`, area and angle all build on it.
+    pub fn magnitude_squared(self) -> f64 {
+        (self * self.Reverse())[0]
     }
 
-    pub fn norm(self: Self) -> f64 {
-        let scalar_part = (self * self.Conjugate())[0];
-
-        scalar_part.abs().sqrt()
+    pub fn norm(self) -> f64 {
+        self.magnitude_squared().sqrt()
     }
 
-    pub fn inorm(self: Self) -> f64 {
+    pub fn inorm(self) -> f64 {
         self.Dual().norm()
     }
 
-    pub fn normalized(self: Self) -> Self {
+    pub fn normalized(self) -> Self {
         self * (1.0 / self.norm())
     }
 
-    pub fn dot(self: Self, b: Self) -> R300 {
+    pub fn grade_part(self, grade: usize) -> R300 {
         let mut res = R300::zero();
-        let a = self;
-        res[0] = b[0] * a[0] + b[1] * a[1] + b[2] * a[2] + b[3] * a[3]
-            - b[4] * a[4]
-            - b[5] * a[5]
-            - b[6] * a[6]
-            - b[7] * a[7];
-        res[1] = b[1] * a[0] + b[0] * a[1] - b[4] * a[2] - b[5] * a[3] + b[2] * a[4] + b[3] * a[5]
-            - b[7] * a[6]
-            - b[6] * a[7];
-        res[2] = b[2] * a[0] + b[4] * a[1] + b[0] * a[2] - b[6] * a[3] - b[1] * a[4]
-            + b[7] * a[5]
-            + b[3] * a[6]
-            + b[5] * a[7];
-        res[3] = b[3] * a[0] + b[5] * a[1] + b[6] * a[2] + b[0] * a[3]
-            - b[7] * a[4]
-            - b[1] * a[5]
-            - b[2] * a[6]
-            - b[4] * a[7];
-        res[4] = b[4] * a[0] + b[7] * a[3] + b[0] * a[4] + b[3] * a[7];
-        res[5] = b[5] * a[0] - b[7] * a[2] + b[0] * a[5] - b[2] * a[7];
-        res[6] = b[6] * a[0] + b[7] * a[1] + b[0] * a[6] + b[1] * a[7];
-        res[7] = b[7] * a[0] + b[0] * a[7];
+        for (i, &blade_grade) in basis_grades.iter().enumerate() {
+            if blade_grade == grade {
+                res[i] = self[i];
+            }
+        }
         res
     }
 
-    pub fn wedge(self: Self, b: Self) -> R300 {
-        let mut res = R300::zero();
-        let a = self;
-        res[0] = b[0] * a[0];
-        res[1] = b[1] * a[0] + b[0] * a[1];
-        res[2] = b[2] * a[0] + b[0] * a[2];
-        res[3] = b[3] * a[0] + b[0] * a[3];
-        res[4] = b[4] * a[0] + b[2] * a[1] - b[1] * a[2] + b[0] * a[4];
-        res[5] = b[5] * a[0] + b[3] * a[1] - b[1] * a[3] + b[0] * a[5];
-        res[6] = b[6] * a[0] + b[3] * a[2] - b[2] * a[3] + b[0] * a[6];
-        res[7] = b[7] * a[0] + b[6] * a[1] - b[5] * a[2] + b[4] * a[3] + b[3] * a[4] - b[2] * a[5]
-            + b[1] * a[6]
-            + b[0] * a[7];
-        res
+    // The grade of a homogeneous multivector, None for zero or mixed grades.
+    pub fn grade(self) -> Option<usize> {
+        let mut grades = (0..basis_count)
+            .filter(|&i| self[i] != 0.0)
+            .map(|i| basis_grades[i]);
+        let first = grades.next()?;
+        grades.all(|grade| grade == first).then_some(first)
     }
 
-    pub fn geometric_product(self: Self, b: Self) -> R300 {
-        let mut res = R300::zero();
-        let a = self;
-        res[0] = b[0] * a[0] + b[1] * a[1] + b[2] * a[2] + b[3] * a[3]
-            - b[4] * a[4]
-            - b[5] * a[5]
-            - b[6] * a[6]
-            - b[7] * a[7];
-        res[1] = b[1] * a[0] + b[0] * a[1] - b[4] * a[2] - b[5] * a[3] + b[2] * a[4] + b[3] * a[5]
-            - b[7] * a[6]
-            - b[6] * a[7];
-        res[2] = b[2] * a[0] + b[4] * a[1] + b[0] * a[2] - b[6] * a[3] - b[1] * a[4]
-            + b[7] * a[5]
-            + b[3] * a[6]
-            + b[5] * a[7];
-        res[3] = b[3] * a[0] + b[5] * a[1] + b[6] * a[2] + b[0] * a[3]
-            - b[7] * a[4]
-            - b[1] * a[5]
-            - b[2] * a[6]
-            - b[4] * a[7];
-        res[4] = b[4] * a[0] + b[2] * a[1] - b[1] * a[2] + b[7] * a[3] + b[0] * a[4] - b[6] * a[5]
-            + b[5] * a[6]
-            + b[3] * a[7];
-        res[5] = b[5] * a[0] + b[3] * a[1] - b[7] * a[2] - b[1] * a[3] + b[6] * a[4] + b[0] * a[5]
-            - b[4] * a[6]
-            - b[2] * a[7];
-        res[6] = b[6] * a[0] + b[7] * a[1] + b[3] * a[2] - b[2] * a[3] - b[5] * a[4]
-            + b[4] * a[5]
-            + b[0] * a[6]
-            + b[1] * a[7];
-        res[7] = b[7] * a[0] + b[6] * a[1] - b[5] * a[2] + b[4] * a[3] + b[3] * a[4] - b[2] * a[5]
-            + b[1] * a[6]
-            + b[0] * a[7];
-        res
+    // Angle between two blades of the same grade, cos(theta) = <A reverse(B)>_0 / (|A| |B|).
+    // For vectors this is the usual angle between them, for bivectors the angle between planes.
+    pub fn angle(self, b: Self) -> Option<f64> {
+        let grade = self.grade()?;
+        if b.grade()? != grade {
+            return None;
+        }
+        let cos = (self * b.Reverse())[0] / (self.norm() * b.norm());
+        Some(cos.clamp(-1.0, 1.0).acos())
+    }
+
+    // Splits A into its scalar part s and the rest B. When B is a blade, B*B is a scalar sigma
+    // and power series in B collapse to closed forms, which is what the functions below use.
+    fn split_scalar_blade(self) -> Option<(f64, R300, f64)> {
+        let scalar = self[0];
+        let blade = self - scalar;
+        match blade.grade() {
+            None if blade == R300::zero() => Some((scalar, blade, 0.0)),
+            Some(_) => Some((scalar, blade, (blade * blade)[0])),
+            None => None,
+        }
     }
 
-    pub fn add(self: R300, b: R300) -> R300 {
-        let mut res = R300::zero();
-        let a = self;
-        res[0] = a[0] + b[0];
-        res[1] = a[1] + b[1];
-        res[2] = a[2] + b[2];
-        res[3] = a[3] + b[3];
-        res[4] = a[4] + b[4];
-        res[5] = a[5] + b[5];
-        res[6] = a[6] + b[6];
-        res[7] = a[7] + b[7];
-        res
+    pub fn exp(self) -> Option<R300> {
+        let (s, blade, sigma) = self.split_scalar_blade()?;
+        Some(s.exp() * (even_series(sigma) + blade * odd_series(sigma)))
     }
 
-    pub fn sub(self: R300, b: R300) -> R300 {
-        let mut res = R300::zero();
-        let a = self;
-        res[0] = a[0] - b[0];
-        res[1] = a[1] - b[1];
-        res[2] = a[2] - b[2];
-        res[3] = a[3] - b[3];
-        res[4] = a[4] - b[4];
-        res[5] = a[5] - b[5];
-        res[6] = a[6] - b[6];
-        res[7] = a[7] - b[7];
-        res
+    pub fn cosh(self) -> Option<R300> {
+        let (s, blade, sigma) = self.split_scalar_blade()?;
+        let (cosh_b, sinh_b) = (R300::new(even_series(sigma), 0), blade * odd_series(sigma));
+        Some(s.cosh() * cosh_b + s.sinh() * sinh_b)
+    }
+
+    pub fn sinh(self) -> Option<R300> {
+        let (s, blade, sigma) = self.split_scalar_blade()?;
+        let (cosh_b, sinh_b) = (R300::new(even_series(sigma), 0), blade * odd_series(sigma));
+        Some(s.sinh() * cosh_b + s.cosh() * sinh_b)
+    }
+
+    pub fn cos(self) -> Option<R300> {
+        let (s, blade, sigma) = self.split_scalar_blade()?;
+        let (cos_b, sin_b) = (
+            R300::new(even_series(-sigma), 0),
+            blade * odd_series(-sigma),
+        );
+        Some(s.cos() * cos_b - s.sin() * sin_b)
+    }
+
+    pub fn sin(self) -> Option<R300> {
+        let (s, blade, sigma) = self.split_scalar_blade()?;
+        let (cos_b, sin_b) = (
+            R300::new(even_series(-sigma), 0),
+            blade * odd_series(-sigma),
+        );
+        Some(s.sin() * cos_b + s.cos() * sin_b)
+    }
+
+    // Principal logarithm of a positive scalar or of s + B with B*B < 0 (a rotor-like element),
+    // log(s + B) = ln|s + B| + B/|B| atan2(|B|, s).
+    pub fn log(self) -> Option<R300> {
+        let (s, blade, sigma) = self.split_scalar_blade()?;
+        if blade == R300::zero() {
+            return (s > 0.0).then(|| R300::new(s.ln(), 0));
+        }
+        if sigma >= 0.0 {
+            return None;
+        }
+        let theta = (-sigma).sqrt();
+        Some(s.hypot(theta).ln() + blade * (theta.atan2(s) / theta))
+    }
+
+    pub fn sqrt(self) -> Option<R300> {
+        if self.is_scalar() {
+            return (self[0] >= 0.0).then(|| R300::new(self[0].sqrt(), 0));
+        }
+        (self.log()? * 0.5).exp()
+    }
+
+    pub fn dot(self, b: Self) -> R300 {
+        Multivector::from(self).dot(&Multivector::from(b)).into()
+    }
+
+    pub fn wedge(self, b: Self) -> R300 {
+        Multivector::from(self).wedge(&Multivector::from(b)).into()
+    }
+
+    pub fn geometric_product(self, b: Self) -> R300 {
+        Multivector::from(self)
+            .geometric_product(&Multivector::from(b))
+            .into()
+    }
+
+    // In three dimensions A * conj(A) only has scalar and pseudoscalar parts, and those commute
+    // with everything, so inverting it reduces to inverting the complex-like number s + pI.
+    pub fn inverse(self) -> Option<R300> {
+        let center = self * self.Conjugate();
+        let (s, p) = (center[0], center[7]);
+        let denominator = s * s + p * p;
+        if denominator.abs() < TOLERANCE {
+            return None;
+        }
+        let center_inverse = R300::new(s / denominator, 0) + R300::new(-p / denominator, 7);
+        Some(self.Conjugate() * center_inverse)
+    }
+
+    // Integer powers by repeated squaring, negative powers go through the inverse.
+    pub fn pow(self, n: i32) -> Option<R300> {
+        let mut base = if n < 0 { self.inverse()? } else { self };
+        let mut exponent = n.unsigned_abs();
+        let mut res = R300::new(1.0, 0);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                res = res * base;
+            }
+            base = base * base;
+            exponent >>= 1;
+        }
+        Some(res)
+    }
+
+    pub fn divide(self: R300, b: R300) -> Option<R300> {
+        Some(self.geometric_product(b.inverse()?))
     }
+}
+
+// sum sigma^k / (2k)!, that is cosh(sqrt(sigma)) continued to cos(sqrt(-sigma)) for sigma < 0.
+fn even_series(sigma: f64) -> f64 {
+    if sigma > 0.0 {
+        sigma.sqrt().cosh()
+    } else {
+        (-sigma).sqrt().cos()
+    }
+}
 
-    pub fn divide(self: R300, b: R300) -> R300 {
-        self.geometric_product(b.Reverse() * (1.0 / self.norm() * b.norm()))
+// sum sigma^k / (2k + 1)!, that is sinh(x) / x with x = sqrt(sigma), or sin(x) / x for sigma < 0.
+fn odd_series(sigma: f64) -> f64 {
+    if sigma == 0.0 {
+        return 1.0;
+    }
+    if sigma > 0.0 {
+        let x = sigma.sqrt();
+        x.sinh() / x
+    } else {
+        let x = (-sigma).sqrt();
+        x.sin() / x
     }
 }
 
 impl Not for R300 {
     type Output = R300;
 
-    fn not(self: Self) -> R300 {
-        let mut res = R300::zero();
-        let a = self;
-        res[0] = -a[7];
-        res[1] = -a[6];
-        res[2] = a[5];
-        res[3] = -a[4];
-        res[4] = a[3];
-        res[5] = -a[2];
-        res[6] = a[1];
-        res[7] = a[0];
-        res
+    fn not(self) -> R300 {
+        self.Dual()
     }
 }
 
@@ -436,7 +485,17 @@ impl Add for R300 {
     type Output = R300;
 
     fn add(self: R300, b: R300) -> R300 {
-        self.add(b)
+        let mut res = R300::zero();
+        let a = self;
+        res[0] = a[0] + b[0];
+        res[1] = a[1] + b[1];
+        res[2] = a[2] + b[2];
+        res[3] = a[3] + b[3];
+        res[4] = a[4] + b[4];
+        res[5] = a[5] + b[5];
+        res[6] = a[6] + b[6];
+        res[7] = a[7] + b[7];
+        res
     }
 }
 
@@ -446,7 +505,17 @@ impl Sub for R300 {
     type Output = R300;
 
     fn sub(self: R300, b: R300) -> R300 {
-        self.sub(b)
+        let mut res = R300::zero();
+        let a = self;
+        res[0] = a[0] - b[0];
+        res[1] = a[1] - b[1];
+        res[2] = a[2] - b[2];
+        res[3] = a[3] - b[3];
+        res[4] = a[4] - b[4];
+        res[5] = a[5] - b[5];
+        res[6] = a[6] - b[6];
+        res[7] = a[7] - b[7];
+        res
     }
 }
 
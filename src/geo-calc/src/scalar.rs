@@ -0,0 +1,78 @@
+use num_rational::BigRational;
+use num_traits::{One, ToPrimitive, Zero};
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// What a multivector coefficient needs to support for the products in `Multivector`.
+pub trait Scalar:
+    Clone
+    + PartialEq
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Neg<Output = Self>
+{
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn is_zero(&self) -> bool;
+    fn from_integer(value: i64) -> Self;
+    /// The integer this scalar is exactly equal to, if any.
+    fn to_integer(&self) -> Option<i64>;
+    /// 1 / self, None when it is zero or has no reciprocal in this scalar type.
+    fn recip(&self) -> Option<Self>;
+}
+
+impl Scalar for f64 {
+    fn zero() -> Self {
+        0.0
+    }
+
+    fn one() -> Self {
+        1.0
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == 0.0
+    }
+
+    fn from_integer(value: i64) -> Self {
+        value as f64
+    }
+
+    fn to_integer(&self) -> Option<i64> {
+        (self.fract() == 0.0 && self.abs() <= i64::MAX as f64).then_some(*self as i64)
+    }
+
+    fn recip(&self) -> Option<Self> {
+        (*self != 0.0).then(|| 1.0 / self)
+    }
+}
+
+impl Scalar for BigRational {
+    fn zero() -> Self {
+        Zero::zero()
+    }
+
+    fn one() -> Self {
+        One::one()
+    }
+
+    fn is_zero(&self) -> bool {
+        Zero::is_zero(self)
+    }
+
+    fn from_integer(value: i64) -> Self {
+        BigRational::from_integer(value.into())
+    }
+
+    fn to_integer(&self) -> Option<i64> {
+        if self.is_integer() {
+            self.numer().to_i64()
+        } else {
+            None
+        }
+    }
+
+    fn recip(&self) -> Option<Self> {
+        (!Zero::is_zero(self)).then(|| BigRational::recip(self))
+    }
+}
@@ -0,0 +1,271 @@
+use crate::decompose::decompose;
+use crate::primitive::{
+    factor, factor_trivector, point, signed_volume, Handedness, Point, Primitive,
+};
+use crate::r300::R300;
+use crate::{Evaluated, Kind};
+use latex_expr_parser::TokenKind;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+
+/// How bivectors are drawn. Both have the bivector's area and orientation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+#[cfg_attr(test, ts(export_to = "../../components/generated/"))]
+#[serde(rename_all = "snake_case")]
+pub enum BivectorStyle {
+    /// Spanned by two orthogonal sides, which says more about how a wedge is built.
+    #[default]
+    Parallelogram,
+    /// A disc with a circulation arrow, which does not suggest a particular pair of sides.
+    Disc,
+}
+
+/// How trivectors are drawn. Both have the trivector's volume and handedness.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+#[cfg_attr(test, ts(export_to = "../../components/generated/"))]
+#[serde(rename_all = "snake_case")]
+pub enum TrivectorStyle {
+    /// Spanned by three edges, the factors of a wedge when the value is one.
+    #[default]
+    Parallelepiped,
+    Sphere,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+#[cfg_attr(test, ts(export_to = "../../components/generated/"))]
+#[serde(default)]
+pub struct SceneOptions {
+    pub bivectors: BivectorStyle,
+    pub trivectors: TrivectorStyle,
+}
+
+/// Text placed at a point, e.g. the name of a vector at its tip.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+#[cfg_attr(test, ts(export_to = "../../components/generated/"))]
+pub struct Label {
+    pub position: Point,
+    pub text: String,
+}
+
+/// One named value and the shapes that draw it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+#[cfg_attr(test, ts(export_to = "../../components/generated/"))]
+pub struct Item {
+    pub name: String,
+    /// Absent for values with several grades.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub grade: Option<usize>,
+    /// A CSS colour, see `color`.
+    pub color: String,
+    pub shapes: Vec<Primitive>,
+    pub label: Label,
+    /// The signed volume of a trivector, see `primitive::signed_volume`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub volume: Option<f64>,
+    /// The source span of the node this item draws, so clicking it can select the node.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub start: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub end: Option<usize>,
+}
+
+/// Everything the 3D view or an export draws, so that they all show the same geometry.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+#[cfg_attr(test, ts(export_to = "../../components/generated/"))]
+pub struct Scene {
+    pub items: Vec<Item>,
+}
+
+impl Scene {
+    /// The scene of `values`, sorted by name so it does not change between calls.
+    pub fn new(values: &BTreeMap<String, R300>, options: &SceneOptions) -> Self {
+        Scene {
+            items: values
+                .iter()
+                .map(|(name, value)| describe(name, *value, options))
+                .collect(),
+        }
+    }
+
+    /// The scene of every node of `root` that has a value, in evaluation order. Identifiers are
+    /// labelled with their name and other nodes with their value.
+    pub fn from_evaluated(root: &Evaluated, options: &SceneOptions) -> Self {
+        Scene {
+            items: root
+                .post_order()
+                .into_iter()
+                .filter_map(|node| {
+                    let value = node.meta.value?;
+                    let name = match node.kind() {
+                        Kind::Identifier(name) | Kind::Constant(name) => name.to_string(),
+                        _ => value.to_latex(),
+                    };
+                    let mut item = describe(&name, value, options);
+                    let edges = wedge_factors(node).unwrap_or_default();
+                    if let (&[u, v, w], Some(3), TrivectorStyle::Parallelepiped) =
+                        (&edges[..], value.grade(), options.trivectors)
+                    {
+                        item.shapes = vec![parallelepiped(u, v, w)];
+                        item.label.position = point(u + v + w);
+                    }
+                    item.start = Some(node.start());
+                    item.end = Some(node.end());
+                    Some(item)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The colour of a grade, or of a value with several grades for None.
+pub fn color(grade: Option<usize>) -> &'static str {
+    match grade {
+        Some(0) => "#ffffff",
+        Some(1) => "#00ffff",
+        Some(2) => "#ff9800",
+        Some(3) => "#e040fb",
+        _ => "#8bc34a",
+    }
+}
+
+/// How `value` is drawn: vectors as arrows, bivectors as oriented parallelograms or discs,
+/// trivectors as oriented cubes. Mixtures of grades show each of their grade parts as
+/// [`decompose`] splits them, with a spinor's arc, and scalars only get their label.
+pub fn describe(name: &str, value: R300, options: &SceneOptions) -> Item {
+    let grade = value.grade();
+    let origin = [0.0; 3];
+    let (shapes, anchor) = match grade {
+        Some(1) => (
+            vec![Primitive::Arrow {
+                origin,
+                vector: point(value),
+            }],
+            point(value),
+        ),
+        Some(2) => bivector(value, options.bivectors),
+        Some(3) => trivector(value, options.trivectors),
+        None => {
+            let decomposition = decompose(name, value, options);
+            // A spinor is labelled halfway along its arc.
+            let anchor = match decomposition.shapes.first() {
+                Some(Primitive::Arc { u, v, angle, .. }) => {
+                    let (cos, sin) = ((0.5 * angle).cos(), (0.5 * angle).sin());
+                    [0, 1, 2].map(|i| cos * u[i] + sin * v[i])
+                }
+                _ => origin,
+            };
+            (decomposition.all_shapes(), anchor)
+        }
+        _ => (vec![], origin),
+    };
+    Item {
+        name: name.to_string(),
+        grade,
+        color: color(grade).to_string(),
+        shapes,
+        label: Label {
+            position: anchor,
+            text: name.to_string(),
+        },
+        volume: signed_volume(value),
+        start: None,
+        end: None,
+    }
+}
+
+/// The shapes of a bivector and where its label goes.
+fn bivector(value: R300, style: BivectorStyle) -> (Vec<Primitive>, Point) {
+    let Some((u, v)) = factor(value) else {
+        return (vec![], [0.0; 3]);
+    };
+    // Sides of equal length, the area being |B|.
+    let side = value.norm().sqrt();
+    let (u, v) = (side * u, (1.0 / side) * v);
+    match style {
+        BivectorStyle::Parallelogram => (
+            vec![Primitive::Parallelogram {
+                origin: [0.0; 3],
+                u: point(u),
+                v: point(v),
+            }],
+            point(0.5 * (u + v)),
+        ),
+        BivectorStyle::Disc => {
+            let radius = (value.norm() / PI).sqrt();
+            (
+                vec![Primitive::Disc {
+                    center: [0.0; 3],
+                    normal: cross(point(u), point(v)),
+                    radius,
+                }],
+                point((radius / side) * u),
+            )
+        }
+    }
+}
+
+/// A cube or ball of volume `|V|`, right handed for a positive multiple of e123.
+fn trivector(value: R300, style: TrivectorStyle) -> (Vec<Primitive>, Point) {
+    let (Some(volume), Some((u, v, w))) = (signed_volume(value), factor_trivector(value)) else {
+        return (vec![], [0.0; 3]);
+    };
+    match style {
+        TrivectorStyle::Parallelepiped => (vec![parallelepiped(u, v, w)], point(u + v + w)),
+        TrivectorStyle::Sphere => {
+            let radius = (3.0 * volume.abs() / (4.0 * PI)).cbrt();
+            (
+                vec![Primitive::Sphere {
+                    center: [0.0; 3],
+                    radius,
+                    handedness: Handedness::of(volume),
+                }],
+                [0.0, 0.0, radius],
+            )
+        }
+    }
+}
+
+fn parallelepiped(u: R300, v: R300, w: R300) -> Primitive {
+    Primitive::Parallelepiped {
+        origin: [0.0; 3],
+        u: point(u),
+        v: point(v),
+        w: point(w),
+    }
+}
+
+/// The vectors a node wedges together, e.g. `a`, `b` and `c` for `a \wedge b \wedge c`, so
+/// its volume is drawn on the edges the user chose.
+fn wedge_factors(node: &Evaluated) -> Option<Vec<R300>> {
+    match node.kind() {
+        Kind::BinaryOp(TokenKind::Wedge) => {
+            let mut factors = wedge_factors(&node.children[0])?;
+            factors.extend(wedge_factors(&node.children[1])?);
+            Some(factors)
+        }
+        _ => node
+            .meta
+            .value
+            .filter(|value| value.is_vector())
+            .map(|value| vec![value]),
+    }
+}
+
+fn cross(a: Point, b: Point) -> Point {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
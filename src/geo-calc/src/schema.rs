@@ -0,0 +1,410 @@
+//! The JSON format of evaluated expressions, as returned by `calculate_expression`.
+//!
+//! A document is `{"version": 2, "algebra": "R300", "root": <node>}`. Every node has its source
+//! span, its `value` as named blade components, a `kind` telling which fields follow, its
+//! operands as `children`, and optional `errors`, `derivation` and `jacobian`:
+//!
+//! ```json
+//! {"start": 0, "end": 4, "value": {"scalar": 0, "e1": 1, ...}, "kind": "BinaryOp",
+//!  "op": "Plus", "children": [{...}, {...}]}
+//! ```
+//!
+//! `value` always uses the standard blades. A document written under another convention lists
+//! its blades as `basis`, e.g. with `e31` instead of `e13`, and gives every node the
+//! `components` of its value in them.
+//!
+//! Version 1 is the unversioned output from before the format was documented, with `value` as
+//! `{"mvec": [...]}` and the kind as an externally tagged `type` holding the operands.
+//! `from_json` reads both.
+//!
+//! `schema/evaluated-expression.schema.json` and the TypeScript definitions in
+//! `src/components/generated` are generated from these types. `cargo test` fails when they are
+//! out of date, `UPDATE_GENERATED=1 cargo test` rewrites them.
+
+use crate::convention::Convention;
+use crate::derivation::Derivation;
+use crate::error::EvalError;
+use crate::r300::R300;
+use crate::sensitivity::PartialDerivative;
+use crate::{Evaluated, Kind};
+use latex_expr_parser::TokenKind;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+pub const SCHEMA_VERSION: u32 = 2;
+pub const ALGEBRA: &str = "R300";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema, ts_rs::TS))]
+#[cfg_attr(test, ts(export_to = "../../components/generated/"))]
+pub struct EvaluatedExpression {
+    pub version: u32,
+    /// The algebra the values live in, `R300` is the geometric algebra of 3D Euclidean space.
+    pub algebra: String,
+    /// The blades of the convention the `components` of every node are written in, e.g. `e31`
+    /// for the cyclic one. Absent for the standard blades of `value`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub basis: Option<Vec<String>>,
+    pub root: Node,
+}
+
+impl EvaluatedExpression {
+    pub fn new(root: Node) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            algebra: ALGEBRA.to_string(),
+            basis: None,
+            root,
+        }
+    }
+
+    /// The document of `root` with every value also written in `convention`'s blades, unless
+    /// those are the standard ones.
+    pub fn with_convention(root: &Evaluated, convention: &Convention) -> Self {
+        let mut document = Self::from(root);
+        if *convention != Convention::standard() {
+            document.basis = Some(convention.names());
+            document.root.add_components(convention);
+        }
+        document
+    }
+}
+
+/// A multivector by the coefficients of its basis blades.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema, ts_rs::TS))]
+#[cfg_attr(test, ts(export_to = "../../components/generated/"))]
+pub struct Blades {
+    pub scalar: f64,
+    pub e1: f64,
+    pub e2: f64,
+    pub e3: f64,
+    pub e12: f64,
+    pub e13: f64,
+    pub e23: f64,
+    pub e123: f64,
+}
+
+impl From<R300> for Blades {
+    fn from(value: R300) -> Self {
+        Self {
+            scalar: value[0],
+            e1: value[1],
+            e2: value[2],
+            e3: value[3],
+            e12: value[4],
+            e13: value[5],
+            e23: value[6],
+            e123: value[7],
+        }
+    }
+}
+
+impl From<Blades> for R300 {
+    fn from(blades: Blades) -> Self {
+        let components = [
+            blades.scalar,
+            blades.e1,
+            blades.e2,
+            blades.e3,
+            blades.e12,
+            blades.e13,
+            blades.e23,
+            blades.e123,
+        ];
+        components
+            .iter()
+            .enumerate()
+            .fold(R300::zero(), |res, (i, &coefficient)| {
+                res + R300::new(coefficient, i)
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema, ts_rs::TS))]
+#[cfg_attr(test, ts(export_to = "../../components/generated/"))]
+pub struct Node {
+    pub start: usize,
+    pub end: usize,
+    /// Always in the standard blades, whatever the document's `basis`.
+    pub value: Blades,
+    /// `value` in the document's `basis`, present when it has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub components: Option<Vec<f64>>,
+    #[serde(flatten)]
+    pub kind: NodeKind,
+    /// The operands, left to right, e.g. the base of a power or the arguments of a call.
+    pub children: Vec<Node>,
+    /// Present when `value` has a single grade.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub grade: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub description: Option<String>,
+    /// Why this node could not be evaluated, in which case `value` is zero.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub errors: Option<Vec<NodeError>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub derivation: Option<Derivation>,
+    /// The derivatives of `value` with respect to each component of the inputs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub jacobian: Option<Vec<PartialDerivative>>,
+}
+
+/// What a node computes, the serialized form of `Kind`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema, ts_rs::TS))]
+#[cfg_attr(test, ts(export_to = "../../components/generated/"))]
+#[serde(tag = "kind")]
+pub enum NodeKind {
+    Identifier { name: String },
+    Constant { name: String },
+    Int,
+    BinaryOp { op: Operator },
+    UnaryOp { op: Operator },
+    Reverse,
+    Dual,
+    Conjugate,
+    Involute,
+    Inverse,
+    Power { exponent: i32 },
+    Magnitude,
+    Angle,
+    Area,
+    FunctionCall { name: String },
+    Equation { holds: bool },
+}
+
+impl From<Kind<'_>> for NodeKind {
+    fn from(kind: Kind) -> Self {
+        match kind {
+            Kind::Identifier(name) => NodeKind::Identifier {
+                name: name.to_string(),
+            },
+            Kind::Constant(name) => NodeKind::Constant {
+                name: name.to_string(),
+            },
+            Kind::Int => NodeKind::Int,
+            Kind::BinaryOp(op) => NodeKind::BinaryOp { op: op.into() },
+            Kind::UnaryOp(op) => NodeKind::UnaryOp { op: op.into() },
+            Kind::Reverse => NodeKind::Reverse,
+            Kind::Dual => NodeKind::Dual,
+            Kind::Conjugate => NodeKind::Conjugate,
+            Kind::Involute => NodeKind::Involute,
+            Kind::Inverse => NodeKind::Inverse,
+            Kind::Power(exponent) => NodeKind::Power { exponent },
+            Kind::Magnitude => NodeKind::Magnitude,
+            Kind::Angle => NodeKind::Angle,
+            Kind::Area => NodeKind::Area,
+            Kind::FunctionCall(name) => NodeKind::FunctionCall {
+                name: name.to_string(),
+            },
+            Kind::Equation { holds } => NodeKind::Equation { holds },
+        }
+    }
+}
+
+/// The operator of a `BinaryOp` or `UnaryOp` node, named by the parser's token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema, ts_rs::TS))]
+#[cfg_attr(test, ts(export_to = "../../components/generated/"))]
+pub enum Operator {
+    Plus,
+    Minus,
+    Multiply,
+    Dot,
+    Wedge,
+    Frac,
+    Caret,
+    Equals,
+    Dagger,
+    Tilde,
+    Star,
+    Bar,
+    Hat,
+    Pipe,
+    DoublePipe,
+    LParen,
+    RParen,
+}
+
+impl From<TokenKind> for Operator {
+    fn from(token: TokenKind) -> Self {
+        match token {
+            TokenKind::Plus => Operator::Plus,
+            TokenKind::Minus => Operator::Minus,
+            TokenKind::Multiply => Operator::Multiply,
+            TokenKind::Dot => Operator::Dot,
+            TokenKind::Wedge => Operator::Wedge,
+            TokenKind::Frac => Operator::Frac,
+            TokenKind::Caret => Operator::Caret,
+            TokenKind::Equals => Operator::Equals,
+            TokenKind::Dagger => Operator::Dagger,
+            TokenKind::Tilde => Operator::Tilde,
+            TokenKind::Star => Operator::Star,
+            TokenKind::Bar => Operator::Bar,
+            TokenKind::Hat => Operator::Hat,
+            TokenKind::Pipe => Operator::Pipe,
+            TokenKind::DoublePipe => Operator::DoublePipe,
+            TokenKind::LParen => Operator::LParen,
+            TokenKind::RParen => Operator::RParen,
+        }
+    }
+}
+
+/// An evaluation error of a node, see `EvalError`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema, ts_rs::TS))]
+#[cfg_attr(test, ts(export_to = "../../components/generated/"))]
+pub struct NodeError {
+    pub message: String,
+    /// The span to highlight, which can be a part of the node such as an exponent.
+    pub start: usize,
+    pub end: usize,
+}
+
+impl From<&EvalError> for NodeError {
+    fn from(error: &EvalError) -> Self {
+        let (start, end) = error.span();
+        Self {
+            message: error.to_string(),
+            start,
+            end,
+        }
+    }
+}
+
+impl Node {
+    fn add_components(&mut self, convention: &Convention) {
+        self.components = Some(convention.components(&self.value.into()));
+        for child in self.children.iter_mut() {
+            child.add_components(convention);
+        }
+    }
+}
+
+impl From<&Evaluated<'_>> for Node {
+    fn from(node: &Evaluated) -> Self {
+        Node {
+            start: node.start(),
+            end: node.end(),
+            value: node.value().into(),
+            components: None,
+            kind: node.kind().into(),
+            children: node.children.iter().map(Node::from).collect(),
+            grade: node.meta.grade,
+            description: node.meta.description.clone(),
+            errors: (!node.meta.errors.is_empty())
+                .then(|| node.meta.errors.iter().map(NodeError::from).collect()),
+            derivation: node.meta.derivation.clone(),
+            jacobian: node.meta.jacobian.clone(),
+        }
+    }
+}
+
+impl From<&Evaluated<'_>> for EvaluatedExpression {
+    fn from(node: &Evaluated) -> Self {
+        EvaluatedExpression::new(node.into())
+    }
+}
+
+#[derive(Debug)]
+pub enum SchemaError {
+    Json(serde_json::Error),
+    UnsupportedVersion(u64),
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SchemaError::Json(e) => write!(f, "Invalid evaluated expression: {}", e),
+            SchemaError::UnsupportedVersion(version) => write!(
+                f,
+                "Unsupported format version {}, expected at most {}",
+                version, SCHEMA_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+impl From<serde_json::Error> for SchemaError {
+    fn from(e: serde_json::Error) -> Self {
+        SchemaError::Json(e)
+    }
+}
+
+/// Reads an evaluated expression written by any version of the format.
+pub fn from_json(json: &str) -> Result<EvaluatedExpression, SchemaError> {
+    let document: Value = serde_json::from_str(json)?;
+    match document.get("version").map(Value::as_u64) {
+        None => {
+            let mut root = document;
+            upgrade_v1(&mut root)?;
+            Ok(EvaluatedExpression::new(serde_json::from_value(root)?))
+        }
+        Some(Some(2)) => Ok(serde_json::from_value(document)?),
+        Some(Some(version)) => Err(SchemaError::UnsupportedVersion(version)),
+        Some(None) => Err(SchemaError::Json(serde::de::Error::custom(
+            "version must be an integer",
+        ))),
+    }
+}
+
+/// The fields version 1 kept the operands in, in the order they become `children`.
+const OPERAND_FIELDS: [&str; 5] = ["left", "right", "operand", "base", "args"];
+
+/// Rewrites a node of the unversioned format, which serialized the evaluated tree as derived:
+/// `value` as `{"mvec": [...]}` and the kind as an externally tagged `type` whose variants
+/// hold the operands. The result is a version 2 node.
+fn upgrade_v1(node: &mut Value) -> Result<(), SchemaError> {
+    let invalid = |reason: &str| SchemaError::Json(serde::de::Error::custom(reason.to_string()));
+    let fields = node
+        .as_object_mut()
+        .ok_or_else(|| invalid("a node must be an object"))?;
+    let value: R300 = serde_json::from_value(fields.remove("value").unwrap_or_default())?;
+    let (kind, operands) = match fields.remove("type") {
+        Some(Value::String(kind)) => (kind, serde_json::Map::new()),
+        Some(Value::Object(tagged)) if tagged.len() == 1 => {
+            let (kind, operands) = tagged.into_iter().next().unwrap();
+            match operands {
+                Value::Object(operands) => (kind, operands),
+                _ => return Err(invalid("the fields of a node type must be an object")),
+            }
+        }
+        _ => return Err(invalid("a node must have a type")),
+    };
+    let kind = kind.trim_end_matches("Node").to_string();
+    if kind == "Constant" {
+        fields.insert("description".to_string(), "reserved symbol".into());
+    }
+    fields.extend(operands);
+    let mut children = Vec::new();
+    for field in OPERAND_FIELDS {
+        match fields.remove(field) {
+            Some(Value::Array(args)) => children.extend(args),
+            Some(child) => children.push(child),
+            None => {}
+        }
+    }
+    children.iter_mut().try_for_each(upgrade_v1)?;
+    fields.insert("children".to_string(), Value::Array(children));
+    fields.insert("kind".to_string(), kind.into());
+    fields.insert(
+        "value".to_string(),
+        serde_json::to_value(Blades::from(value))?,
+    );
+    if let Some(grade) = value.grade() {
+        fields.insert("grade".to_string(), grade.into());
+    }
+    Ok(())
+}
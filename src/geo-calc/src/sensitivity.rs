@@ -0,0 +1,453 @@
+use crate::inference::Grades;
+use crate::multivector::Multivector;
+use crate::r300::{basis, basis_count, basis_grades, R300};
+use crate::scalar::Scalar;
+use crate::schema::Blades;
+use crate::{arguments, Evaluated, Kind};
+use latex_expr_parser::TokenKind;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// Below this size of z², relative to the rest, the builtins sum their power series in z²
+/// instead of taking its square root, which has no derivative at zero.
+const SERIES: f64 = 1e-3;
+
+/// A number with its derivatives along several directions at once, one per input component.
+/// Missing trailing derivatives are zero, so constants can leave `tangent` empty.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DualNumber {
+    pub value: f64,
+    pub tangent: Vec<f64>,
+}
+
+impl DualNumber {
+    pub fn constant(value: f64) -> Self {
+        Self {
+            value,
+            tangent: vec![],
+        }
+    }
+
+    /// `f(self)` given `f(value)` and `f'(value)`.
+    fn chain(&self, value: f64, derivative: f64) -> Self {
+        Self {
+            value,
+            tangent: self.tangent.iter().map(|t| derivative * t).collect(),
+        }
+    }
+
+    pub fn sqrt(&self) -> Self {
+        let root = self.value.sqrt();
+        // The norm of zero is not differentiable, its derivative is taken to be zero.
+        let derivative = if root == 0.0 { 0.0 } else { 0.5 / root };
+        self.chain(root, derivative)
+    }
+
+    pub fn exp(&self) -> Self {
+        let exp = self.value.exp();
+        self.chain(exp, exp)
+    }
+
+    pub fn ln(&self) -> Self {
+        self.chain(self.value.ln(), 1.0 / self.value)
+    }
+
+    pub fn sin(&self) -> Self {
+        self.chain(self.value.sin(), self.value.cos())
+    }
+
+    pub fn cos(&self) -> Self {
+        self.chain(self.value.cos(), -self.value.sin())
+    }
+
+    /// The angle of the point `(x, self)` like `f64::atan2`, with no derivative at the origin.
+    pub fn atan2(&self, x: &Self) -> Self {
+        let (y0, x0) = (self.value, x.value);
+        let squared = x0 * x0 + y0 * y0;
+        Self {
+            value: y0.atan2(x0),
+            tangent: if squared == 0.0 {
+                vec![]
+            } else {
+                self.combine(x0 / squared, x, -y0 / squared)
+            },
+        }
+    }
+
+    /// The arccosine clamped to [-1, 1] like `R300::angle`, flat outside of it.
+    pub fn acos(&self) -> Self {
+        let x = self.value.clamp(-1.0, 1.0);
+        let derivative = if x.abs() < 1.0 {
+            -1.0 / (1.0 - x * x).sqrt()
+        } else {
+            0.0
+        };
+        self.chain(x.acos(), derivative)
+    }
+
+    /// The tangent `a t + b u` of `t` and `u` the tangents of `self` and `other`.
+    fn combine(&self, a: f64, other: &Self, b: f64) -> Vec<f64> {
+        let len = self.tangent.len().max(other.tangent.len());
+        let at = |tangent: &[f64], i: usize| tangent.get(i).copied().unwrap_or(0.0);
+        (0..len)
+            .map(|i| a * at(&self.tangent, i) + b * at(&other.tangent, i))
+            .collect()
+    }
+}
+
+impl Add for DualNumber {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            value: self.value + other.value,
+            tangent: self.combine(1.0, &other, 1.0),
+        }
+    }
+}
+
+impl Sub for DualNumber {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self {
+            value: self.value - other.value,
+            tangent: self.combine(1.0, &other, -1.0),
+        }
+    }
+}
+
+impl Mul for DualNumber {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let (a, b) = (self.value, other.value);
+        Self {
+            value: a * b,
+            tangent: self.combine(b, &other, a),
+        }
+    }
+}
+
+impl Neg for DualNumber {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.chain(-self.value, -1.0)
+    }
+}
+
+impl Scalar for DualNumber {
+    fn zero() -> Self {
+        Self::constant(0.0)
+    }
+
+    fn one() -> Self {
+        Self::constant(1.0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.value == 0.0 && self.tangent.iter().all(|t| *t == 0.0)
+    }
+
+    fn from_integer(value: i64) -> Self {
+        Self::constant(value as f64)
+    }
+
+    fn to_integer(&self) -> Option<i64> {
+        self.tangent
+            .iter()
+            .all(|t| *t == 0.0)
+            .then(|| Scalar::to_integer(&self.value))
+            .flatten()
+    }
+
+    fn recip(&self) -> Option<Self> {
+        let x = self.value;
+        (x != 0.0).then(|| self.chain(1.0 / x, -1.0 / (x * x)))
+    }
+}
+
+/// The derivative of a node's value with respect to one component of one input.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema, ts_rs::TS))]
+#[cfg_attr(test, ts(export_to = "../../components/generated/"))]
+pub struct PartialDerivative {
+    pub input: String,
+    /// The component of the input, named like in `basis`, e.g. `e1`.
+    pub blade: String,
+    pub derivative: Blades,
+}
+
+/// One direction of differentiation: a blade component of an input identifier.
+struct Direction<'a> {
+    input: &'a str,
+    blade: usize,
+}
+
+/// Fills in the Jacobian of every node with respect to the components of `inputs`, in the grades
+/// each input has (a zero input counts as a vector). Nodes without a value get none.
+///
+/// Everything is differentiated exactly with `DualNumber` coefficients, the algebra through the
+/// generic products and the builtin functions through their closed forms below.
+pub fn differentiate(root: &mut Evaluated, vars: &HashMap<String, R300>, inputs: &[String]) {
+    let mut directions = Vec::new();
+    let mut seeds = HashMap::new();
+    for input in inputs {
+        let Some(&value) = vars.get(input) else {
+            continue;
+        };
+        let grades = match Grades::of(value) {
+            grades if grades.is_empty() => Grades::VECTOR,
+            grades => grades,
+        };
+        let mut seed = Multivector::from_fn(|i| DualNumber::constant(value[i]));
+        for blade in (0..basis_count).filter(|&i| grades.contains(basis_grades[i])) {
+            let mut tangent = vec![0.0; directions.len() + 1];
+            tangent[directions.len()] = 1.0;
+            seed.mvec[blade].tangent = tangent;
+            directions.push(Direction { input, blade });
+        }
+        seeds.insert(input.as_str(), seed);
+    }
+    forward(root, &seeds, &directions);
+}
+
+fn forward(
+    node: &mut Evaluated,
+    seeds: &HashMap<&str, Multivector<DualNumber>>,
+    directions: &[Direction],
+) -> Option<Multivector<DualNumber>> {
+    let children = node
+        .children
+        .iter_mut()
+        .map(|child| forward(child, seeds, directions))
+        .collect::<Vec<_>>();
+    node.meta.value?;
+    let children = children.into_iter().collect::<Option<Vec<_>>>()?;
+    let constant = |value: R300| Multivector::from_fn(|i| DualNumber::constant(value[i]));
+    let scalar = |value: DualNumber| Multivector::new(value, 0);
+    let value = match node.kind() {
+        Kind::Identifier(name) => seeds
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| constant(node.value())),
+        Kind::Constant(_) | Kind::Int => constant(node.value()),
+        Kind::Equation { .. } => children[0].clone() - children[1].clone(),
+        Kind::BinaryOp(op) => {
+            let (l, r) = (children[0].clone(), children[1].clone());
+            match op {
+                TokenKind::Plus => l + r,
+                TokenKind::Minus => l - r,
+                TokenKind::Multiply => l * r,
+                TokenKind::Dot => l | r,
+                TokenKind::Wedge => l ^ r,
+                TokenKind::Frac => l * r.inverse()?,
+                _ => return None,
+            }
+        }
+        Kind::UnaryOp(TokenKind::Plus) => children[0].clone(),
+        Kind::UnaryOp(TokenKind::Minus) => -children[0].clone(),
+        Kind::Reverse => children[0].Reverse(),
+        Kind::Dual => children[0].Dual(),
+        Kind::Conjugate => children[0].Conjugate(),
+        Kind::Involute => children[0].Involute(),
+        Kind::Inverse => children[0].inverse()?,
+        Kind::Power(n) => children[0].pow(n as i64)?,
+        Kind::Magnitude | Kind::Area => scalar(norm(&children[0])),
+        Kind::Angle => {
+            let (a, b) = (&children[0], &children[1]);
+            let cos = a.geometric_product(&b.Reverse())[0].clone() * (norm(a) * norm(b)).recip()?;
+            scalar(cos.acos())
+        }
+        Kind::FunctionCall(name) => builtin(name, arguments(node.node, &children))?,
+        // Other operators, and kinds added later, are not differentiated.
+        _ => return None,
+    };
+    node.meta.jacobian = Some(
+        directions
+            .iter()
+            .enumerate()
+            .map(|(t, direction)| PartialDerivative {
+                input: direction.input.to_string(),
+                blade: basis[direction.blade].to_string(),
+                derivative: R300::from(Multivector::from_fn(|i| {
+                    value[i].tangent.get(t).copied().unwrap_or(0.0)
+                }))
+                .into(),
+            })
+            .collect(),
+    );
+    Some(value)
+}
+
+fn norm(value: &Multivector<DualNumber>) -> DualNumber {
+    value
+        .mvec
+        .iter()
+        .fold(DualNumber::zero(), |sum, c| sum + c.clone() * c.clone())
+        .sqrt()
+}
+
+/// A builtin applied to `args`, the same functions as `functions::BUILTINS`.
+fn builtin(name: &str, args: &[Multivector<DualNumber>]) -> Option<Multivector<DualNumber>> {
+    match (name, args) {
+        ("exp", [a]) => Some(a.exp()),
+        ("sin", [a]) => Some(a.sin()),
+        ("cos", [a]) => Some(a.cos()),
+        ("sinh", [a]) => Some(a.sinh()),
+        ("cosh", [a]) => Some(a.cosh()),
+        ("log" | "ln", [a]) => a.log(),
+        ("sqrt", [a]) => a.sqrt(),
+        ("atan2", [y, x]) => Some(Multivector::new(y[0].atan2(&x[0]), 0)),
+        _ => None,
+    }
+}
+
+/// The closed forms of the builtins, which unlike `R300`'s hold for every multivector so that
+/// they can be differentiated in directions of any grade.
+///
+/// A multivector is `α + z` with `α` its scalar and e123 parts, which commute with everything
+/// and multiply like complex numbers, and `z` its vector and bivector parts. `z² = λ` is again
+/// central, so `e^z = cosh √λ + z sinh √λ / √λ` and the other functions follow the same way.
+impl Multivector<DualNumber> {
+    pub fn exp(&self) -> Self {
+        let (alpha, z, lambda) = self.split();
+        let (even, odd) = series(&lambda);
+        central_exp(&alpha) * (even + z * odd)
+    }
+
+    pub fn cosh(&self) -> Self {
+        let (alpha, z, lambda) = self.split();
+        let ((cosh, sinh), (even, odd)) = (central_cosh_sinh(&alpha), series(&lambda));
+        cosh * even + sinh * z * odd
+    }
+
+    pub fn sinh(&self) -> Self {
+        let (alpha, z, lambda) = self.split();
+        let ((cosh, sinh), (even, odd)) = (central_cosh_sinh(&alpha), series(&lambda));
+        sinh * even + cosh * z * odd
+    }
+
+    /// `cos α cos z - sin α sin z` with `cos z = cosh √-λ`, `cos α = cosh(e123 α)` and
+    /// `sin α = -e123 sinh(e123 α)`.
+    pub fn cos(&self) -> Self {
+        let (alpha, z, lambda) = self.split();
+        let ((cos, sin), (even, odd)) = (central_cos_sin(&alpha), series(&-lambda));
+        cos * even - sin * z * odd
+    }
+
+    pub fn sin(&self) -> Self {
+        let (alpha, z, lambda) = self.split();
+        let ((cos, sin), (even, odd)) = (central_cos_sin(&alpha), series(&-lambda));
+        sin * even + cos * z * odd
+    }
+
+    /// `α + z = (α + √λ) P + (α - √λ) Q` for the idempotents `P, Q = (1 ± z / √λ) / 2`, so the
+    /// logarithm is `(log(α + √λ) + log(α - √λ)) / 2 + z (log(α + √λ) - log(α - √λ)) / 2√λ`.
+    /// Near `λ = 0` the second term is `z / α` times `Σ (λ / α²)^k / (2k + 1)`. None at zero.
+    pub fn log(&self) -> Option<Self> {
+        let (alpha, z, lambda) = self.split();
+        let alpha_inverse = alpha.inverse()?;
+        let ratio = lambda.clone() * alpha_inverse.clone() * alpha_inverse.clone();
+        if size(&ratio) < SERIES {
+            let mut sum = Self::zero();
+            let mut power = Self::new(DualNumber::one(), 0);
+            for k in 0..5 {
+                sum = sum + power.scale(&DualNumber::constant(1.0 / (2 * k + 1) as f64));
+                power = power * ratio.clone();
+            }
+            return Some(central_log(&alpha)? + z * alpha_inverse * sum);
+        }
+        let root = central_exp(&central_log(&lambda)?.scale(&DualNumber::constant(0.5)));
+        let plus = central_log(&(alpha.clone() + root.clone()))?;
+        let minus = central_log(&(alpha - root.clone()))?;
+        let half = DualNumber::constant(0.5);
+        Some(
+            (plus.clone() + minus.clone()).scale(&half)
+                + z * (plus - minus) * root.inverse()?.scale(&half),
+        )
+    }
+
+    /// `e^{log(A) / 2}`, None at zero where the square root has no derivative.
+    pub fn sqrt(&self) -> Option<Self> {
+        Some(self.log()?.scale(&DualNumber::constant(0.5)).exp())
+    }
+
+    /// `α`, `z` and `λ = z²`, keeping only the central part of `z z`.
+    fn split(&self) -> (Self, Self, Self) {
+        let alpha = central(&self.mvec[0], &self.mvec[7]);
+        let z = self.clone() - alpha.clone();
+        let square = z.clone() * z.clone();
+        (alpha, z, central(&square.mvec[0], &square.mvec[7]))
+    }
+}
+
+fn central(scalar: &DualNumber, pseudoscalar: &DualNumber) -> Multivector<DualNumber> {
+    Multivector::new(scalar.clone(), 0) + Multivector::new(pseudoscalar.clone(), 7)
+}
+
+/// `|c|` of a central `c`, without its derivative.
+fn size(c: &Multivector<DualNumber>) -> f64 {
+    c[0].value.hypot(c[7].value)
+}
+
+/// `e^{a + b e123} = e^a (cos b + e123 sin b)`.
+fn central_exp(c: &Multivector<DualNumber>) -> Multivector<DualNumber> {
+    let radius = c[0].exp();
+    central(&(radius.clone() * c[7].cos()), &(radius * c[7].sin()))
+}
+
+/// The principal logarithm `ln |c| + e123 arg c` of a central `c`, None at zero.
+fn central_log(c: &Multivector<DualNumber>) -> Option<Multivector<DualNumber>> {
+    let (a, b) = (&c[0], &c[7]);
+    let squared = a.clone() * a.clone() + b.clone() * b.clone();
+    (squared.value != 0.0)
+        .then(|| central(&(DualNumber::constant(0.5) * squared.ln()), &b.atan2(a)))
+}
+
+fn central_cosh_sinh(
+    c: &Multivector<DualNumber>,
+) -> (Multivector<DualNumber>, Multivector<DualNumber>) {
+    let half = DualNumber::constant(0.5);
+    let (plus, minus) = (central_exp(c), central_exp(&-c.clone()));
+    (
+        (plus.clone() + minus.clone()).scale(&half),
+        (plus - minus).scale(&half),
+    )
+}
+
+fn central_cos_sin(
+    c: &Multivector<DualNumber>,
+) -> (Multivector<DualNumber>, Multivector<DualNumber>) {
+    let i = Multivector::new(DualNumber::one(), 7);
+    let (cosh, sinh) = central_cosh_sinh(&(i.clone() * c.clone()));
+    (cosh, -(i * sinh))
+}
+
+/// `cosh √λ` and `sinh √λ / √λ` of a central `λ`. Both are power series in `λ`, summed directly
+/// near zero where `√λ` has no derivative.
+fn series(lambda: &Multivector<DualNumber>) -> (Multivector<DualNumber>, Multivector<DualNumber>) {
+    if size(lambda) < SERIES {
+        let (mut even, mut odd) = (Multivector::zero(), Multivector::zero());
+        let mut power = Multivector::new(DualNumber::one(), 0);
+        let mut factorial = 1.0;
+        for k in 0..5 {
+            even = even + power.scale(&DualNumber::constant(1.0 / factorial));
+            factorial *= (2 * k + 1) as f64;
+            odd = odd + power.scale(&DualNumber::constant(1.0 / factorial));
+            factorial *= (2 * k + 2) as f64;
+            power = power * lambda.clone();
+        }
+        return (even, odd);
+    }
+    // λ is not zero here, so neither is its square root.
+    let root = central_exp(
+        &central_log(lambda)
+            .unwrap()
+            .scale(&DualNumber::constant(0.5)),
+    );
+    let (cosh, sinh) = central_cosh_sinh(&root);
+    (cosh, sinh * root.inverse().unwrap())
+}
@@ -0,0 +1,306 @@
+use crate::convention::Convention;
+use crate::error::EvalError;
+use crate::expand::Expander;
+use crate::format::blade_latex;
+use crate::multivector::Multivector;
+use crate::r300::R300;
+use crate::scalar::Scalar;
+use crate::symbols::ReservedSymbols;
+use latex_expr_parser::ASTNode;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// A product of named variables with their powers, e.g. `a_{1}^{2} b_{3}`, sorted by name.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Monomial {
+    powers: Vec<(String, u32)>,
+}
+
+impl Monomial {
+    pub fn degree(&self) -> u32 {
+        self.powers.iter().map(|(_, power)| power).sum()
+    }
+
+    fn product(&self, other: &Monomial) -> Monomial {
+        let mut powers: BTreeMap<String, u32> = self.powers.iter().cloned().collect();
+        for (name, power) in &other.powers {
+            *powers.entry(name.clone()).or_insert(0) += power;
+        }
+        Monomial {
+            powers: powers.into_iter().collect(),
+        }
+    }
+
+    fn to_latex(&self) -> String {
+        self.powers
+            .iter()
+            .map(|(name, power)| match power {
+                1 => name.clone(),
+                _ => format!("{}^{{{}}}", name, power),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// A polynomial in the components of the expression's variables. Like terms are collected as
+/// they are produced, so cancellations such as `a_1 b_2 - b_2 a_1` disappear right away.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Polynomial {
+    terms: BTreeMap<Monomial, f64>,
+}
+
+impl Polynomial {
+    pub fn constant(value: f64) -> Self {
+        let mut res = Self::default();
+        res.insert(Monomial::default(), value);
+        res
+    }
+
+    pub fn variable(name: &str) -> Self {
+        let mut res = Self::default();
+        res.insert(
+            Monomial {
+                powers: vec![(name.to_string(), 1)],
+            },
+            1.0,
+        );
+        res
+    }
+
+    pub fn as_constant(&self) -> Option<f64> {
+        match self.terms.len() {
+            0 => Some(0.0),
+            1 => self.terms.get(&Monomial::default()).copied(),
+            _ => None,
+        }
+    }
+
+    /// Highest degree terms first, then by variable name.
+    pub fn to_latex(&self) -> String {
+        let mut terms = self.terms.iter().collect::<Vec<_>>();
+        terms.sort_by(|(a, _), (b, _)| b.degree().cmp(&a.degree()).then(a.cmp(b)));
+
+        let mut res = String::new();
+        for (i, (monomial, &coefficient)) in terms.into_iter().enumerate() {
+            let sign = match (i, coefficient < 0.0) {
+                (0, true) => "-",
+                (0, false) => "",
+                (_, true) => " - ",
+                (_, false) => " + ",
+            };
+            let magnitude = coefficient.abs();
+            let term = match (monomial.powers.is_empty(), magnitude == 1.0) {
+                (true, _) => number(magnitude),
+                (false, true) => monomial.to_latex(),
+                (false, false) => format!("{} {}", number(magnitude), monomial.to_latex()),
+            };
+            res.push_str(sign);
+            res.push_str(&term);
+        }
+        if res.is_empty() {
+            "0".to_string()
+        } else {
+            res
+        }
+    }
+
+    fn insert(&mut self, monomial: Monomial, coefficient: f64) {
+        let sum = self.terms.get(&monomial).copied().unwrap_or(0.0) + coefficient;
+        if sum == 0.0 {
+            self.terms.remove(&monomial);
+        } else {
+            self.terms.insert(monomial, sum);
+        }
+    }
+}
+
+fn number(value: f64) -> String {
+    let formatted = format!("{:.6}", value);
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+impl Add for Polynomial {
+    type Output = Polynomial;
+
+    fn add(mut self, b: Polynomial) -> Polynomial {
+        for (monomial, coefficient) in b.terms {
+            self.insert(monomial, coefficient);
+        }
+        self
+    }
+}
+
+impl Sub for Polynomial {
+    type Output = Polynomial;
+
+    fn sub(self, b: Polynomial) -> Polynomial {
+        self + (-b)
+    }
+}
+
+impl Neg for Polynomial {
+    type Output = Polynomial;
+
+    fn neg(mut self) -> Polynomial {
+        for coefficient in self.terms.values_mut() {
+            *coefficient = -*coefficient;
+        }
+        self
+    }
+}
+
+impl Mul for Polynomial {
+    type Output = Polynomial;
+
+    fn mul(self, b: Polynomial) -> Polynomial {
+        let mut res = Polynomial::default();
+        for (a_monomial, a_coefficient) in &self.terms {
+            for (b_monomial, b_coefficient) in &b.terms {
+                res.insert(
+                    a_monomial.product(b_monomial),
+                    a_coefficient * b_coefficient,
+                );
+            }
+        }
+        res
+    }
+}
+
+impl Scalar for Polynomial {
+    fn zero() -> Self {
+        Polynomial::default()
+    }
+
+    fn one() -> Self {
+        Polynomial::constant(1.0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    fn from_integer(value: i64) -> Self {
+        Polynomial::constant(value as f64)
+    }
+
+    fn to_integer(&self) -> Option<i64> {
+        Scalar::to_integer(&self.as_constant()?)
+    }
+
+    // Only constants can be divided by, `1 / a_1` is not a polynomial.
+    fn recip(&self) -> Option<Self> {
+        Scalar::recip(&self.as_constant()?).map(Polynomial::constant)
+    }
+}
+
+/// One basis blade of a symbolic result, e.g. `e12` with coefficient `a_{1} b_{2} - a_{2} b_{1}`.
+#[derive(Debug, Serialize)]
+pub struct SymbolicBlade {
+    pub blade: String,
+    pub latex: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SymbolicResult {
+    pub blades: Vec<SymbolicBlade>,
+    pub latex: String,
+}
+
+impl SymbolicResult {
+    /// `value` written in `convention`'s blades, `e31` having minus the coefficient of `e13`.
+    pub fn new(value: Multivector<Polynomial>, convention: &Convention) -> Self {
+        let nonzero = convention
+            .blades()
+            .iter()
+            .filter(|blade| !value[blade.index].is_zero())
+            .map(|blade| {
+                let coefficient = value[blade.index].clone();
+                let coefficient = if blade.sign < 0.0 {
+                    -coefficient
+                } else {
+                    coefficient
+                };
+                (blade, coefficient)
+            })
+            .collect::<Vec<_>>();
+        let blades = nonzero
+            .iter()
+            .map(|(blade, coefficient)| SymbolicBlade {
+                blade: blade.name.clone(),
+                latex: coefficient.to_latex(),
+            })
+            .collect::<Vec<_>>();
+        let latex = nonzero
+            .iter()
+            .map(|(blade, coefficient)| {
+                let latex = coefficient.to_latex();
+                let single_term = coefficient.terms.len() == 1 && !latex.starts_with('-');
+                match (blade.grade(), single_term) {
+                    (0, _) => latex,
+                    (_, true) if latex == "1" => blade_latex(blade),
+                    (_, true) => format!("{} {}", latex, blade_latex(blade)),
+                    (_, false) => format!("({}) {}", latex, blade_latex(blade)),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" + ");
+        SymbolicResult {
+            blades,
+            latex: if latex.is_empty() {
+                "0".to_string()
+            } else {
+                latex
+            },
+        }
+    }
+}
+
+/// Expands `ast` with every variable replaced by its named components, `a` becoming
+/// `a_{1} e_1 + a_{2} e_2 + a_{3} e_3`. Each variable keeps the grades it has in `vars`, or is a
+/// vector when missing. A top-level `=` expands to `left - right`, which is 0 for an identity.
+/// Components and the result are named by `convention`'s blades, so a bivector has `B_{31}`.
+pub fn calculate_symbolic(
+    ast: &ASTNode,
+    vars: &HashMap<String, R300>,
+    symbols: &ReservedSymbols,
+    convention: &Convention,
+) -> Result<SymbolicResult, EvalError> {
+    let identifier = |_: &ASTNode, name: &str| {
+        if let Some(value) = symbols.get(name) {
+            return Ok(Multivector::from_fn(|i| Polynomial::constant(value[i])));
+        }
+        let template = vars
+            .get(name)
+            .copied()
+            .filter(|value| *value != R300::zero())
+            .unwrap_or(R300::vector(1.0, 1.0, 1.0));
+        Ok(Multivector::from_fn(|i| {
+            let blade = convention.blade(i);
+            if template.grade_part(blade.grade()) == R300::zero() {
+                Polynomial::zero()
+            } else if i == 0 {
+                Polynomial::variable(name)
+            } else {
+                let component = Polynomial::variable(&format!("{}_{{{}}}", name, blade.indices()));
+                if blade.sign < 0.0 {
+                    -component
+                } else {
+                    component
+                }
+            }
+        }))
+    };
+    let expander = Expander {
+        identifier: &identifier,
+        unsupported: |start, end| EvalError::NotSymbolic { start, end },
+    };
+    Ok(SymbolicResult::new(
+        expander.expand_expression(ast)?,
+        convention,
+    ))
+}
@@ -0,0 +1,79 @@
+use crate::r300::{e1, e123, e2, e3, R300};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// Identifiers the evaluator resolves to fixed multivectors instead of user variables.
+///
+/// The default set covers the basis blades written as `e_1`, `e_{12}`, `e_{123}` (any index
+/// order, so `e_{31}` is `-e13`), the Pauli style `\sigma_1`..`\sigma_3`, Hestenes' unit
+/// pseudoscalar `I` / `i`, and Euler's number `e`, which makes `e^{x}` the exponential of `x`.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReservedSymbols {
+    symbols: HashMap<String, R300>,
+}
+
+#[wasm_bindgen]
+impl ReservedSymbols {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        let mut symbols = Self::empty();
+        let vectors = [e1, e2, e3];
+
+        for i in 0..3 {
+            symbols.insert(&format!("e_{}", i + 1), vectors[i]);
+            symbols.insert(&format!("\\sigma_{}", i + 1), vectors[i]);
+            for j in (0..3).filter(|&j| j != i) {
+                symbols.insert(&format!("e_{}{}", i + 1, j + 1), vectors[i] * vectors[j]);
+                for k in (0..3).filter(|&k| k != i && k != j) {
+                    symbols.insert(
+                        &format!("e_{}{}{}", i + 1, j + 1, k + 1),
+                        vectors[i] * vectors[j] * vectors[k],
+                    );
+                }
+            }
+        }
+        symbols.insert("I", e123);
+        symbols.insert("i", e123);
+        symbols.insert("e", EULER);
+        symbols
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            symbols: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, name: &str, value: R300) {
+        self.symbols.insert(normalize_symbol(name), value);
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.symbols.remove(&normalize_symbol(name)).is_some()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.symbols.contains_key(&normalize_symbol(name))
+    }
+
+    pub fn get(&self, name: &str) -> Option<R300> {
+        self.symbols.get(&normalize_symbol(name)).copied()
+    }
+}
+
+impl Default for ReservedSymbols {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub const EULER: R300 = R300::new(std::f64::consts::E, 0);
+
+/// `e_{12}`, `e_12` and `e_{1 2}` all name the same blade, so braces and whitespace are
+/// dropped before lookup. Multivector literals read blade indices the same way.
+pub(crate) fn normalize_symbol(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace() && *c != '{' && *c != '}')
+        .collect()
+}